@@ -31,4 +31,201 @@ light-has-no-state = Light {$name} has no state
 
 # Scene formatting
 global = Global
-scene-name-group-name = {$name} - {$group_name}
+show-hidden-scenes = Show hidden scenes
+hide-hidden-scenes = Hide hidden scenes
+
+# Scene recall options
+scene-transition = Fade duration
+scene-brightness = Brightness
+seconds = {$seconds} s
+
+# Schedules
+schedules = Schedules
+schedule = Schedule
+no-schedules-found = No schedules found
+schedule-name = Name
+schedule-kind-recurring = Recurring
+schedule-kind-alarm = Alarm
+schedule-kind-timer = Timer
+schedule-time = Time (hh:mm)
+schedule-date = Date (yyyy-mm-dd)
+schedule-minutes = Duration
+minutes = minutes
+schedule-recurring = {$days} at {$time}
+schedule-alarm = On {$date_time}
+schedule-timer = Timer of {$minutes} min
+schedule-unsupported = Custom schedule
+light = Light
+group = Group
+scene = Scene
+turn-on-off = Turn on
+turn-on = Turn on {$name}
+turn-off = Turn off {$name}
+invalid-time = Invalid time
+invalid-date = Invalid date
+invalid-duration = Invalid duration
+no-weekday-selected = No weekday selected
+no-target-selected = Nothing selected
+cancel = Cancel
+save = Save
+monday-short = Mon
+tuesday-short = Tue
+wednesday-short = Wed
+thursday-short = Thu
+friday-short = Fri
+saturday-short = Sat
+sunday-short = Sun
+
+# Sleep timer
+sleep-timer = Sleep timer
+turn-off-in = Turn off in
+turns-off-in = Off in {$remaining}
+minutes-short = {$minutes} min
+
+# Wake-up alarms
+wake-ups = Wake-up
+wake-up = Wake-up
+no-wake-ups-found = No wake-up alarms found
+wake-up-duration = Sunrise duration
+wake-up-description = {$days} at {$time} · {$minutes} min sunrise in {$group_name}
+
+# Automations
+automations = Automations
+automation = Automation
+no-automations-found = No automations found
+latitude = Latitude
+longitude = Longitude
+invalid-location = Invalid latitude or longitude
+location-required = Set a location to run the automations
+sun-times = Sunrise at {$sunrise} · Sunset at {$sunset}
+no-sun-times = No sunrise or sunset today at this location
+sunrise = Sunrise
+sunset = Sunset
+at-sunrise = At sunrise
+before-sunrise = {$minutes} min before sunrise
+after-sunrise = {$minutes} min after sunrise
+at-sunset = At sunset
+before-sunset = {$minutes} min before sunset
+after-sunset = {$minutes} min after sunset
+
+# Adaptive lighting
+adaptive-lighting = Adaptive lighting
+adaptive-brightness = Adapt the brightness
+
+# Session lock
+when-locked = When the session is locked or suspended
+do-nothing = Do nothing
+
+# Idle dimming
+dim-when-idle = Dim when I'm away
+idle-after = After
+
+# Notification alerts
+notification-alerts = Notification alerts
+no-notification-alerts = No light alerted on notifications
+notification-alert-description = {$app_name}, {$urgency} and above: {$light_name}
+any-app = Any application
+minimum-urgency = Minimum urgency
+urgency-low = Low
+urgency-normal = Normal
+urgency-critical = Critical
+breathe = Breathe
+red = Red
+orange = Orange
+green = Green
+blue = Blue
+
+# Busy light
+busy-light = Busy light
+busy-with = Busy: {$summary}
+busy = Busy
+available = Available
+calendar-file = Calendar file
+calendar-file-description = Path of a local .ics file, such as a CalDAV export. Leave empty to only set the busy state by hand.
+no-calendar = No calendar, busy state set by hand
+no-busy-light = No busy light
+remove = Remove
+
+# Focus timer
+focus-timer = Focus timer
+focus-work = Work
+focus-break = Break
+focus-minutes = {$minutes} min
+focus-sessions = Sessions today: {$today}, total: {$total}
+focus-signal-color = Color
+focus-signal-pulse = Pulse
+
+# Keyboard shortcuts
+keyboard-shortcuts = Keyboard shortcuts
+default-group = Default group
+brightness-step = Brightness step
+color-temperature-step = Color temperature step
+percent = {$percent}%
+mireds = {$mireds} mireds
+keyboard-shortcuts-description = Add these commands as custom shortcuts in the keyboard settings of COSMIC. They change the default group, or the group given after the command.
+
+panel-icon-description = Scroll over the panel icon to change the brightness of the default group, middle-click it to turn the group on or off.
+panel-level-off = Off
+
+tint-panel-icon = Tint the panel icon with the color of the brightest light of the default group
+
+pin-to-top = Pin to the top
+show-pins-in-panel = Show the pins in the panel
+
+settings = COSMIC Hue settings
+open-settings = Settings…
+bridge-settings = Bridge
+discover-bridge = Search again
+invalid-bridge-ip = Invalid address
+bridge-paired = Paired
+general = General
+refresh-interval = Refresh interval
+debounce-delay = Slider delay
+debounce-delay-description = The changes made with the sliders are sent to the bridge once they stop moving for this delay.
+milliseconds = {$milliseconds} ms
+visible-sections = Sections of the popup
+visible-items = Lights, groups and scenes shown
+diagnostics = Diagnostics
+applet-version = Applet version
+settings-version = Settings version
+bridge-reachable = Bridge reachable
+last-refresh = Last refresh
+dbus-service = D-Bus service
+none = None
+yes = Yes
+no = No
+
+edit-items = Rename, hide and reorder
+finish-editing = Done
+show-item = Show
+hide-item = Hide
+
+search-placeholder = Search lights, groups and scenes
+no-search-results = Nothing matches the search
+search-keyword-on = on
+search-keyword-off = off
+search-keyword-unreachable = unreachable
+
+collapse-section = Collapse
+expand-section = Expand
+more-options = More options
+cancel-sleep-timer = Cancel the sleep timer
+skip-focus-phase = Skip to the next phase
+stop-focus-timer = Stop the focus timer
+start-focus-timer = Start the focus timer
+edit = Edit
+delete = Delete
+add = Add
+scene-options = Scene options
+activate-scene = Activate the scene
+decrease = Decrease
+increase = Increase
+
+rename-light = Rename on the bridge
+save-light-name = Save the name
+light-name-empty = The name can't be empty
+light-alias-kept = Still shown as “{$alias}” on this computer
+
+idle-source-unavailable = Neither the compositor nor logind report when you are away, the lights won't be dimmed
+
+no-bridge-at-address = No Hue bridge answers at this address
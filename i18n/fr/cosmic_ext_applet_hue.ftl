@@ -31,4 +31,201 @@ light-has-no-state = La lumière {$name} n'a pas d'état
 
 # Formatage des scènes
 global = Global
-scene-name-group-name = {$name} - {$group_name}
+show-hidden-scenes = Afficher les scènes masquées
+hide-hidden-scenes = Masquer les scènes masquées
+
+# Options de rappel des scènes
+scene-transition = Durée du fondu
+scene-brightness = Luminosité
+seconds = {$seconds} s
+
+# Programmations
+schedules = Programmations
+schedule = Programmation
+no-schedules-found = Aucune programmation trouvée
+schedule-name = Nom
+schedule-kind-recurring = Récurrente
+schedule-kind-alarm = Alarme
+schedule-kind-timer = Minuteur
+schedule-time = Heure (hh:mm)
+schedule-date = Date (aaaa-mm-jj)
+schedule-minutes = Durée
+minutes = minutes
+schedule-recurring = {$days} à {$time}
+schedule-alarm = Le {$date_time}
+schedule-timer = Minuteur de {$minutes} min
+schedule-unsupported = Programmation personnalisée
+light = Lumière
+group = Groupe
+scene = Scène
+turn-on-off = Allumer
+turn-on = Allumer {$name}
+turn-off = Éteindre {$name}
+invalid-time = Heure invalide
+invalid-date = Date invalide
+invalid-duration = Durée invalide
+no-weekday-selected = Aucun jour sélectionné
+no-target-selected = Aucune sélection
+cancel = Annuler
+save = Enregistrer
+monday-short = Lun
+tuesday-short = Mar
+wednesday-short = Mer
+thursday-short = Jeu
+friday-short = Ven
+saturday-short = Sam
+sunday-short = Dim
+
+# Minuteur d'extinction
+sleep-timer = Minuteur d'extinction
+turn-off-in = Éteindre dans
+turns-off-in = Extinction dans {$remaining}
+minutes-short = {$minutes} min
+
+# Réveils lumineux
+wake-ups = Réveil
+wake-up = Réveil
+no-wake-ups-found = Aucun réveil lumineux trouvé
+wake-up-duration = Durée du lever de soleil
+wake-up-description = {$days} à {$time} · lever de soleil de {$minutes} min dans {$group_name}
+
+# Automatisations
+automations = Automatisations
+automation = Automatisation
+no-automations-found = Aucune automatisation trouvée
+latitude = Latitude
+longitude = Longitude
+invalid-location = Latitude ou longitude invalide
+location-required = Définissez une position pour lancer les automatisations
+sun-times = Lever du soleil à {$sunrise} · Coucher du soleil à {$sunset}
+no-sun-times = Pas de lever ni de coucher du soleil aujourd'hui à cette position
+sunrise = Lever du soleil
+sunset = Coucher du soleil
+at-sunrise = Au lever du soleil
+before-sunrise = {$minutes} min avant le lever du soleil
+after-sunrise = {$minutes} min après le lever du soleil
+at-sunset = Au coucher du soleil
+before-sunset = {$minutes} min avant le coucher du soleil
+after-sunset = {$minutes} min après le coucher du soleil
+
+# Éclairage adaptatif
+adaptive-lighting = Éclairage adaptatif
+adaptive-brightness = Adapter la luminosité
+
+# Verrouillage de la session
+when-locked = Quand la session est verrouillée ou en veille
+do-nothing = Ne rien faire
+
+# Atténuation en cas d'inactivité
+dim-when-idle = Atténuer en mon absence
+idle-after = Après
+
+# Alertes de notification
+notification-alerts = Alertes de notification
+no-notification-alerts = Aucune lumière alertée lors des notifications
+notification-alert-description = {$app_name}, {$urgency} et plus : {$light_name}
+any-app = Toute application
+minimum-urgency = Urgence minimale
+urgency-low = Basse
+urgency-normal = Normale
+urgency-critical = Critique
+breathe = Respiration
+red = Rouge
+orange = Orange
+green = Vert
+blue = Bleu
+
+# Lumière occupé
+busy-light = Lumière occupé
+busy-with = Occupé : {$summary}
+busy = Occupé
+available = Disponible
+calendar-file = Fichier de calendrier
+calendar-file-description = Chemin d'un fichier .ics local, comme un export CalDAV. Laisser vide pour choisir l'état occupé uniquement à la main.
+no-calendar = Pas de calendrier, état occupé choisi à la main
+no-busy-light = Aucune lumière occupé
+remove = Supprimer
+
+# Minuteur de concentration
+focus-timer = Minuteur de concentration
+focus-work = Travail
+focus-break = Pause
+focus-minutes = {$minutes} min
+focus-sessions = Sessions aujourd'hui : {$today}, au total : {$total}
+focus-signal-color = Couleur
+focus-signal-pulse = Pulsation
+
+# Raccourcis clavier
+keyboard-shortcuts = Raccourcis clavier
+default-group = Groupe par défaut
+brightness-step = Pas de luminosité
+color-temperature-step = Pas de température de couleur
+percent = {$percent} %
+mireds = {$mireds} mireds
+keyboard-shortcuts-description = Ajoutez ces commandes comme raccourcis personnalisés dans les paramètres du clavier de COSMIC. Elles modifient le groupe par défaut, ou le groupe donné après la commande.
+
+panel-icon-description = Faites défiler sur l'icône du panneau pour changer la luminosité du groupe par défaut, cliquez dessus avec le bouton du milieu pour allumer ou éteindre le groupe.
+panel-level-off = Éteint
+
+tint-panel-icon = Teinter l'icône du panneau de la couleur de la lampe la plus lumineuse du groupe par défaut
+
+pin-to-top = Épingler en haut
+show-pins-in-panel = Afficher les épingles dans le panneau
+
+settings = Paramètres de COSMIC Hue
+open-settings = Paramètres…
+bridge-settings = Pont
+discover-bridge = Rechercher à nouveau
+invalid-bridge-ip = Adresse invalide
+bridge-paired = Appairé
+general = Général
+refresh-interval = Intervalle de rafraîchissement
+debounce-delay = Délai des curseurs
+debounce-delay-description = Les changements faits avec les curseurs sont envoyés au pont une fois qu'ils ne bougent plus pendant ce délai.
+milliseconds = {$milliseconds} ms
+visible-sections = Sections de la fenêtre
+visible-items = Lampes, groupes et scènes affichés
+diagnostics = Diagnostic
+applet-version = Version de l'applet
+settings-version = Version des paramètres
+bridge-reachable = Pont joignable
+last-refresh = Dernier rafraîchissement
+dbus-service = Service D-Bus
+none = Aucun
+yes = Oui
+no = Non
+
+edit-items = Renommer, masquer et réordonner
+finish-editing = Terminé
+show-item = Afficher
+hide-item = Masquer
+
+search-placeholder = Rechercher des lampes, groupes et scènes
+no-search-results = Aucun résultat
+search-keyword-on = allumé
+search-keyword-off = éteint
+search-keyword-unreachable = injoignable
+
+collapse-section = Replier
+expand-section = Déplier
+more-options = Plus d'options
+cancel-sleep-timer = Annuler la minuterie
+skip-focus-phase = Passer à la phase suivante
+stop-focus-timer = Arrêter le minuteur de concentration
+start-focus-timer = Démarrer le minuteur de concentration
+edit = Modifier
+delete = Supprimer
+add = Ajouter
+scene-options = Options de la scène
+activate-scene = Activer la scène
+decrease = Diminuer
+increase = Augmenter
+
+rename-light = Renommer sur le pont
+save-light-name = Enregistrer le nom
+light-name-empty = Le nom ne peut pas être vide
+light-alias-kept = Toujours affichée « {$alias} » sur cet ordinateur
+
+idle-source-unavailable = Ni le compositeur ni logind n'indiquent quand vous êtes absent, les lampes ne seront pas atténuées
+
+no-bridge-at-address = Aucun pont Hue ne répond à cette adresse
@@ -0,0 +1,137 @@
+// SPDX-License-Identifier: MIT
+
+//! Day curve followed by the adaptive lighting: a cool and bright light during the day, a
+//! warm and dim light in the evening and at night.
+
+use chrono::{DateTime, Local, NaiveTime, Timelike};
+
+use crate::sun;
+
+/// Color temperature at night, in mireds (2500K).
+const WARM_COLOR_TEMPERATURE: u16 = 400;
+
+/// Color temperature during the day, in mireds (5000K).
+const COOL_COLOR_TEMPERATURE: u16 = 200;
+
+/// Brightness at night, about 40%.
+const MIN_BRIGHTNESS: u8 = 102;
+
+/// Brightness during the day.
+const MAX_BRIGHTNESS: u8 = 254;
+
+/// Sunrise and sunset used when the location is unknown or during the polar day and night,
+/// in minutes since midnight.
+const DEFAULT_SUNRISE_MINUTES: f64 = 7.0 * 60.0;
+const DEFAULT_SUNSET_MINUTES: f64 = 19.0 * 60.0;
+
+/// The morning transition runs from 30 minutes before sunrise to 90 minutes after it.
+const MORNING_TRANSITION: (f64, f64) = (-30.0, 90.0);
+
+/// The evening transition runs from 2 hours before sunset to 30 minutes after it.
+const EVENING_TRANSITION: (f64, f64) = (-120.0, 30.0);
+
+/// The state of the lights at a given time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdaptiveState {
+    /// Color temperature, in mireds.
+    pub color_temperature: u16,
+    pub brightness: u8,
+}
+
+/// Compute the state of the lights at a time, following the sun when the location is known.
+pub fn adaptive_state(now: DateTime<Local>, location: Option<(f64, f64)>) -> AdaptiveState {
+    let (sunrise, sunset) = location
+        .and_then(|(latitude, longitude)| sun::sun_times(now.date_naive(), latitude, longitude))
+        .map(|sun_times| {
+            (
+                minutes_since_midnight(sun_times.sunrise.with_timezone(&Local).time()),
+                minutes_since_midnight(sun_times.sunset.with_timezone(&Local).time()),
+            )
+        })
+        .unwrap_or((DEFAULT_SUNRISE_MINUTES, DEFAULT_SUNSET_MINUTES));
+
+    let minutes = minutes_since_midnight(now.time());
+    let daylight = if minutes < sunrise + MORNING_TRANSITION.1 {
+        ease(minutes, sunrise + MORNING_TRANSITION.0, sunrise + MORNING_TRANSITION.1)
+    } else {
+        1.0 - ease(minutes, sunset + EVENING_TRANSITION.0, sunset + EVENING_TRANSITION.1)
+    };
+
+    AdaptiveState {
+        color_temperature: lerp(WARM_COLOR_TEMPERATURE, COOL_COLOR_TEMPERATURE, daylight),
+        brightness: lerp(MIN_BRIGHTNESS.into(), MAX_BRIGHTNESS.into(), daylight) as u8,
+    }
+}
+
+fn minutes_since_midnight(time: NaiveTime) -> f64 {
+    time.num_seconds_from_midnight() as f64 / 60.0
+}
+
+/// Progress from 0 to 1 between two times, with a smooth start and end.
+fn ease(minutes: f64, start: f64, end: f64) -> f64 {
+    let progress = ((minutes - start) / (end - start)).clamp(0.0, 1.0);
+    (1.0 - (progress * std::f64::consts::PI).cos()) / 2.0
+}
+
+fn lerp(from: u16, to: u16, progress: f64) -> u16 {
+    (from as f64 + (to as f64 - from as f64) * progress).round() as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 6, 21, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn bright_and_cool_at_noon() {
+        let state = adaptive_state(at(12, 0), None);
+        assert_eq!(state.color_temperature, COOL_COLOR_TEMPERATURE);
+        assert_eq!(state.brightness, MAX_BRIGHTNESS);
+    }
+
+    #[test]
+    fn dim_and_warm_at_night() {
+        for (hour, minute) in [(0, 0), (3, 0), (23, 0)] {
+            let state = adaptive_state(at(hour, minute), None);
+            assert_eq!(state.color_temperature, WARM_COLOR_TEMPERATURE);
+            assert_eq!(state.brightness, MIN_BRIGHTNESS);
+        }
+    }
+
+    #[test]
+    fn halfway_through_the_morning_transition() {
+        // The transition runs from 06:30 to 08:30 around the default sunrise at 07:00
+        let state = adaptive_state(at(7, 30), None);
+        assert_eq!(state.color_temperature, 300);
+        assert_eq!(state.brightness, 178);
+    }
+
+    #[test]
+    fn halfway_through_the_evening_transition() {
+        // The transition runs from 17:00 to 19:30 around the default sunset at 19:00
+        let state = adaptive_state(at(18, 15), None);
+        assert_eq!(state.color_temperature, 300);
+        assert_eq!(state.brightness, 178);
+    }
+
+    #[test]
+    fn ease_endpoints() {
+        assert_eq!(ease(10.0, 10.0, 20.0), 0.0);
+        assert_eq!(ease(20.0, 10.0, 20.0), 1.0);
+        assert!((ease(15.0, 10.0, 20.0) - 0.5).abs() < 1e-9);
+        // Outside of the transition
+        assert_eq!(ease(0.0, 10.0, 20.0), 0.0);
+        assert_eq!(ease(30.0, 10.0, 20.0), 1.0);
+    }
+
+    #[test]
+    fn lerp_both_ways() {
+        assert_eq!(lerp(400, 200, 0.0), 400);
+        assert_eq!(lerp(400, 200, 1.0), 200);
+        assert_eq!(lerp(102, 254, 0.25), 140);
+    }
+}
@@ -1,10 +1,34 @@
 // SPDX-License-Identifier: MIT
 
-use crate::config::Config;
+use crate::adaptive;
+use crate::busy;
+use crate::calendar;
+use crate::config::{
+    self, get_bridge, AdaptiveOptions, AutomationAction, AutomationRule, BusyLightOptions, Config,
+    FocusSignal, IdleDimOptions, ItemRef, LockAction, NotificationAlert, NotificationRule,
+    SceneRecallOptions, Section, SunEvent, Urgency, MAX_PINS, MAX_RECENT_SCENES,
+};
 use crate::fl;
+use crate::focus::{self, FocusPhase};
+use crate::idle;
+use crate::idle_notify::{self, IdleEvent};
+use crate::lock;
+use crate::logind::{self, LogindEvent};
+use crate::notifications::{self, Notification};
+use crate::panel_icon::PanelIconState;
+use crate::scene;
+use crate::schedule::{self, ScheduleTarget, ScheduleTime};
+use crate::service::{self, ServiceChange, ServiceHandle};
+use crate::snapshot::Snapshot;
+use crate::sun;
+use crate::wake_up::{self, WakeUp};
 use cosmic::cctk::wayland_protocols::xdg::shell::client::xdg_positioner::Anchor;
 use cosmic::cctk::wayland_protocols::xdg::shell::client::xdg_positioner::Gravity;
+use cosmic::applet::Size;
+use cosmic::applet::cosmic_panel_config::{PanelAnchor, PanelSize};
 use cosmic::cosmic_config::{self, CosmicConfigEntry};
+use cosmic::iced::keyboard::{self, key::Named, Key};
+use cosmic::iced::mouse::ScrollDelta;
 use cosmic::iced::{Alignment, Length, Rectangle, Subscription};
 use cosmic::iced::{Limits, window::Id};
 use cosmic::iced_winit::commands::popup::{destroy_popup, get_popup};
@@ -15,10 +39,13 @@ use cosmic::widget::{self, RectangleTracker, rectangle_tracker};
 use cosmic::{Action, Task};
 use cosmic::{iced_core, prelude::*};
 use huelib;
+use huelib::resource::light::ColorMode;
 use huelib::response::{Modified, Response};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::net::IpAddr;
 use std::net::Ipv4Addr;
+use std::path::Path;
+use std::time::{Duration, Instant};
 
 /// The application model stores app-specific state used to describe its interface and
 /// drive its logic.
@@ -35,8 +62,12 @@ pub struct AppModel {
     last_discovery: Option<Result<IpAddr, String>>,
     /// The lights.
     lights: Vec<LightVm>,
+    /// The names of the lights, in the same order, for the dropdowns.
+    light_labels: Vec<String>,
     /// The groups.
     groups: Vec<GroupVm>,
+    /// The names of the groups, in the same order, for the dropdowns.
+    group_labels: Vec<String>,
     /// The scenes.
     scenes: Vec<SceneVm>,
     /// Lights menu expanded.
@@ -45,10 +76,85 @@ pub struct AppModel {
     groups_menu_expanded: bool,
     /// Scenes menu expanded.
     scenes_menu_expanded: bool,
+    /// The schedules.
+    schedules: Vec<ScheduleVm>,
+    /// Schedules menu expanded.
+    schedules_menu_expanded: bool,
+    /// The schedule being created or edited.
+    schedule_editor: Option<ScheduleEditor>,
+    /// The wake-up alarms.
+    wake_ups: Vec<WakeUp>,
+    /// Wake-up alarms menu expanded.
+    wake_ups_menu_expanded: bool,
+    /// The wake-up alarm being created or edited.
+    wake_up_editor: Option<WakeUpEditor>,
+    /// Automation rules menu expanded.
+    automations_menu_expanded: bool,
+    /// The automation rule being created or edited.
+    automation_editor: Option<AutomationEditor>,
+    /// Focus timer menu expanded.
+    focus_menu_expanded: bool,
+    /// The running focus timer.
+    focus_timer: Option<FocusTimer>,
+    /// The state of the focus light before the timer changed its color.
+    focus_snapshot: Option<Snapshot>,
+    /// Latitude typed in the automations section.
+    latitude_input: String,
+    /// Longitude typed in the automations section.
+    longitude_input: String,
+    location_error: Option<String>,
+    /// Last time the automation rules were checked.
+    last_automation_check: chrono::DateTime<chrono::Local>,
+    /// The lights, groups and scenes can be renamed, hidden and reordered.
+    edit_mode: bool,
+    /// The light, group or scene being dragged to a new place.
+    dragged_item: Option<ItemRef>,
+    /// Pending alias changes (item, alias, counter)
+    pending_aliases: HashMap<ItemRef, (String, u64)>,
+    /// Text typed in the search field of the popup.
+    search_query: String,
+    /// The lights, groups and scenes matching the search, kept until the search or the data changes.
+    search_results: Vec<ItemRef>,
+    /// The light, group or scene chosen with the keyboard.
+    selected_item: Option<ItemRef>,
+    /// The light being renamed on the bridge, with the name typed (light_id, name).
+    renaming_light: Option<(String, String)>,
+    light_name_error: Option<String>,
+    /// The light or group whose menu is open (item_id, item_type).
+    row_menu: Option<(String, String)>,
+    /// Running sleep timers, by (item_id, item_type).
+    sleep_timers: HashMap<(String, String), SleepTimer>,
+    /// Show the recyclable and hidden scenes.
+    show_hidden_scenes: bool,
+    /// The scene whose recall options are displayed.
+    scene_options_expanded: Option<String>,
+    /// Cached colors of the light states stored in each scene, by scene id.
+    scene_palettes: HashMap<String, Vec<(f32, f32, f32)>>,
     /// The color picker popup id.
     color_picker_popup: Option<Id>,
     /// The more menu popup id.
     more_menu_popup: Option<Id>,
+    /// The settings window id.
+    settings_window: Option<Id>,
+    /// Bridge address typed in the settings.
+    bridge_ip_input: String,
+    bridge_ip_error: Option<String>,
+    /// Last refresh of the lights, with its error.
+    last_refresh: Option<(chrono::DateTime<chrono::Local>, Option<String>)>,
+    /// What the panel icon shows.
+    panel_icon: PanelIconState,
+    /// The panel icon drawn from its state.
+    panel_icon_handle: icon::Handle,
+    /// The popup showing the level of the default group after a scroll or a middle-click.
+    level_popup: Option<Id>,
+    /// Last known level of the default group.
+    panel_level: Option<PanelLevel>,
+    /// Scroll over the panel icon not yet turned into brightness steps.
+    panel_scroll: f32,
+    /// Pending brightness steps of the default group (steps, counter)
+    pending_panel_steps: Option<(i32, u64)>,
+    /// Counter to hide the level popup once the last change is shown.
+    level_counter: u64,
     /// Active color picker item id.
     active_color_picker_item: Option<(String, String)>,
     /// Last active color picker item id.
@@ -67,21 +173,65 @@ pub struct AppModel {
     pending_light_color: HashMap<String, ((u16, u8, u8), u64)>,
     /// Pending group color changes (group_id, (hue, saturation, value), counter)
     pending_group_color: HashMap<String, ((u16, u8, u8), u64)>,
+    /// Pending scene recall options changes (scene_id, options, counter)
+    pending_scene_options: HashMap<String, (SceneRecallOptions, u64)>,
+    /// Pending idle dimming options change ((options, counter))
+    pending_idle_dim: Option<(Option<IdleDimOptions>, u64)>,
+    /// Pending color temperature (and brightness) changes for lights (light_id -> (values, counter))
+    pending_light_color_temperature: HashMap<String, ((u16, Option<u8>), u64)>,
+    /// Whether the session is locked.
+    session_locked: bool,
+    /// Whether the computer is about to suspend.
+    session_sleeping: bool,
+    /// The state of the lights before the session was locked or suspended.
+    lock_snapshot: Option<Snapshot>,
+    /// Whether the idle group is dimmed, or being dimmed.
+    idle_dimmed: bool,
+    /// The state of the lights before the idle group was dimmed.
+    idle_snapshot: Option<Snapshot>,
+    /// Whether neither the compositor nor logind report when the user is away.
+    idle_unavailable: bool,
+    /// The notification rule being created or edited.
+    notification_rule_editor: Option<NotificationRuleEditor>,
+    /// Lights being alerted for a notification.
+    alerting_lights: HashSet<String>,
+    /// The busy light settings being edited.
+    busy_light_editor: Option<BusyLightEditor>,
+    /// The occurrence of the calendar event in progress.
+    calendar_event: Option<calendar::Occurrence>,
+    /// The D-Bus service, once served.
+    service: Option<ServiceHandle>,
+    /// Busy or available set by hand, until the calendar changes.
+    busy_override: Option<bool>,
+    /// Whether the busy light is red, or being turned red.
+    busy_light_on: bool,
+    /// The state of the busy light before turning it red.
+    busy_snapshot: Option<Snapshot>,
+    /// Lights changed by hand, left alone by the adaptive lighting until turned off.
+    adaptive_overrides: HashSet<String>,
+    /// Last color temperature and brightness set by the adaptive lighting, by light id.
+    adaptive_applied: HashMap<String, (u16, Option<u8>)>,
     /// Counter for debounce operations
     debounce_counter: u64,
 }
 
 pub struct LightVm {
     id: String,
+    /// The local alias, or the name given on the bridge.
     name: String,
+    bridge_name: String,
     on: Option<bool>,
     brightness: Option<u8>,
     color: Option<(f32, f32, f32)>,
+    /// Lights switched off at the wall can't be reached by the bridge.
+    reachable: bool,
 }
 
 pub struct GroupVm {
     id: String,
+    /// The local alias, or the name given on the bridge.
     name: String,
+    bridge_name: String,
     on: Option<bool>,
     brightness: Option<u8>,
     color: Option<(f32, f32, f32)>,
@@ -90,8 +240,256 @@ pub struct GroupVm {
 
 pub struct SceneVm {
     id: String,
+    /// The local alias, or the name given on the bridge.
     name: String,
+    bridge_name: String,
     group: String,
+    /// Recyclable scenes and light scenes created by other apps.
+    hidden: bool,
+    /// Last change of the scene on the bridge.
+    last_updated: Option<chrono::NaiveDateTime>,
+}
+
+/// A pending command turning off a light or a group.
+pub struct SleepTimer {
+    ends_at: Instant,
+    /// The timer schedule of the bridge, `None` when the applet turns the item off itself.
+    schedule_id: Option<String>,
+}
+
+pub struct ScheduleVm {
+    id: String,
+    name: String,
+    enabled: bool,
+    /// `None` when the time pattern can't be edited.
+    time: Option<ScheduleTime>,
+    /// The randomized part of the time, like `A00:30:00`.
+    randomization: Option<String>,
+    /// `None` when the command was not created by this applet.
+    target: Option<ScheduleTarget>,
+}
+
+/// The form used to create or edit a schedule.
+#[derive(Debug, Clone)]
+pub struct ScheduleEditor {
+    /// The edited schedule, `None` when creating a new one.
+    id: Option<String>,
+    name: String,
+    kind: ScheduleKind,
+    weekdays: u8,
+    /// Time of day, as `hh:mm`.
+    time: String,
+    /// Date of an alarm, as `yyyy-mm-dd`.
+    date: String,
+    /// Duration of a timer, in minutes.
+    timer_minutes: String,
+    target_kind: ScheduleTargetKind,
+    /// Ids and labels of the lights, groups or scenes which can be targeted.
+    targets: Vec<(String, String)>,
+    target_labels: Vec<String>,
+    target: Option<usize>,
+    on: bool,
+    /// The randomized part of the edited time, kept when saving.
+    randomization: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleKind {
+    Recurring,
+    Alarm,
+    Timer,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleTargetKind {
+    Light,
+    Group,
+    Scene,
+}
+
+impl ScheduleEditor {
+    /// Read the time of the schedule from the form.
+    fn schedule_time(&self) -> Result<ScheduleTime, String> {
+        let time = || {
+            chrono::NaiveTime::parse_from_str(self.time.trim(), "%H:%M")
+                .map_err(|_| fl!("invalid-time"))
+        };
+        match self.kind {
+            ScheduleKind::Recurring if self.weekdays == 0 => Err(fl!("no-weekday-selected")),
+            ScheduleKind::Recurring => Ok(ScheduleTime::Recurring {
+                weekdays: self.weekdays,
+                time: time()?,
+            }),
+            ScheduleKind::Alarm => {
+                let date = chrono::NaiveDate::parse_from_str(self.date.trim(), "%Y-%m-%d")
+                    .map_err(|_| fl!("invalid-date"))?;
+                Ok(ScheduleTime::Alarm(date.and_time(time()?)))
+            }
+            ScheduleKind::Timer => match self.timer_minutes.trim().parse::<u32>() {
+                Ok(minutes) if minutes > 0 => Ok(ScheduleTime::Timer(minutes * 60)),
+                _ => Err(fl!("invalid-duration")),
+            },
+        }
+    }
+
+    /// Read the target of the schedule from the form.
+    fn schedule_target(&self, scenes: &[SceneVm]) -> Result<ScheduleTarget, String> {
+        let Some((id, _)) = self.target.and_then(|index| self.targets.get(index)) else {
+            return Err(fl!("no-target-selected"));
+        };
+        let id = id.clone();
+        match self.target_kind {
+            ScheduleTargetKind::Light => Ok(ScheduleTarget::Light { id, on: self.on }),
+            ScheduleTargetKind::Group => Ok(ScheduleTarget::Group { id, on: self.on }),
+            ScheduleTargetKind::Scene => {
+                let group_id = scenes
+                    .iter()
+                    .find(|scene| scene.id == id)
+                    .map(|scene| scene.group.clone())
+                    .filter(|group_id| !group_id.is_empty())
+                    .unwrap_or_else(|| "0".to_string());
+                Ok(ScheduleTarget::Scene {
+                    group_id,
+                    scene_id: id,
+                })
+            }
+        }
+    }
+}
+
+/// The form used to create or edit a wake-up alarm.
+#[derive(Debug, Clone)]
+pub struct WakeUpEditor {
+    /// The edited alarm, `None` when creating a new one.
+    wake_up: Option<WakeUp>,
+    name: String,
+    /// Ids and names of the groups which can be woken up.
+    groups: Vec<(String, String)>,
+    group_labels: Vec<String>,
+    group: Option<usize>,
+    weekdays: u8,
+    /// Time of day, as `hh:mm`.
+    time: String,
+    duration_minutes: u16,
+    error: Option<String>,
+}
+
+/// The form used to create or edit an automation rule.
+#[derive(Debug, Clone)]
+pub struct AutomationEditor {
+    /// The edited rule, `None` when creating a new one.
+    id: Option<String>,
+    name: String,
+    enabled: bool,
+    event: SunEvent,
+    offset_minutes: i16,
+    action_kind: AutomationActionKind,
+    /// Group ids, scene ids (empty for a group state) and labels of the targets.
+    targets: Vec<(String, String, String)>,
+    target_labels: Vec<String>,
+    target: Option<usize>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutomationActionKind {
+    Scene,
+    TurnOn,
+    TurnOff,
+}
+
+/// Messages emitted by the automation rule editor.
+#[derive(Debug, Clone)]
+pub enum AutomationEditorMessage {
+    Name(String),
+    Event(SunEvent),
+    Offset(i16),
+    ActionKind(AutomationActionKind),
+    Target(usize),
+}
+
+/// The form used to create or edit a notification rule.
+#[derive(Debug, Clone)]
+pub struct NotificationRuleEditor {
+    /// The edited rule, `None` when creating a new one.
+    id: Option<String>,
+    app_name: String,
+    min_urgency: Urgency,
+    /// Ids and names of the lights which can be alerted.
+    lights: Vec<(String, String)>,
+    light_labels: Vec<String>,
+    light: Option<usize>,
+    /// The flash color among `FLASH_COLORS`, `None` to breathe.
+    flash_color: Option<usize>,
+    error: Option<String>,
+}
+
+/// Messages emitted by the notification rule editor.
+#[derive(Debug, Clone)]
+pub enum NotificationRuleEditorMessage {
+    AppName(String),
+    MinUrgency(Urgency),
+    Light(usize),
+    FlashColor(Option<usize>),
+}
+
+/// A running focus timer.
+#[derive(Debug, Clone)]
+pub struct FocusTimer {
+    phase: FocusPhase,
+    ends_at: Instant,
+}
+
+/// The level of the default group, shown after a scroll or a middle-click on the panel icon.
+#[derive(Debug, Clone)]
+pub struct PanelLevel {
+    group_id: String,
+    name: String,
+    on: bool,
+    brightness: u8,
+}
+
+/// The form used to edit the busy light settings.
+#[derive(Debug, Clone)]
+pub struct BusyLightEditor {
+    calendar_path: String,
+    /// Ids and names of the lights which can be the busy light.
+    lights: Vec<(String, String)>,
+    light_labels: Vec<String>,
+    light: Option<usize>,
+    error: Option<String>,
+}
+
+/// Messages emitted by the busy light editor.
+#[derive(Debug, Clone)]
+pub enum BusyLightEditorMessage {
+    CalendarPath(String),
+    Light(usize),
+}
+
+/// Messages emitted by the wake-up alarm editor.
+#[derive(Debug, Clone)]
+pub enum WakeUpEditorMessage {
+    Name(String),
+    Group(usize),
+    ToggleWeekday(u8),
+    Time(String),
+    Duration(u16),
+}
+
+/// Messages emitted by the schedule editor.
+#[derive(Debug, Clone)]
+pub enum ScheduleEditorMessage {
+    Name(String),
+    Kind(ScheduleKind),
+    ToggleWeekday(u8),
+    Time(String),
+    Date(String),
+    TimerMinutes(String),
+    TargetKind(ScheduleTargetKind),
+    Target(usize),
+    On(bool),
 }
 
 /// Messages emitted by the application and its widgets.
@@ -102,11 +500,12 @@ pub enum Message {
     DiscoverBridge,
     BridgeDiscoveryFinished(Result<IpAddr, String>),
     PairBridge,
-    PairBridgeFinished(Result<String, String>),
+    PairBridgeFinished(Result<(String, Option<String>), String>),
     LoadGroups,
     GroupsLoaded(Result<Vec<huelib::resource::Group>, String>),
     LoadScenes,
     ScenesLoaded(Result<Vec<huelib::resource::Scene>, String>),
+    ScenePaletteLoaded(String, Result<Vec<(f32, f32, f32)>, String>),
     LoadLights,
     LightsLoaded(Result<Vec<huelib::resource::Light>, String>),
     ToggleLight(String, bool),
@@ -116,6 +515,60 @@ pub enum Message {
     ToggleLightsMenu,
     ToggleGroupsMenu,
     ToggleScenesMenu,
+    ToggleSchedulesMenu,
+    LoadSchedules,
+    SchedulesLoaded(Result<Vec<huelib::resource::Schedule>, String>),
+    ToggleSchedule(String, bool),
+    DeleteSchedule(String),
+    NewSchedule,
+    EditSchedule(String),
+    ScheduleEditor(ScheduleEditorMessage),
+    CancelScheduleEditor,
+    SaveSchedule,
+    SchedulesModified(Result<(), String>),
+    ToggleWakeUpsMenu,
+    NewWakeUp,
+    EditWakeUp(String),
+    WakeUpEditor(WakeUpEditorMessage),
+    CancelWakeUpEditor,
+    SaveWakeUp,
+    ToggleWakeUp(String, bool),
+    DeleteWakeUp(String),
+    ToggleAutomationsMenu,
+    ToggleFocusMenu,
+    StartFocusTimer,
+    StopFocusTimer,
+    SkipFocusPhase,
+    FocusTick,
+    FocusStarted(Result<Option<Snapshot>, String>),
+    FocusPhaseShown(Result<(), String>),
+    FocusRestored(Result<(), String>),
+    SetFocusLight(usize),
+    SetFocusSignal(FocusSignal),
+    SetFocusWorkMinutes(u16),
+    SetFocusBreakMinutes(u16),
+    SetLatitude(String),
+    SetLongitude(String),
+    SaveLocation,
+    NewAutomation,
+    EditAutomation(String),
+    AutomationEditor(AutomationEditorMessage),
+    CancelAutomationEditor,
+    SaveAutomation,
+    ToggleAutomation(String, bool),
+    DeleteAutomation(String),
+    AutomationTick,
+    ToggleRowMenu((String, String)),
+    StartSleepTimer((String, String), u32),
+    /// The schedule of a sleep timer created on the bridge, with the end of the timer it was
+    /// created for.
+    SleepTimerStarted((String, String), Instant, Result<String, String>),
+    CancelSleepTimer((String, String)),
+    SleepTimerTick,
+    ToggleHiddenScenes,
+    ToggleSceneOptions(String),
+    SetSceneRecallOptions(String, SceneRecallOptions),
+    ApplySceneRecallOptions(String, u64),
     SetLightBrightness(String, f32),
     SetGroupBrightness(String, f32),
     ToggleColorPicker((String, String)),
@@ -124,6 +577,88 @@ pub enum Message {
     SetGroupColor(widget::color_picker::ColorPickerUpdate),
     RectanglesUpdated(RectangleUpdate<u32>),
     ApplyLightBrightness(String, u64),
+    SetLightColorTemperature(String, u16, Option<u8>),
+    ApplyLightColorTemperature(String, u64),
+    SetAdaptiveOptions(String, Option<AdaptiveOptions>),
+    AdaptiveTick,
+    Logind(LogindEvent),
+    LockActionsApplied(Result<Snapshot, String>),
+    LockRestored(Result<(), String>),
+    SetLockAction(String, Option<LockAction>),
+    NotificationReceived(Notification),
+    NotificationAlertFinished(String, Result<(), String>),
+    NewNotificationRule,
+    EditNotificationRule(String),
+    NotificationRuleEditor(NotificationRuleEditorMessage),
+    CancelNotificationRuleEditor,
+    SaveNotificationRule,
+    DeleteNotificationRule(String),
+    ServiceChanged(ServiceChange),
+    OpenSettings,
+    SettingsOpened(Id),
+    SetBridgeIpInput(String),
+    SaveBridgeIp,
+    /// The id of the bridge answering at a new address was read.
+    BridgeIdRead(IpAddr, Result<String, String>),
+    SetRefreshInterval(u16),
+    SetDebounceDelay(u16),
+    SetSectionVisible(Section, bool),
+    SetItemHidden(ItemRef, bool),
+    ToggleEditMode,
+    StartDrag(ItemRef),
+    DropItem(ItemRef),
+    CancelDrag,
+    SetAlias(ItemRef, String),
+    ApplyAlias(ItemRef, u64),
+    SetSearch(String),
+    FocusSearch,
+    /// A key pressed in a window, acted upon only in the popup.
+    PopupKey(Id, Box<Message>),
+    /// Move the row chosen with the keyboard by a number of rows.
+    MoveSelection(isize),
+    PressSelection,
+    /// Change the brightness of the chosen row by a number of steps.
+    AdjustSelectionBrightness(i32),
+    OpenSelectionColorPicker,
+    /// Activate a scene by its place in the list, from 0.
+    ActivateSceneNumber(usize),
+    CloseTopPopup,
+    StartRenameLight(String),
+    SetLightNameInput(String),
+    SaveLightName,
+    CancelRenameLight,
+    LightRenamed(Result<(String, String), String>),
+    Refresh,
+    RefreshPanelIcon,
+    PanelIconLoaded(Result<(Vec<huelib::resource::Light>, Vec<String>), String>),
+    SetTintPanelIcon(bool),
+    SetPinned(ItemRef, bool),
+    PressPin(ItemRef),
+    SetShowPinsInPanel(bool),
+    PanelScrolled(ScrollDelta),
+    ApplyPanelBrightness(u64),
+    TogglePanelGroup,
+    PanelGroupChanged(Result<PanelLevel, String>),
+    HidePanelLevel(u64),
+    SetShortcutDefaultGroup(usize),
+    SetBrightnessStep(u8),
+    SetColorTemperatureStep(u16),
+    CalendarTick,
+    CalendarLoaded(Result<Option<calendar::Occurrence>, String>),
+    SetBusy(bool),
+    BusyLightSet(Result<Snapshot, String>),
+    BusyLightRestored(Result<(), String>),
+    EditBusyLight,
+    BusyLightEditor(BusyLightEditorMessage),
+    CancelBusyLightEditor,
+    SaveBusyLight,
+    RemoveBusyLight,
+    IdleChanged(IdleEvent),
+    IdleDimmed(Result<Snapshot, String>),
+    IdleRestored(Result<(), String>),
+    SetIdleDim(Option<IdleDimOptions>),
+    ApplyIdleDim(u64),
+    AdaptiveLightsLoaded(Result<(Vec<huelib::resource::Group>, Vec<huelib::resource::Light>), String>),
     ApplyGroupBrightness(String, u64),
     ApplyLightColor(String, u64),
     ApplyGroupColor(String, u64),
@@ -144,7 +679,7 @@ impl cosmic::Application for AppModel {
     type Message = Message;
 
     /// Unique identifier in RDNN (reverse domain name notation) format.
-    const APP_ID: &'static str = "com.clockworksfr.cosmichue";
+    const APP_ID: &'static str = config::APP_ID;
 
     fn core(&self) -> &cosmic::Core {
         &self.core
@@ -159,45 +694,121 @@ impl cosmic::Application for AppModel {
         core: cosmic::Core,
         _flags: Self::Flags,
     ) -> (Self, Task<cosmic::Action<Self::Message>>) {
+        let panel_icon_state = PanelIconState {
+            foreground: theme_foreground(cosmic::theme::active().cosmic()),
+            ..PanelIconState::default()
+        };
+
         // Construct the app model with the runtime's core.
-        let app = AppModel {
+        let mut app = AppModel {
             core,
             popup: None,
             is_scanning: false,
             last_discovery: None,
             lights: Vec::new(),
+            light_labels: Vec::new(),
             groups: Vec::new(),
+            group_labels: Vec::new(),
             scenes: Vec::new(),
             lights_menu_expanded: false,
             groups_menu_expanded: false,
             scenes_menu_expanded: false,
-            config: cosmic_config::Config::new(Self::APP_ID, Config::VERSION)
-                .map(|context| match Config::get_entry(&context) {
-                    Ok(config) => config,
-                    Err((_errors, config)) => {
-                        // for why in errors {
-                        //     tracing::error!(%why, "error loading app config");
-                        // }
-
-                        config
-                    }
-                })
-                .unwrap_or_default(),
+            schedules: Vec::new(),
+            schedules_menu_expanded: false,
+            schedule_editor: None,
+            wake_ups: Vec::new(),
+            wake_ups_menu_expanded: false,
+            wake_up_editor: None,
+            automations_menu_expanded: false,
+            focus_menu_expanded: false,
+            focus_timer: None,
+            focus_snapshot: None,
+            automation_editor: None,
+            latitude_input: String::new(),
+            longitude_input: String::new(),
+            location_error: None,
+            last_automation_check: chrono::Local::now(),
+            edit_mode: false,
+            search_query: String::new(),
+            search_results: Vec::new(),
+            selected_item: None,
+            dragged_item: None,
+            pending_aliases: HashMap::new(),
+            renaming_light: None,
+            light_name_error: None,
+            row_menu: None,
+            sleep_timers: HashMap::new(),
+            show_hidden_scenes: false,
+            scene_options_expanded: None,
+            scene_palettes: HashMap::new(),
+            config: config::load(),
             color_picker_model: widget::ColorPickerModel::new("hex", "rgb", None, None),
             active_color_picker_item: None,
             last_active_color_picker_item: None,
             color_picker_popup: None,
             more_menu_popup: None,
+            settings_window: None,
+            bridge_ip_input: String::new(),
+            bridge_ip_error: None,
+            last_refresh: None,
+            panel_icon: panel_icon_state,
+            panel_icon_handle: icon::from_svg_bytes(panel_icon_state.svg()),
+            level_popup: None,
+            panel_level: None,
+            panel_scroll: 0.0,
+            pending_panel_steps: None,
+            level_counter: 0,
             color_button_tracker: None,
             color_button_rectangles: HashMap::new(),
             pending_light_brightness: HashMap::new(),
             pending_group_brightness: HashMap::new(),
             pending_light_color: HashMap::new(),
             pending_group_color: HashMap::new(),
+            pending_scene_options: HashMap::new(),
+            pending_idle_dim: None,
+            pending_light_color_temperature: HashMap::new(),
+            session_locked: false,
+            session_sleeping: false,
+            lock_snapshot: None,
+            idle_dimmed: false,
+            idle_snapshot: None,
+            idle_unavailable: false,
+            notification_rule_editor: None,
+            alerting_lights: HashSet::new(),
+            busy_light_editor: None,
+            calendar_event: None,
+            service: None,
+            busy_override: None,
+            busy_light_on: false,
+            busy_snapshot: None,
+            adaptive_overrides: HashSet::new(),
+            adaptive_applied: HashMap::new(),
             debounce_counter: 0,
         };
 
-        (app, Task::none())
+        if let Some((latitude, longitude)) = app.config.get_location() {
+            app.latitude_input = latitude.to_string();
+            app.longitude_input = longitude.to_string();
+        }
+        if let Some(bridge_ip) = app.config.get_bridge_ip() {
+            app.bridge_ip_input = bridge_ip.to_string();
+        }
+
+        // Read the calendar right away instead of waiting for the first check
+        let task = if app
+            .config
+            .get_busy_light()
+            .is_some_and(|options| options.calendar_path.is_some())
+        {
+            Task::perform(async {}, |_| Action::App(Message::CalendarTick))
+        } else {
+            Task::none()
+        };
+
+        // Draw the panel icon from the state of the lights
+        let refresh_icon = Task::perform(async {}, |_| Action::App(Message::RefreshPanelIcon));
+
+        (app, Task::batch([task, refresh_icon]))
     }
 
     fn on_close_requested(&self, id: Id) -> Option<Message> {
@@ -205,21 +816,53 @@ impl cosmic::Application for AppModel {
     }
 
     fn view(&self) -> Element<'_, Self::Message> {
-        // Load the custom lightbulb icon
-        const LIGHTBULB_ICON: &[u8] = include_bytes!("../resources/icon.svg");
-        let icon_handle = icon::from_svg_bytes(LIGHTBULB_ICON);
-        
-        self.core
+        // The lightbulb icon follows the state of the lights
+        let button = self
+            .core
             .applet
-            .icon_button_from_handle(icon_handle)
-            .on_press(Message::TogglePopup)
+            .icon_button_from_handle(self.panel_icon_handle.clone())
+            .on_press(Message::TogglePopup);
+
+        // Scroll to change the brightness of the default group, middle-click to toggle it
+        let main_icon = cosmic::iced::widget::mouse_area(button)
+            .on_scroll(Message::PanelScrolled)
+            .on_middle_press(Message::TogglePanelGroup);
+
+        if !self.panel_has_room_for_pins() {
+            return main_icon.into();
+        }
+        // The name of a pin shows away from the edge of the screen
+        let tooltip_position = match self.core.applet.anchor {
+            PanelAnchor::Bottom => widget::tooltip::Position::Top,
+            _ => widget::tooltip::Position::Bottom,
+        };
+        let mut children: Vec<Element<'_, Message>> = vec![main_icon.into()];
+        for pin in self.config.get_pins() {
+            let Some((name, state)) = self.pin_icon_state(pin) else {
+                continue;
+            };
+            let button = self
+                .core
+                .applet
+                .icon_button_from_handle(icon::from_svg_bytes(state.svg()))
+                .on_press(Message::PressPin(pin.clone()));
+            children.push(widget::tooltip(button, widget::text(name), tooltip_position).into());
+        }
+        widget::row::with_children(children)
+            .align_y(Alignment::Center)
             .into()
     }
 
     fn view_window(&self, id: Id) -> Element<'_, Self::Message> {        
         if Some(id) == self.more_menu_popup {
+            // The settings can be taller than the popup
             let container = widget::container(
-                        widget::column::with_children(vec![
+                        widget::scrollable(widget::column::with_children(vec![
+                            widget::flex_row(
+                                vec![
+                                    widget::button::standard(fl!("open-settings")).on_press(Message::OpenSettings).into(),
+                                ]
+                            ).padding(10).into(),
                             widget::flex_row(
                                 vec![
                                     widget::text(fl!("bridge-ip")).into(),
@@ -228,13 +871,19 @@ impl cosmic::Application for AppModel {
                                 ]
                             ).padding(10).into(),
                             widget::divider::horizontal::default().into(),
+                            widget::container(self.build_notification_rules_settings()).padding(10).into(),
+                            widget::divider::horizontal::default().into(),
+                            widget::container(self.build_busy_light_settings()).padding(10).into(),
+                            widget::divider::horizontal::default().into(),
+                            widget::container(self.build_shortcut_settings()).padding(10).into(),
+                            widget::divider::horizontal::default().into(),
                             widget::flex_row(
                                 vec![
                                     widget::button::destructive(fl!("unpair-bridge")).on_press(Message::UnpairBridge).into(),
                                 ]
                             ).padding(10).into(),
                         ])
-                        .spacing(10)
+                        .spacing(10))
                 ).padding(10).style(
                     |theme| widget::container::Style {
                         border: cosmic::iced::Border {
@@ -253,6 +902,45 @@ impl cosmic::Application for AppModel {
                 .max_width(360.0)
                 .limits(Limits::NONE.min_width(120.0).max_width(360.0))
                 .into()
+        } else if Some(id) == self.settings_window {
+            widget::container(widget::scrollable(self.build_settings_window()))
+                .class(cosmic::theme::Container::Background)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .into()
+        } else if Some(id) == self.level_popup {
+            let Some(level) = &self.panel_level else {
+                return widget::text("").into();
+            };
+            let percent = (f32::from(level.brightness) * 100.0 / 254.0).round();
+            let value = if level.on {
+                fl!("percent", percent = percent as u8)
+            } else {
+                fl!("panel-level-off")
+            };
+
+            let container = widget::container(
+                widget::column::column()
+                    .spacing(10.0)
+                    .push(widget::flex_row(vec![
+                        widget::icon::from_name("display-brightness-symbolic").into(),
+                        widget::text(level.name.clone()).into(),
+                        widget::horizontal_space().into(),
+                        widget::text(value).into(),
+                    ]).spacing(10))
+                    .push(cosmic::iced::widget::progress_bar(
+                        0.0..=100.0,
+                        if level.on { percent } else { 0.0 },
+                    )),
+            ).padding(10);
+
+            self.core
+                .applet
+                .popup_container(container)
+                .min_width(240.0)
+                .max_width(240.0)
+                .limits(Limits::NONE.min_width(240.0).max_width(240.0))
+                .into()
         } else if Some(id) == self.color_picker_popup {
 
             let message = match self.active_color_picker_item.as_ref() {
@@ -324,9 +1012,31 @@ impl cosmic::Application for AppModel {
                     widget::flex_row(vec![
                         widget::text(fl!("app-title")).align_y(Alignment::Center).height(30.0).into(),
                         widget::horizontal_space().into(),
-                        widget::button::icon(widget::icon::from_name("view-more-symbolic")).on_press(Message::ToggleMoreMenu).into(),
+                        widget::button::icon(widget::icon::from_name(if self.edit_mode { "object-select-symbolic" } else { "document-edit-symbolic" }))
+                            .labelled(if self.edit_mode { fl!("finish-editing") } else { fl!("edit-items") })
+                            .on_press(Message::ToggleEditMode)
+                            .into(),
+                        widget::button::icon(widget::icon::from_name("view-more-symbolic")).labelled(fl!("more-options")).on_press(Message::ToggleMoreMenu).into(),
                     ])
                 ).into();
+
+                // Show the busy and available buttons
+                if self.config.get_busy_light().is_some() {
+                    content_list = content_list.add(self.build_busy_light_row());
+                }
+
+                // Show the pinned lights, groups and scenes
+                if !self.config.get_pins().is_empty() {
+                    content_list = content_list.add(self.build_pins_row());
+                }
+
+                content_list = content_list.add(
+                    widget::search_input(fl!("search-placeholder"), &self.search_query)
+                        .id(search_input_id())
+                        .on_input(Message::SetSearch)
+                        .on_submit(|_| Message::PressSelection)
+                        .on_clear(Message::SetSearch(String::new())),
+                );
                 
                 // Load data on popup opening
                 if self.popup.is_some() {
@@ -337,18 +1047,30 @@ impl cosmic::Application for AppModel {
                     ]);
                 }
 
-                // Build the lights list
-                content_list = content_list.add(self.build_lights_section());
-
-                // Build the groups list
-                content_list = content_list.add(self.build_groups_section());
-
-                // Build the scenes list
-                content_list = content_list.add(self.build_scenes_section());
+                // Build the sections chosen in the settings, or the results of the search
+                if !self.search_query.trim().is_empty() {
+                    content_list = content_list.add(self.build_search_results());
+                }
+                for section in Section::ALL {
+                    if !self.search_query.trim().is_empty() || !self.config.is_section_visible(section) {
+                        continue;
+                    }
+                    content_list = content_list.add(match section {
+                        Section::Lights => self.build_lights_section(),
+                        Section::Groups => self.build_groups_section(),
+                        Section::Scenes => self.build_scenes_section(),
+                        Section::Schedules => self.build_schedules_section(),
+                        Section::WakeUps => self.build_wake_ups_section(),
+                        Section::Automations => self.build_automations_section(),
+                        Section::Focus => self.build_focus_section(),
+                    });
+                }
             }
             content_list = content_list.into();
 
             let main_container = widget::container(content_list).padding(10);
+            // Releasing a dragged item outside of the rows leaves the order as it was
+            let main_container = cosmic::iced::widget::mouse_area(main_container).on_release(Message::CancelDrag);
     
             self.core
                 .applet
@@ -367,10 +1089,94 @@ impl cosmic::Application for AppModel {
     /// activated by selectively appending to the subscription batch, and will
     /// continue to execute for the duration that they remain in the batch.
     fn subscription(&self) -> Subscription<Self::Message> {
-        Subscription::batch(vec![
+        let mut subscriptions = vec![
             rectangle_tracker::subscription(0)
                 .map(|(_sub_id, update)| Message::RectanglesUpdated(update)),
-        ])
+        ];
+
+        // Refresh the remaining time of the sleep timers
+        if !self.sleep_timers.is_empty() {
+            subscriptions.push(
+                cosmic::iced::time::every(Duration::from_secs(1)).map(|_| Message::SleepTimerTick),
+            );
+        }
+
+        // Follow the time of day with the adaptive lighting
+        if !self.config.get_all_adaptive_options().is_empty() {
+            subscriptions.push(
+                cosmic::iced::time::every(Duration::from_secs(60)).map(|_| Message::AdaptiveTick),
+            );
+        }
+
+        // Turn the lights off when the session is locked or suspended
+        if !self.config.get_all_lock_actions().is_empty() {
+            subscriptions.push(Subscription::run(logind::events).map(Message::Logind));
+        }
+
+        // Dim the idle group while the user is away
+        if let Some(options) = self.config.get_idle_dim() {
+            let timeout = Duration::from_secs(u64::from(options.minutes) * 60);
+            subscriptions.push(
+                Subscription::run_with_id(timeout, idle_notify::idle_events(timeout))
+                    .map(Message::IdleChanged),
+            );
+        }
+
+        // Alert lights on desktop notifications
+        if !self.config.get_notification_rules().is_empty() {
+            subscriptions.push(
+                Subscription::run(notifications::notifications).map(Message::NotificationReceived),
+            );
+        }
+
+        // Serve the D-Bus interface once a bridge is paired
+        if self.config.get_username().is_some() {
+            subscriptions.push(Subscription::run(service::serve).map(Message::ServiceChanged));
+        }
+
+        // Keep the panel icon and the lists in sync with the lights changed elsewhere
+        if self.config.get_username().is_some() {
+            subscriptions.push(
+                cosmic::iced::time::every(self.config.get_general().refresh_interval())
+                    .map(|_| Message::Refresh),
+            );
+        }
+
+        // Operate the popup with the keyboard
+        if self.popup.is_some() && self.config.get_username().is_some() {
+            subscriptions.push(cosmic::iced::event::listen_with(|event, status, window| {
+                keyboard_message(event, status).map(|message| Message::PopupKey(window, Box::new(message)))
+            }));
+        }
+
+        // Move the focus timer forward every second
+        if self.focus_timer.is_some() {
+            subscriptions.push(
+                cosmic::iced::time::every(Duration::from_secs(1)).map(|_| Message::FocusTick),
+            );
+        }
+
+        // Follow the busy events of the calendar
+        if self
+            .config
+            .get_busy_light()
+            .is_some_and(|options| options.calendar_path.is_some())
+        {
+            subscriptions.push(
+                cosmic::iced::time::every(Duration::from_secs(60)).map(|_| Message::CalendarTick),
+            );
+        }
+
+        // Check the automation rules every minute
+        if self.config.get_location().is_some()
+            && self.config.get_automation_rules().iter().any(|rule| rule.enabled)
+        {
+            subscriptions.push(
+                cosmic::iced::time::every(Duration::from_secs(60)).map(|_| Message::AutomationTick),
+            );
+        }
+
+        Subscription::batch(subscriptions)
     }
 
     /// Handles messages emitted by the application and its widgets.
@@ -404,6 +1210,8 @@ impl cosmic::Application for AppModel {
                 self.is_scanning = false;
 
                 self.last_discovery = Some(Ok(bridge_ip.clone()));
+                self.bridge_ip_input = bridge_ip.to_string();
+                self.bridge_ip_error = None;
                 if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
                     let _ = self.config.set_bridge_ip(&ctx, Some(bridge_ip));
                 }
@@ -434,11 +1242,17 @@ impl cosmic::Application for AppModel {
 
                     let open_popup = get_popup(popup_settings);
 
+                    // Start a new search, the shortcuts act on the rows until the search field is focused
+                    self.search_query.clear();
+                    self.search_results.clear();
+                    self.selected_item = None;
+
                     let maybe_load = if self.config.get_username().is_some() {
                         Task::batch([
                             Task::perform(async {}, |_| Action::App(Message::LoadLights)),
                             Task::perform(async {}, |_| Action::App(Message::LoadGroups)),
                             Task::perform(async {}, |_| Action::App(Message::LoadScenes)),
+                            Task::perform(async {}, |_| Action::App(Message::LoadSchedules)),
                         ])
                         // Task::perform(async {}, |_| Action::App(Message::LoadLights))
                     } else {
@@ -452,6 +1266,13 @@ impl cosmic::Application for AppModel {
                 if self.popup.as_ref() == Some(&id) {
                     self.popup = None;
                 }
+                if self.level_popup.as_ref() == Some(&id) {
+                    self.level_popup = None;
+                }
+                if self.settings_window.as_ref() == Some(&id) {
+                    self.settings_window = None;
+                    return cosmic::iced::window::close(id);
+                }
             }
             Message::PairBridge => {
                 let bridge_ip = match self.config.get_bridge_ip() {
@@ -460,15 +1281,18 @@ impl cosmic::Application for AppModel {
                 };
                 return Task::perform(
                     async move {
-                        huelib::bridge::register_user(bridge_ip, "cosmic-hue")
-                            .map_err(|e| e.to_string())
+                        let username = huelib::bridge::register_user(bridge_ip, "cosmic-hue")
+                            .map_err(|e| e.to_string())?;
+                        // Remember the bridge, to notice another one taking its address
+                        Ok((username, config::read_bridge_id(bridge_ip).ok()))
                     },
                     |result| Action::App(Message::PairBridgeFinished(result)),
                 );
             }
-            Message::PairBridgeFinished(Ok(username)) => {
+            Message::PairBridgeFinished(Ok((username, bridge_id))) => {
                 if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
                     let _ = self.config.set_username(&ctx, Some(username));
+                    let _ = self.config.set_bridge_id(&ctx, bridge_id);
                 };
                 return Task::perform(async {}, |_| Action::App(Message::LoadLights));
             }
@@ -480,6 +1304,10 @@ impl cosmic::Application for AppModel {
                 if self.lights_menu_expanded {
                     self.groups_menu_expanded = false;
                     self.scenes_menu_expanded = false;
+                    self.schedules_menu_expanded = false;
+                    self.wake_ups_menu_expanded = false;
+                    self.automations_menu_expanded = false;
+                    self.focus_menu_expanded = false;
                 }
             }
             Message::ToggleGroupsMenu => {
@@ -487,6 +1315,10 @@ impl cosmic::Application for AppModel {
                 if self.groups_menu_expanded {
                     self.lights_menu_expanded = false;
                     self.scenes_menu_expanded = false;
+                    self.schedules_menu_expanded = false;
+                    self.wake_ups_menu_expanded = false;
+                    self.automations_menu_expanded = false;
+                    self.focus_menu_expanded = false;
                 }
             }
             Message::ToggleScenesMenu => {
@@ -494,142 +1326,459 @@ impl cosmic::Application for AppModel {
                 if self.scenes_menu_expanded {
                     self.lights_menu_expanded = false;
                     self.groups_menu_expanded = false;
+                    self.schedules_menu_expanded = false;
+                    self.wake_ups_menu_expanded = false;
+                    self.automations_menu_expanded = false;
+                    self.focus_menu_expanded = false;
                 }
             }
-            Message::LoadLights => {
+            Message::ToggleSchedulesMenu => {
+                self.schedules_menu_expanded = !self.schedules_menu_expanded;
+                if self.schedules_menu_expanded {
+                    self.lights_menu_expanded = false;
+                    self.groups_menu_expanded = false;
+                    self.scenes_menu_expanded = false;
+                    self.wake_ups_menu_expanded = false;
+                    self.automations_menu_expanded = false;
+                    self.focus_menu_expanded = false;
+                }
+            }
+            Message::ToggleWakeUpsMenu => {
+                self.wake_ups_menu_expanded = !self.wake_ups_menu_expanded;
+                if self.wake_ups_menu_expanded {
+                    self.lights_menu_expanded = false;
+                    self.groups_menu_expanded = false;
+                    self.scenes_menu_expanded = false;
+                    self.schedules_menu_expanded = false;
+                    self.automations_menu_expanded = false;
+                    self.focus_menu_expanded = false;
+                }
+            }
+            Message::ToggleAutomationsMenu => {
+                self.automations_menu_expanded = !self.automations_menu_expanded;
+                if self.automations_menu_expanded {
+                    self.lights_menu_expanded = false;
+                    self.groups_menu_expanded = false;
+                    self.scenes_menu_expanded = false;
+                    self.schedules_menu_expanded = false;
+                    self.wake_ups_menu_expanded = false;
+                    self.focus_menu_expanded = false;
+                }
+            }
+            Message::ToggleFocusMenu => {
+                self.focus_menu_expanded = !self.focus_menu_expanded;
+                if self.focus_menu_expanded {
+                    self.lights_menu_expanded = false;
+                    self.groups_menu_expanded = false;
+                    self.scenes_menu_expanded = false;
+                    self.schedules_menu_expanded = false;
+                    self.wake_ups_menu_expanded = false;
+                    self.automations_menu_expanded = false;
+                }
+            }
+            Message::LoadSchedules => {
                 let bridge = match get_bridge(&self.config) {
                     Some(bridge) => bridge,
                     None => return Task::none(),
                 };
                 return Task::perform(
                     async move {
-                        let lights = bridge.get_all_lights().map_err(|e| e.to_string())?;
-                        Ok(lights)
+                        let schedules = bridge.get_all_schedules().map_err(|e| e.to_string())?;
+                        Ok(schedules)
                     },
-                    |result| Action::App(Message::LightsLoaded(result)),
+                    |result| Action::App(Message::SchedulesLoaded(result)),
                 );
             }
-            Message::LightsLoaded(Ok(lights)) => {
-                println!("Lights loaded: {}", lights.len());
-                let mut lights_vm: Vec<LightVm> = lights
+            Message::SchedulesLoaded(Ok(schedules)) => {
+                println!("Schedules loaded: {}", schedules.len());
+
+                // Recover the sleep timers started before the applet was restarted
+                let now = chrono::Utc::now().naive_utc();
+                for schedule in &schedules {
+                    if schedule.description != schedule::SLEEP_TIMER_DESCRIPTION
+                        || schedule.status != huelib::resource::schedule::Status::Enabled
+                    {
+                        continue;
+                    }
+                    let (Some(ScheduleTime::Timer(secs)), Some(start_time)) =
+                        (ScheduleTime::parse(&schedule.local_time), schedule.start_time)
+                    else {
+                        continue;
+                    };
+                    let item = match ScheduleTarget::from_command(&schedule.command) {
+                        Some(ScheduleTarget::Light { id, on: false }) => (id, "light".to_string()),
+                        Some(ScheduleTarget::Group { id, on: false }) => (id, "group".to_string()),
+                        _ => continue,
+                    };
+                    // The start time of the timers is expressed in UTC
+                    let end_time = start_time + chrono::Duration::seconds(secs as i64);
+                    if let Ok(remaining) = (end_time - now).to_std() {
+                        self.sleep_timers.entry(item).or_insert(SleepTimer {
+                            ends_at: Instant::now() + remaining,
+                            schedule_id: Some(schedule.id.clone()),
+                        });
+                    }
+                }
+
+                // The wake-up alarms are managed in their own section
+                self.wake_ups = WakeUp::from_schedules(&schedules);
+
+                let mut schedules_vm: Vec<ScheduleVm> = schedules
                     .into_iter()
-                    .map(|light| LightVm {
-                        id: light.id,
-                        name: light.name,
-                        on: light.state.on,
-                        brightness: light.state.brightness,
-                        color: Some(hsv_to_rgb(
-                            light.state.hue,
-                            light.state.saturation,
-                            light.state.brightness,
-                        )),
+                    .filter(|schedule| !WakeUp::is_wake_up_schedule(schedule))
+                    .map(|schedule| ScheduleVm {
+                        enabled: schedule.status == huelib::resource::schedule::Status::Enabled,
+                        time: ScheduleTime::parse(&schedule.local_time),
+                        randomization: schedule::randomization(&schedule.local_time).map(str::to_string),
+                        target: ScheduleTarget::from_command(&schedule.command),
+                        id: schedule.id,
+                        name: schedule.name,
                     })
                     .collect();
 
                 // Trier par ordre alphabétique
-                lights_vm.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-                self.lights = lights_vm;
+                schedules_vm.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+                self.schedules = schedules_vm;
             }
-            Message::LightsLoaded(Err(error)) => {
-                println!("Error loading lights: {:?}", error);
+            Message::SchedulesLoaded(Err(error)) => {
+                println!("Error loading schedules: {:?}", error);
             }
-            Message::LoadGroups => {
+            Message::ToggleSchedule(schedule_id, enabled) => {
+                if let Some(schedule) = self.schedules.iter_mut().find(|schedule| schedule.id == schedule_id) {
+                    schedule.enabled = enabled;
+                }
                 let bridge = match get_bridge(&self.config) {
                     Some(bridge) => bridge,
                     None => return Task::none(),
                 };
+                let status = if enabled {
+                    huelib::resource::schedule::Status::Enabled
+                } else {
+                    huelib::resource::schedule::Status::Disabled
+                };
+                let modifier = huelib::resource::schedule::Modifier::new().with_status(status);
                 return Task::perform(
-                    async move {
-                        let groups = bridge.get_all_groups().map_err(|e| e.to_string())?;
-                        Ok(groups)
+                    async move { bridge.set_schedule(&schedule_id, &modifier) },
+                    |result| {
+                        Action::App(Message::ResponsesModified(
+                            result.map_err(|e| e.to_string()),
+                        ))
                     },
-                    |result| Action::App(Message::GroupsLoaded(result)),
                 );
             }
-            Message::GroupsLoaded(Ok(groups)) => {
-                println!("Groups loaded: {}", groups.len());
-                let mut groups_vm: Vec<GroupVm> = groups
-                    .into_iter()
-                    .map(|group| {
-                        let mut first_light = None;
-                        if let Some(light_id) = group.lights.first() {
-                            if let Some(light) =
-                                self.lights.iter().find(|light| light.id == *light_id)
-                            {
-                                first_light = Some(light);
-                            }
-                        }
-                        let color = if first_light.is_some() {
-                            first_light.unwrap().color
-                        } else {
-                            Some((0.0, 0.0, 0.0))
-                        };
-                        let brightness = if first_light.is_some() {
-                            first_light.unwrap().brightness
-                        } else {
-                            None
-                        };
-
-                        GroupVm {
-                            id: group.id,
-                            name: group.name,
-                            on: group.state.map(|state| state.any_on),
-                            brightness,
-                            color,
-                            lights: group.lights,
-                        }
-                    })
-                    .collect();
-
-                // Trier par ordre alphabétique
-                groups_vm.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-                self.groups = groups_vm;
-            }
-            Message::GroupsLoaded(Err(error)) => {
-                println!("Error loading groups: {:?}", error);
-            }
-            Message::LoadScenes => {
+            Message::DeleteSchedule(schedule_id) => {
+                self.schedules.retain(|schedule| schedule.id != schedule_id);
                 let bridge = match get_bridge(&self.config) {
                     Some(bridge) => bridge,
                     None => return Task::none(),
                 };
                 return Task::perform(
-                    async move {
-                        let scenes = bridge.get_all_scenes().map_err(|e| e.to_string())?;
-                        Ok(scenes)
-                    },
-                    |result| Action::App(Message::ScenesLoaded(result)),
+                    async move { bridge.delete_schedule(&schedule_id).map_err(|e| e.to_string()) },
+                    |result| Action::App(Message::SchedulesModified(result)),
                 );
             }
-            Message::ScenesLoaded(Ok(scenes)) => {
-                println!("Scenes loaded: {}", scenes.len());
-                let mut scenes_vm: Vec<SceneVm> = scenes
-                    .into_iter()
-                    .map(|scene| SceneVm {
-                        id: scene.id,
-                        name: scene.name,
-                        group: scene.group.unwrap_or_else(String::new),
-                    })
-                    .collect();
+            Message::NewSchedule => {
+                let mut editor = ScheduleEditor {
+                    id: None,
+                    name: String::new(),
+                    kind: ScheduleKind::Recurring,
+                    weekdays: schedule::WORKING_DAYS,
+                    time: "07:00".to_string(),
+                    date: chrono::Local::now().format("%Y-%m-%d").to_string(),
+                    timer_minutes: "30".to_string(),
+                    target_kind: ScheduleTargetKind::Group,
+                    targets: Vec::new(),
+                    target_labels: Vec::new(),
+                    target: None,
+                    on: true,
+                    randomization: None,
+                    error: None,
+                };
+                self.set_schedule_editor_targets(&mut editor);
+                self.schedule_editor = Some(editor);
+            }
+            Message::EditSchedule(schedule_id) => {
+                let Some(schedule) = self.schedules.iter().find(|schedule| schedule.id == schedule_id) else {
+                    return Task::none();
+                };
+                let (Some(time), Some(target)) = (schedule.time, schedule.target.clone()) else {
+                    return Task::none();
+                };
 
-                // Trier par ordre alphabétique
-                scenes_vm.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-                self.scenes = scenes_vm;
+                let mut editor = ScheduleEditor {
+                    id: Some(schedule.id.clone()),
+                    name: schedule.name.clone(),
+                    kind: ScheduleKind::Recurring,
+                    weekdays: schedule::WORKING_DAYS,
+                    time: "07:00".to_string(),
+                    date: chrono::Local::now().format("%Y-%m-%d").to_string(),
+                    timer_minutes: "30".to_string(),
+                    target_kind: ScheduleTargetKind::Group,
+                    targets: Vec::new(),
+                    target_labels: Vec::new(),
+                    target: None,
+                    on: true,
+                    randomization: schedule.randomization.clone(),
+                    error: None,
+                };
+                match time {
+                    ScheduleTime::Recurring { weekdays, time } => {
+                        editor.weekdays = weekdays;
+                        editor.time = time.format("%H:%M").to_string();
+                    }
+                    ScheduleTime::Alarm(date_time) => {
+                        editor.kind = ScheduleKind::Alarm;
+                        editor.date = date_time.format("%Y-%m-%d").to_string();
+                        editor.time = date_time.format("%H:%M").to_string();
+                    }
+                    ScheduleTime::Timer(secs) => {
+                        editor.kind = ScheduleKind::Timer;
+                        editor.timer_minutes = (secs / 60).to_string();
+                    }
+                }
+                let target_id = match target {
+                    ScheduleTarget::Light { id, on } => {
+                        editor.target_kind = ScheduleTargetKind::Light;
+                        editor.on = on;
+                        id
+                    }
+                    ScheduleTarget::Group { id, on } => {
+                        editor.on = on;
+                        id
+                    }
+                    ScheduleTarget::Scene { scene_id, .. } => {
+                        editor.target_kind = ScheduleTargetKind::Scene;
+                        scene_id
+                    }
+                };
+                self.set_schedule_editor_targets(&mut editor);
+                editor.target = editor.targets.iter().position(|(id, _)| *id == target_id);
+                self.schedule_editor = Some(editor);
             }
-            Message::ScenesLoaded(Err(error)) => {
-                println!("Error loading scenes: {:?}", error);
+            Message::ScheduleEditor(message) => {
+                let Some(mut editor) = self.schedule_editor.take() else {
+                    return Task::none();
+                };
+                match message {
+                    ScheduleEditorMessage::Name(name) => editor.name = name,
+                    ScheduleEditorMessage::Kind(kind) => editor.kind = kind,
+                    ScheduleEditorMessage::ToggleWeekday(weekday) => editor.weekdays ^= weekday,
+                    ScheduleEditorMessage::Time(time) => editor.time = time,
+                    ScheduleEditorMessage::Date(date) => editor.date = date,
+                    ScheduleEditorMessage::TimerMinutes(minutes) => editor.timer_minutes = minutes,
+                    ScheduleEditorMessage::TargetKind(target_kind) => {
+                        editor.target_kind = target_kind;
+                        self.set_schedule_editor_targets(&mut editor);
+                    }
+                    ScheduleEditorMessage::Target(index) => editor.target = Some(index),
+                    ScheduleEditorMessage::On(on) => editor.on = on,
+                }
+                self.schedule_editor = Some(editor);
             }
-            Message::ToggleLight(light_id, new_state) => {
-                if let Some(light) = self.lights.iter_mut().find(|light| light.id == light_id) {
-                    light.on = Some(new_state);
+            Message::CancelScheduleEditor => {
+                self.schedule_editor = None;
+            }
+            Message::SaveSchedule => {
+                let Some(editor) = self.schedule_editor.as_mut() else {
+                    return Task::none();
+                };
+                let (time, target) = match (editor.schedule_time(), editor.schedule_target(&self.scenes)) {
+                    (Ok(time), Ok(target)) => (time, target),
+                    (Err(error), _) | (_, Err(error)) => {
+                        editor.error = Some(error);
+                        return Task::none();
+                    }
+                };
+                let name = if editor.name.trim().is_empty() {
+                    fl!("schedule")
+                } else {
+                    editor.name.trim().to_string()
+                };
+                let schedule_id = editor.id.clone();
+                let local_time = format!(
+                    "{}{}",
+                    time.to_local_time(),
+                    editor.randomization.as_deref().unwrap_or_default()
+                );
+
+                let (Some(bridge), Some(username)) = (get_bridge(&self.config), self.config.get_username()) else {
+                    return Task::none();
+                };
+                let command = target.to_command(username);
+                self.schedule_editor = None;
+
+                return Task::perform(
+                    async move {
+                        let result = match schedule_id {
+                            Some(schedule_id) => {
+                                let modifier = huelib::resource::schedule::Modifier::new()
+                                    .with_name(name)
+                                    .with_command(command)
+                                    .with_local_time(local_time);
+                                bridge.set_schedule(&schedule_id, &modifier).map(|_| ())
+                            }
+                            None => {
+                                let creator = huelib::resource::schedule::Creator::new(
+                                    command,
+                                    local_time,
+                                )
+                                .with_name(name)
+                                // Keep one-off schedules around so they can be rescheduled
+                                .with_auto_delete(false);
+                                bridge.create_schedule(&creator).map(|_| ())
+                            }
+                        };
+                        result.map_err(|e| e.to_string())
+                    },
+                    |result| Action::App(Message::SchedulesModified(result)),
+                );
+            }
+            Message::SchedulesModified(Ok(())) => {
+                return Task::perform(async {}, |_| Action::App(Message::LoadSchedules));
+            }
+            Message::SchedulesModified(Err(error)) => {
+                println!("Error modifying schedules: {:?}", error);
+                return Task::perform(async {}, |_| Action::App(Message::LoadSchedules));
+            }
+            Message::NewWakeUp => {
+                let mut editor = WakeUpEditor {
+                    wake_up: None,
+                    name: fl!("wake-up"),
+                    groups: Vec::new(),
+                    group_labels: Vec::new(),
+                    group: None,
+                    weekdays: schedule::WORKING_DAYS,
+                    time: "07:00".to_string(),
+                    duration_minutes: 30,
+                    error: None,
+                };
+                self.set_wake_up_editor_groups(&mut editor, None);
+                self.wake_up_editor = Some(editor);
+                self.wake_ups_menu_expanded = true;
+            }
+            Message::EditWakeUp(wake_up_id) => {
+                let Some(wake_up) = self.wake_ups.iter().find(|wake_up| wake_up.id == wake_up_id) else {
+                    return Task::none();
+                };
+                let mut editor = WakeUpEditor {
+                    wake_up: Some(wake_up.clone()),
+                    name: wake_up.name.clone(),
+                    groups: Vec::new(),
+                    group_labels: Vec::new(),
+                    group: None,
+                    weekdays: wake_up.weekdays,
+                    time: wake_up.time.format("%H:%M").to_string(),
+                    duration_minutes: wake_up.duration_minutes,
+                    error: None,
+                };
+                self.set_wake_up_editor_groups(&mut editor, Some(&wake_up.group_id));
+                self.wake_up_editor = Some(editor);
+            }
+            Message::WakeUpEditor(message) => {
+                let Some(editor) = self.wake_up_editor.as_mut() else {
+                    return Task::none();
+                };
+                match message {
+                    WakeUpEditorMessage::Name(name) => editor.name = name,
+                    WakeUpEditorMessage::Group(index) => editor.group = Some(index),
+                    WakeUpEditorMessage::ToggleWeekday(weekday) => editor.weekdays ^= weekday,
+                    WakeUpEditorMessage::Time(time) => editor.time = time,
+                    WakeUpEditorMessage::Duration(minutes) => editor.duration_minutes = minutes,
                 }
+            }
+            Message::CancelWakeUpEditor => {
+                self.wake_up_editor = None;
+            }
+            Message::SaveWakeUp => {
+                let Some(editor) = self.wake_up_editor.as_mut() else {
+                    return Task::none();
+                };
+                let Ok(time) = chrono::NaiveTime::parse_from_str(editor.time.trim(), "%H:%M") else {
+                    editor.error = Some(fl!("invalid-time"));
+                    return Task::none();
+                };
+                if editor.weekdays == 0 {
+                    editor.error = Some(fl!("no-weekday-selected"));
+                    return Task::none();
+                }
+                let Some((group_id, group_name)) = editor.group.and_then(|index| editor.groups.get(index)).cloned() else {
+                    editor.error = Some(fl!("no-target-selected"));
+                    return Task::none();
+                };
+
+                let previous = editor.wake_up.clone();
+                let wake_up = WakeUp {
+                    // The id only has to be unique among the alarms of the bridge, an edited alarm
+                    // gets a new one as it lives next to the previous one for a moment
+                    id: chrono::Utc::now().timestamp_millis().to_string(),
+                    name: if editor.name.trim().is_empty() {
+                        group_name
+                    } else {
+                        editor.name.trim().to_string()
+                    },
+                    group_id,
+                    weekdays: editor.weekdays,
+                    time,
+                    duration_minutes: editor.duration_minutes,
+                    enabled: previous.as_ref().map(|wake_up| wake_up.enabled).unwrap_or(true),
+                    schedule_ids: Vec::new(),
+                };
+
+                let (Some(bridge), Some(username)) = (get_bridge(&self.config), self.config.get_username()) else {
+                    return Task::none();
+                };
+                let creators = wake_up.creators(username);
+                self.wake_up_editor = None;
+
+                return Task::perform(
+                    async move {
+                        // Create the new schedules before deleting the ones of the edited alarm, so
+                        // a failure leaves the previous alarm in place
+                        let mut created = Vec::new();
+                        for creator in &creators {
+                            match bridge.create_schedule(creator) {
+                                Ok(schedule_id) => created.push(schedule_id),
+                                Err(error) => {
+                                    for schedule_id in &created {
+                                        let _ = bridge.delete_schedule(schedule_id);
+                                    }
+                                    return Err(error.to_string());
+                                }
+                            }
+                        }
+                        for schedule_id in previous.iter().flat_map(|wake_up| &wake_up.schedule_ids) {
+                            bridge.delete_schedule(schedule_id).map_err(|e| e.to_string())?;
+                        }
+                        Ok(())
+                    },
+                    |result| Action::App(Message::SchedulesModified(result)),
+                );
+            }
+            Message::ToggleWakeUp(wake_up_id, enabled) => {
+                let Some(wake_up) = self.wake_ups.iter_mut().find(|wake_up| wake_up.id == wake_up_id) else {
+                    return Task::none();
+                };
+                wake_up.enabled = enabled;
+                let schedule_ids = wake_up.schedule_ids.clone();
+
                 let bridge = match get_bridge(&self.config) {
                     Some(bridge) => bridge,
                     None => return Task::none(),
                 };
-                let modifier = huelib::resource::light::StateModifier::new().with_on(new_state);
+                let status = if enabled {
+                    huelib::resource::schedule::Status::Enabled
+                } else {
+                    huelib::resource::schedule::Status::Disabled
+                };
+                let modifier = huelib::resource::schedule::Modifier::new().with_status(status);
                 return Task::perform(
-                    async move { bridge.set_light_state(&light_id, &modifier) },
+                    async move {
+                        let mut responses = Vec::new();
+                        for schedule_id in &schedule_ids {
+                            responses.extend(bridge.set_schedule(schedule_id, &modifier)?);
+                        }
+                        Ok::<_, huelib::Error>(responses)
+                    },
                     |result| {
                         Action::App(Message::ResponsesModified(
                             result.map_err(|e| e.to_string()),
@@ -637,172 +1786,742 @@ impl cosmic::Application for AppModel {
                     },
                 );
             }
-            Message::ToggleGroup(group_id, new_state) => {
-                if let Some(group) = self.groups.iter_mut().find(|group| group.id == group_id) {
-                    println!("ToogleGroup: {}, new_state: {}", group_id, new_state);
-                    group.on = Some(new_state);
-                    group.lights.iter().for_each(|light_id| {
-                        if let Some(light) =
-                            self.lights.iter_mut().find(|light| light.id == *light_id)
-                        {
-                            light.on = Some(new_state);
-                        }
-                    });
-                }
+            Message::DeleteWakeUp(wake_up_id) => {
+                let Some(index) = self.wake_ups.iter().position(|wake_up| wake_up.id == wake_up_id) else {
+                    return Task::none();
+                };
+                let wake_up = self.wake_ups.remove(index);
+
                 let bridge = match get_bridge(&self.config) {
                     Some(bridge) => bridge,
                     None => return Task::none(),
                 };
-                let modifier = huelib::resource::group::StateModifier::new().with_on(new_state);
                 return Task::perform(
-                    async move { bridge.set_group_state(&group_id, &modifier) },
-                    |result| {
-                        Action::App(Message::ResponsesModified(
-                            result.map_err(|e| e.to_string()),
+                    async move {
+                        for schedule_id in &wake_up.schedule_ids {
+                            bridge.delete_schedule(schedule_id).map_err(|e| e.to_string())?;
+                        }
+                        Ok(())
+                    },
+                    |result| Action::App(Message::SchedulesModified(result)),
+                );
+            }
+            Message::SetLatitude(latitude) => {
+                self.latitude_input = latitude;
+            }
+            Message::SetLongitude(longitude) => {
+                self.longitude_input = longitude;
+            }
+            Message::SaveLocation => {
+                let latitude = self.latitude_input.trim().replace(',', ".").parse::<f64>();
+                let longitude = self.longitude_input.trim().replace(',', ".").parse::<f64>();
+                match (latitude, longitude) {
+                    (Ok(latitude), Ok(longitude))
+                        if (-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude) =>
+                    {
+                        self.location_error = None;
+                        if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                            let _ = self.config.set_latitude(&ctx, Some(latitude));
+                            let _ = self.config.set_longitude(&ctx, Some(longitude));
+                        }
+                    }
+                    _ => self.location_error = Some(fl!("invalid-location")),
+                }
+            }
+            Message::NewAutomation => {
+                let mut editor = AutomationEditor {
+                    id: None,
+                    name: fl!("automation"),
+                    enabled: true,
+                    event: SunEvent::Sunset,
+                    offset_minutes: 0,
+                    action_kind: AutomationActionKind::Scene,
+                    targets: Vec::new(),
+                    target_labels: Vec::new(),
+                    target: None,
+                    error: None,
+                };
+                self.set_automation_editor_targets(&mut editor);
+                self.automation_editor = Some(editor);
+                self.automations_menu_expanded = true;
+            }
+            Message::EditAutomation(rule_id) => {
+                let Some(rule) = self
+                    .config
+                    .get_automation_rules()
+                    .iter()
+                    .find(|rule| rule.id == rule_id)
+                else {
+                    return Task::none();
+                };
+                let (action_kind, group_id, scene_id) = match &rule.action {
+                    AutomationAction::Scene { group_id, scene_id } => {
+                        (AutomationActionKind::Scene, group_id.clone(), scene_id.clone())
+                    }
+                    AutomationAction::Group { group_id, on: true } => {
+                        (AutomationActionKind::TurnOn, group_id.clone(), String::new())
+                    }
+                    AutomationAction::Group { group_id, on: false } => {
+                        (AutomationActionKind::TurnOff, group_id.clone(), String::new())
+                    }
+                };
+                let mut editor = AutomationEditor {
+                    id: Some(rule.id.clone()),
+                    name: rule.name.clone(),
+                    enabled: rule.enabled,
+                    event: rule.event,
+                    offset_minutes: rule.offset_minutes,
+                    action_kind,
+                    targets: Vec::new(),
+                    target_labels: Vec::new(),
+                    target: None,
+                    error: None,
+                };
+                self.set_automation_editor_targets(&mut editor);
+                editor.target = editor
+                    .targets
+                    .iter()
+                    .position(|(target_group_id, target_scene_id, _)| {
+                        *target_group_id == group_id && *target_scene_id == scene_id
+                    });
+                self.automation_editor = Some(editor);
+            }
+            Message::AutomationEditor(message) => {
+                let Some(mut editor) = self.automation_editor.take() else {
+                    return Task::none();
+                };
+                match message {
+                    AutomationEditorMessage::Name(name) => editor.name = name,
+                    AutomationEditorMessage::Event(event) => editor.event = event,
+                    AutomationEditorMessage::Offset(offset_minutes) => {
+                        editor.offset_minutes = offset_minutes
+                    }
+                    AutomationEditorMessage::ActionKind(action_kind) => {
+                        // The scenes and the groups are listed separately
+                        let was_scene = editor.action_kind == AutomationActionKind::Scene;
+                        editor.action_kind = action_kind;
+                        if was_scene != (action_kind == AutomationActionKind::Scene) {
+                            self.set_automation_editor_targets(&mut editor);
+                        }
+                    }
+                    AutomationEditorMessage::Target(index) => editor.target = Some(index),
+                }
+                self.automation_editor = Some(editor);
+            }
+            Message::CancelAutomationEditor => {
+                self.automation_editor = None;
+            }
+            Message::SaveAutomation => {
+                let Some(editor) = self.automation_editor.as_mut() else {
+                    return Task::none();
+                };
+                let Some((group_id, scene_id, label)) =
+                    editor.target.and_then(|index| editor.targets.get(index)).cloned()
+                else {
+                    editor.error = Some(fl!("no-target-selected"));
+                    return Task::none();
+                };
+                let action = match editor.action_kind {
+                    AutomationActionKind::Scene => AutomationAction::Scene { group_id, scene_id },
+                    AutomationActionKind::TurnOn => AutomationAction::Group { group_id, on: true },
+                    AutomationActionKind::TurnOff => AutomationAction::Group { group_id, on: false },
+                };
+                let rule = AutomationRule {
+                    id: editor
+                        .id
+                        .clone()
+                        .unwrap_or_else(|| chrono::Utc::now().timestamp_millis().to_string()),
+                    name: if editor.name.trim().is_empty() {
+                        label
+                    } else {
+                        editor.name.trim().to_string()
+                    },
+                    enabled: editor.enabled,
+                    event: editor.event,
+                    offset_minutes: editor.offset_minutes,
+                    action,
+                };
+                self.automation_editor = None;
+
+                let mut rules = self.config.get_automation_rules().to_vec();
+                match rules.iter_mut().find(|existing| existing.id == rule.id) {
+                    Some(existing) => *existing = rule,
+                    None => rules.push(rule),
+                }
+                if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                    let _ = self.config.set_automation_rules(&ctx, rules);
+                }
+            }
+            Message::ToggleAutomation(rule_id, enabled) => {
+                let mut rules = self.config.get_automation_rules().to_vec();
+                if let Some(rule) = rules.iter_mut().find(|rule| rule.id == rule_id) {
+                    rule.enabled = enabled;
+                }
+                if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                    let _ = self.config.set_automation_rules(&ctx, rules);
+                }
+            }
+            Message::DeleteAutomation(rule_id) => {
+                let mut rules = self.config.get_automation_rules().to_vec();
+                rules.retain(|rule| rule.id != rule_id);
+                if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                    let _ = self.config.set_automation_rules(&ctx, rules);
+                }
+            }
+            Message::AutomationTick => {
+                let now = chrono::Local::now();
+                // Don't run the rules missed while the computer was asleep
+                let since = self
+                    .last_automation_check
+                    .max(now - chrono::Duration::minutes(AUTOMATION_CATCH_UP_MINUTES));
+                self.last_automation_check = now;
+
+                let Some((latitude, longitude)) = self.config.get_location() else {
+                    return Task::none();
+                };
+
+                let tasks: Vec<_> = self
+                    .config
+                    .get_automation_rules()
+                    .iter()
+                    .filter(|rule| rule.enabled)
+                    .filter(|rule| automation_due(rule, latitude, longitude, since, now))
+                    .filter_map(|rule| {
+                        println!("Running automation: {}", rule.name);
+                        let bridge = get_bridge(&self.config)?;
+                        let (group_id, modifier) = match &rule.action {
+                            AutomationAction::Scene { group_id, scene_id } => (
+                                group_id.clone(),
+                                huelib::resource::group::StateModifier::new()
+                                    .with_scene(scene_id.clone()),
+                            ),
+                            AutomationAction::Group { group_id, on } => (
+                                group_id.clone(),
+                                huelib::resource::group::StateModifier::new().with_on(*on),
+                            ),
+                        };
+                        Some(Task::perform(
+                            async move { bridge.set_group_state(&group_id, &modifier) },
+                            |result| {
+                                Action::App(Message::ResponsesModified(
+                                    result.map_err(|e| e.to_string()),
+                                ))
+                            },
                         ))
+                    })
+                    .collect();
+                return Task::batch(tasks);
+            }
+            Message::ToggleRowMenu(item) => {
+                if self.row_menu.as_ref() == Some(&item) {
+                    self.row_menu = None;
+                } else {
+                    self.row_menu = Some(item);
+                }
+            }
+            Message::StartSleepTimer((item_id, item_type), minutes) => {
+                let secs = minutes * 60;
+                let item = (item_id.clone(), item_type.clone());
+                let ends_at = Instant::now() + Duration::from_secs(secs as u64);
+                let previous_timer = self.sleep_timers.insert(
+                    item.clone(),
+                    SleepTimer {
+                        ends_at,
+                        schedule_id: None,
+                    },
+                );
+                self.row_menu = None;
+
+                let (Some(bridge), Some(username)) = (get_bridge(&self.config), self.config.get_username()) else {
+                    return Task::none();
+                };
+                let target = if item_type == "group" {
+                    ScheduleTarget::Group { id: item_id, on: false }
+                } else {
+                    ScheduleTarget::Light { id: item_id, on: false }
+                };
+                // A timer of the bridge still fires if the applet exits
+                let creator = huelib::resource::schedule::Creator::new(
+                    target.to_command(username),
+                    ScheduleTime::Timer(secs).to_local_time(),
+                )
+                .with_name(fl!("sleep-timer"))
+                .with_description(schedule::SLEEP_TIMER_DESCRIPTION.to_string())
+                .with_auto_delete(true);
+                let previous_schedule_id = previous_timer.and_then(|timer| timer.schedule_id);
+
+                return Task::perform(
+                    async move {
+                        // Replace the running timer of the item
+                        if let Some(schedule_id) = previous_schedule_id {
+                            let _ = bridge.delete_schedule(&schedule_id);
+                        }
+                        bridge.create_schedule(&creator).map_err(|e| e.to_string())
                     },
+                    move |result| Action::App(Message::SleepTimerStarted(item.clone(), ends_at, result)),
                 );
             }
-            Message::ActivateScene(scene_id) => {
-                if let Some(scene) = self.scenes.iter().find(|scene| scene.id == scene_id) {
-                    let modifier =
-                        huelib::resource::group::StateModifier::new().with_scene(scene_id.clone());
+            Message::SleepTimerStarted(item, ends_at, Ok(schedule_id)) => {
+                let timer = self
+                    .sleep_timers
+                    .get_mut(&item)
+                    .filter(|timer| timer.ends_at == ends_at);
+                if let Some(timer) = timer {
+                    timer.schedule_id = Some(schedule_id);
+                } else {
+                    // The timer was cancelled or replaced by another one in the meantime
                     let bridge = match get_bridge(&self.config) {
                         Some(bridge) => bridge,
                         None => return Task::none(),
                     };
-                    let group_id = scene.group.clone();
                     return Task::perform(
-                        async move { bridge.set_group_state(&group_id, &modifier) },
-                        |result| {
-                            Action::App(Message::SceneActivated(result.map_err(|e| e.to_string())))
-                        },
+                        async move { bridge.delete_schedule(&schedule_id).map_err(|e| e.to_string()) },
+                        |result| Action::App(Message::SchedulesModified(result)),
                     );
                 }
-                return Task::none();
+                return Task::perform(async {}, |_| Action::App(Message::LoadSchedules));
             }
-            Message::SceneActivated(Ok(responses)) => {
-                return Task::batch(vec![
-                    Task::perform(async move { Ok(responses) }, |result| Action::App(Message::ResponsesModified(result))),
-                    Task::perform(
-                        async move {
-                            // Wait for 10 seconds to reload light states to avoid intermediate values
-                            tokio::time::sleep(tokio::time::Duration::from_secs(10)).await;
-                        },
-                        |_| Action::App(Message::LoadGroups),
-                    ),
-                    Task::perform(
-                        async move {
-                            // Wait for 10 seconds to reload light states to avoid intermediate values
-                            tokio::time::sleep(tokio::time::Duration::from_secs(10)).await;
-                        },
-                        |_| Action::App(Message::LoadLights),
-                    ),
-                ]);
+            Message::SleepTimerStarted(item, _, Err(error)) => {
+                // Keep the local timer, the applet turns the item off itself
+                println!("Error creating the sleep timer of {:?}: {:?}", item, error);
             }
-            Message::SceneActivated(Err(error)) => {
-                println!("Error activating scene: {:?}", error);
+            Message::CancelSleepTimer(item) => {
+                let Some(timer) = self.sleep_timers.remove(&item) else {
+                    return Task::none();
+                };
+                let (Some(schedule_id), Some(bridge)) = (timer.schedule_id, get_bridge(&self.config)) else {
+                    return Task::none();
+                };
+                return Task::perform(
+                    async move { bridge.delete_schedule(&schedule_id).map_err(|e| e.to_string()) },
+                    |result| Action::App(Message::SchedulesModified(result)),
+                );
             }
-            Message::ResponsesModified(Ok(responses)) => {
-                println!("ResponsesModified: {:?}", responses);
+            Message::SleepTimerTick => {
+                let now = Instant::now();
+                let expired: Vec<(String, String)> = self
+                    .sleep_timers
+                    .iter()
+                    .filter(|(_, timer)| timer.ends_at <= now)
+                    .map(|(item, _)| item.clone())
+                    .collect();
+
+                let mut tasks = Vec::new();
+                for item in expired {
+                    let Some(timer) = self.sleep_timers.remove(&item) else {
+                        continue;
+                    };
+                    let (item_id, item_type) = item;
+                    if timer.schedule_id.is_some() {
+                        // The bridge turned the item off, reload the states
+                        let message = if item_type == "group" {
+                            Message::LoadGroups
+                        } else {
+                            Message::LoadLights
+                        };
+                        tasks.push(Task::perform(
+                            async move {
+                                // Leave some time to the bridge to run the schedule
+                                tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+                            },
+                            move |_| Action::App(message.clone()),
+                        ));
+                    } else if item_type == "group" {
+                        tasks.push(self.update(Message::ToggleGroup(item_id, false)));
+                    } else {
+                        tasks.push(self.update(Message::ToggleLight(item_id, false)));
+                    }
+                }
+                if !tasks.is_empty() {
+                    tasks.push(Task::perform(async {}, |_| Action::App(Message::LoadSchedules)));
+                }
+                return Task::batch(tasks);
             }
-            Message::ResponsesModified(Err(error)) => {
-                println!("Error: {:?}", error);
+            Message::ToggleHiddenScenes => {
+                self.show_hidden_scenes = !self.show_hidden_scenes;
+                self.update_search_results();
             }
-            Message::SetLightBrightness(light_id, new_brightness) => {
-                // Update the local state immediately
-                if let Some(light) = self.lights.iter_mut().find(|light| light.id == light_id) {
-                    light.brightness = Some(new_brightness as u8);
+            Message::ToggleSceneOptions(scene_id) => {
+                if self.scene_options_expanded.as_ref() == Some(&scene_id) {
+                    self.scene_options_expanded = None;
+                } else {
+                    self.scene_options_expanded = Some(scene_id);
                 }
-                
+            }
+            Message::SetSceneRecallOptions(scene_id, options) => {
                 // Increment the counter and store the value in pending
                 self.debounce_counter += 1;
                 let counter = self.debounce_counter;
-                self.pending_light_brightness.insert(light_id.clone(), (new_brightness, counter));
-                
-                // Create a task that will wait 300ms then apply the change
+                self.pending_scene_options.insert(scene_id.clone(), (options, counter));
+
+                // Create a task that will wait for the debounce delay then save the change
+                let delay = self.config.get_general().debounce_delay();
                 return Task::perform(
                     async move {
-                        tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
-                        (light_id, counter)
+                        tokio::time::sleep(delay).await;
+                        (scene_id, counter)
                     },
-                    |(id, cnt)| Action::App(Message::ApplyLightBrightness(id, cnt)),
+                    |(id, cnt)| Action::App(Message::ApplySceneRecallOptions(id, cnt)),
                 );
             }
-            Message::ApplyLightBrightness(light_id, counter) => {
+            Message::ApplySceneRecallOptions(scene_id, counter) => {
                 // Check if it's still the last request
-                if let Some((brightness, current_counter)) = self.pending_light_brightness.get(&light_id) {
+                if let Some((options, current_counter)) = self.pending_scene_options.get(&scene_id) {
                     if *current_counter == counter {
-                        // It's the last request, apply it
-                        let brightness = *brightness;
-                        self.pending_light_brightness.remove(&light_id);
-                        
-                        let bridge = match get_bridge(&self.config) {
-                            Some(bridge) => bridge,
-                            None => return Task::none(),
-                        };
-                        let modifier = huelib::resource::light::StateModifier::new().with_brightness(
-                            huelib::resource::Adjust::Override((brightness as u8).into()),
-                        );
-                        return Task::perform(
-                            async move { bridge.set_light_state(&light_id, &modifier) },
-                            |result| {
-                                Action::App(Message::ResponsesModified(
-                                    result.map_err(|e| e.to_string()),
-                                ))
-                            },
-                        );
+                        let options = *options;
+                        self.pending_scene_options.remove(&scene_id);
+
+                        let mut scene_recall_options = self.config.get_all_scene_recall_options().clone();
+                        if options == SceneRecallOptions::default() {
+                            scene_recall_options.remove(&scene_id);
+                        } else {
+                            scene_recall_options.insert(scene_id, options);
+                        }
+                        if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                            let _ = self.config.set_scene_recall_options(&ctx, scene_recall_options);
+                        }
                     }
                 }
             }
-            Message::SetGroupBrightness(group_id, new_brightness) => {
-                // Update the local state immediately
-                if let Some(group) = self.groups.iter_mut().find(|group| group.id == group_id) {
-                    if group.brightness.is_some() {
-                        // Find all lights in the group and set their brightness to the new brightness
-                        for light_id in &group.lights {
+            Message::LoadLights => {
+                let bridge = match get_bridge(&self.config) {
+                    Some(bridge) => bridge,
+                    None => return Task::none(),
+                };
+                return Task::perform(
+                    async move {
+                        let lights = bridge.get_all_lights().map_err(|e| e.to_string())?;
+                        Ok(lights)
+                    },
+                    |result| Action::App(Message::LightsLoaded(result)),
+                );
+            }
+            Message::LightsLoaded(Ok(lights)) => {
+                println!("Lights loaded: {}", lights.len());
+                let mut lights_vm: Vec<LightVm> = lights
+                    .into_iter()
+                    .map(|light| LightVm {
+                        id: light.id,
+                        name: light.name.clone(),
+                        bridge_name: light.name,
+                        on: light.state.on,
+                        brightness: light.state.brightness,
+                        color: Some(hsv_to_rgb(
+                            light.state.hue,
+                            light.state.saturation,
+                            light.state.brightness,
+                        )),
+                        reachable: light.state.reachable,
+                    })
+                    .collect();
+
+                self.set_panel_icon(PanelIconState {
+                    on: lights_vm.iter().any(|light| light.on == Some(true)),
+                    unreachable: false,
+                    ..self.panel_icon
+                });
+                self.lights = lights_vm;
+                self.arrange_lights();
+            }
+            Message::LightsLoaded(Err(error)) => {
+                println!("Error loading lights: {:?}", error);
+                self.set_panel_icon(PanelIconState {
+                    unreachable: true,
+                    ..self.panel_icon
+                });
+            }
+            Message::LoadGroups => {
+                let bridge = match get_bridge(&self.config) {
+                    Some(bridge) => bridge,
+                    None => return Task::none(),
+                };
+                return Task::perform(
+                    async move {
+                        let groups = bridge.get_all_groups().map_err(|e| e.to_string())?;
+                        Ok(groups)
+                    },
+                    |result| Action::App(Message::GroupsLoaded(result)),
+                );
+            }
+            Message::GroupsLoaded(Ok(groups)) => {
+                println!("Groups loaded: {}", groups.len());
+                let mut groups_vm: Vec<GroupVm> = groups
+                    .into_iter()
+                    .map(|group| {
+                        let mut first_light = None;
+                        if let Some(light_id) = group.lights.first() {
                             if let Some(light) =
-                                self.lights.iter_mut().find(|light| light.id == *light_id)
+                                self.lights.iter().find(|light| light.id == *light_id)
                             {
-                                light.brightness = Some(new_brightness as u8);
+                                first_light = Some(light);
                             }
                         }
+                        let color = if first_light.is_some() {
+                            first_light.unwrap().color
+                        } else {
+                            Some((0.0, 0.0, 0.0))
+                        };
+                        let brightness = if first_light.is_some() {
+                            first_light.unwrap().brightness
+                        } else {
+                            None
+                        };
 
-                        group.brightness = Some(new_brightness as u8);
+                        GroupVm {
+                            id: group.id,
+                            name: group.name.clone(),
+                            bridge_name: group.name,
+                            on: group.state.map(|state| state.any_on),
+                            brightness,
+                            color,
+                            lights: group.lights,
+                        }
+                    })
+                    .collect();
+
+                self.groups = groups_vm;
+                self.arrange_groups();
+            }
+            Message::GroupsLoaded(Err(error)) => {
+                println!("Error loading groups: {:?}", error);
+            }
+            Message::LoadScenes => {
+                let bridge = match get_bridge(&self.config) {
+                    Some(bridge) => bridge,
+                    None => return Task::none(),
+                };
+                return Task::perform(
+                    async move {
+                        let scenes = bridge.get_all_scenes().map_err(|e| e.to_string())?;
+                        Ok(scenes)
+                    },
+                    |result| Action::App(Message::ScenesLoaded(result)),
+                );
+            }
+            Message::ScenesLoaded(Ok(scenes)) => {
+                println!("Scenes loaded: {}", scenes.len());
+                let scenes_vm: Vec<SceneVm> = scenes
+                    .into_iter()
+                    .map(|scene| SceneVm {
+                        hidden: scene.recycle
+                            || scene.kind == huelib::resource::scene::Kind::LightScene,
+                        id: scene.id,
+                        name: scene.name.clone(),
+                        bridge_name: scene.name,
+                        group: scene.group.unwrap_or_else(String::new),
+                        last_updated: scene.last_updated,
+                    })
+                    .collect();
+
+                let previous_scenes = std::mem::replace(&mut self.scenes, scenes_vm);
+                self.arrange_scenes();
+
+                // Forget the palettes of scenes that no longer exist or were changed since
+                let scenes = &self.scenes;
+                self.scene_palettes.retain(|scene_id, _| {
+                    let Some(scene) = scenes.iter().find(|scene| scene.id == *scene_id) else {
+                        return false;
+                    };
+                    previous_scenes
+                        .iter()
+                        .find(|previous| previous.id == *scene_id)
+                        .is_none_or(|previous| previous.last_updated == scene.last_updated)
+                });
+
+                // Light states are only returned for individual scenes, so fetch the
+                // ones which are not cached yet
+                let mut tasks = Vec::new();
+                for scene in &self.scenes {
+                    if self.scene_palettes.contains_key(&scene.id) {
+                        continue;
+                    }
+                    let bridge = match get_bridge(&self.config) {
+                        Some(bridge) => bridge,
+                        None => return Task::none(),
+                    };
+                    // Reserve the entry so the scene is not fetched twice
+                    self.scene_palettes.insert(scene.id.clone(), Vec::new());
+
+                    let scene_id = scene.id.clone();
+                    tasks.push(Task::perform(
+                        async move {
+                            let result = bridge
+                                .get_scene(&scene_id)
+                                .map(|scene| scene_palette(&scene))
+                                .map_err(|e| e.to_string());
+                            (scene_id, result)
+                        },
+                        |(scene_id, result)| {
+                            Action::App(Message::ScenePaletteLoaded(scene_id, result))
+                        },
+                    ));
+                }
+                return Task::batch(tasks);
+            }
+            Message::ScenesLoaded(Err(error)) => {
+                println!("Error loading scenes: {:?}", error);
+            }
+            Message::ScenePaletteLoaded(scene_id, Ok(palette)) => {
+                self.scene_palettes.insert(scene_id, palette);
+            }
+            Message::ScenePaletteLoaded(scene_id, Err(error)) => {
+                println!("Error loading scene {}: {:?}", scene_id, error);
+                // Drop the reserved entry so the next scenes load retries
+                self.scene_palettes.remove(&scene_id);
+            }
+            Message::ToggleLight(light_id, new_state) => {
+                if let Some(light) = self.lights.iter_mut().find(|light| light.id == light_id) {
+                    light.on = Some(new_state);
+                }
+                // The adaptive lighting takes over again once the light is turned off
+                if !new_state {
+                    self.adaptive_overrides.remove(&light_id);
+                }
+                let bridge = match get_bridge(&self.config) {
+                    Some(bridge) => bridge,
+                    None => return Task::none(),
+                };
+                let modifier = huelib::resource::light::StateModifier::new().with_on(new_state);
+                return Task::perform(
+                    async move { bridge.set_light_state(&light_id, &modifier) },
+                    |result| {
+                        Action::App(Message::ResponsesModified(
+                            result.map_err(|e| e.to_string()),
+                        ))
+                    },
+                );
+            }
+            Message::ToggleGroup(group_id, new_state) => {
+                if let Some(group) = self.groups.iter_mut().find(|group| group.id == group_id) {
+                    println!("ToogleGroup: {}, new_state: {}", group_id, new_state);
+                    group.on = Some(new_state);
+                    group.lights.iter().for_each(|light_id| {
+                        if let Some(light) =
+                            self.lights.iter_mut().find(|light| light.id == *light_id)
+                        {
+                            light.on = Some(new_state);
+                        }
+                    });
+                }
+                let bridge = match get_bridge(&self.config) {
+                    Some(bridge) => bridge,
+                    None => return Task::none(),
+                };
+                if !new_state {
+                    for light_id in self.group_light_ids(&group_id) {
+                        self.adaptive_overrides.remove(&light_id);
+                    }
+                }
+                let modifier = huelib::resource::group::StateModifier::new().with_on(new_state);
+                return Task::perform(
+                    async move { bridge.set_group_state(&group_id, &modifier) },
+                    |result| {
+                        Action::App(Message::ResponsesModified(
+                            result.map_err(|e| e.to_string()),
+                        ))
+                    },
+                );
+            }
+            Message::ActivateScene(scene_id) => {
+                if let Some(scene) = self.scenes.iter().find(|scene| scene.id == scene_id) {
+                    // Remember the scene to list it first next time
+                    let mut recent_scenes = self.config.get_recent_scenes().to_vec();
+                    recent_scenes.retain(|id| *id != scene_id);
+                    recent_scenes.insert(0, scene_id.clone());
+                    recent_scenes.truncate(MAX_RECENT_SCENES);
+                    if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                        let _ = self.config.set_recent_scenes(&ctx, recent_scenes);
                     }
+
+                    let options = self.config.get_scene_recall_options(&scene_id);
+                    let bridge = match get_bridge(&self.config) {
+                        Some(bridge) => bridge,
+                        None => return Task::none(),
+                    };
+                    // Light scenes are not attached to a group, recall them on all the lights
+                    let group_id = if scene.group.is_empty() {
+                        "0".to_string()
+                    } else {
+                        scene.group.clone()
+                    };
+                    let light_ids = self.group_light_ids(&group_id);
+                    self.adaptive_overrides.extend(light_ids);
+                    return Task::perform(
+                        async move { scene::recall(&bridge, &scene_id, &group_id, options) },
+                        |result| {
+                            Action::App(Message::SceneActivated(result.map_err(|e| e.to_string())))
+                        },
+                    );
+                }
+                return Task::none();
+            }
+            Message::SceneActivated(Ok(responses)) => {
+                return Task::batch(vec![
+                    Task::perform(async move { Ok(responses) }, |result| Action::App(Message::ResponsesModified(result))),
+                    Task::perform(
+                        async move {
+                            // Wait for 10 seconds to reload light states to avoid intermediate values
+                            tokio::time::sleep(tokio::time::Duration::from_secs(10)).await;
+                        },
+                        |_| Action::App(Message::LoadGroups),
+                    ),
+                    Task::perform(
+                        async move {
+                            // Wait for 10 seconds to reload light states to avoid intermediate values
+                            tokio::time::sleep(tokio::time::Duration::from_secs(10)).await;
+                        },
+                        |_| Action::App(Message::LoadLights),
+                    ),
+                ]);
+            }
+            Message::SceneActivated(Err(error)) => {
+                println!("Error activating scene: {:?}", error);
+            }
+            Message::ResponsesModified(Ok(responses)) => {
+                println!("ResponsesModified: {:?}", responses);
+            }
+            Message::ResponsesModified(Err(error)) => {
+                println!("Error: {:?}", error);
+            }
+            Message::SetLightBrightness(light_id, new_brightness) => {
+                self.adaptive_overrides.insert(light_id.clone());
+                // Update the local state immediately
+                if let Some(light) = self.lights.iter_mut().find(|light| light.id == light_id) {
+                    light.brightness = Some(new_brightness as u8);
                 }
                 
                 // Increment the counter and store the value in pending
                 self.debounce_counter += 1;
                 let counter = self.debounce_counter;
-                self.pending_group_brightness.insert(group_id.clone(), (new_brightness, counter));
+                self.pending_light_brightness.insert(light_id.clone(), (new_brightness, counter));
                 
-                // Create a task that will wait 300ms then apply the change
+                // Create a task that will wait for the debounce delay then apply the change
+                let delay = self.config.get_general().debounce_delay();
                 return Task::perform(
                     async move {
-                        tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
-                        (group_id, counter)
+                        tokio::time::sleep(delay).await;
+                        (light_id, counter)
                     },
-                    |(id, cnt)| Action::App(Message::ApplyGroupBrightness(id, cnt)),
+                    |(id, cnt)| Action::App(Message::ApplyLightBrightness(id, cnt)),
                 );
             }
-            Message::ApplyGroupBrightness(group_id, counter) => {
+            Message::ApplyLightBrightness(light_id, counter) => {
                 // Check if it's still the last request
-                if let Some((brightness, current_counter)) = self.pending_group_brightness.get(&group_id) {
+                if let Some((brightness, current_counter)) = self.pending_light_brightness.get(&light_id) {
                     if *current_counter == counter {
                         // It's the last request, apply it
                         let brightness = *brightness;
-                        self.pending_group_brightness.remove(&group_id);
+                        self.pending_light_brightness.remove(&light_id);
                         
                         let bridge = match get_bridge(&self.config) {
                             Some(bridge) => bridge,
                             None => return Task::none(),
                         };
-                        let modifier = huelib::resource::group::StateModifier::new().with_brightness(
+                        let modifier = huelib::resource::light::StateModifier::new().with_brightness(
                             huelib::resource::Adjust::Override((brightness as u8).into()),
                         );
                         return Task::perform(
-                            async move { bridge.set_group_state(&group_id, &modifier) },
+                            async move { bridge.set_light_state(&light_id, &modifier) },
                             |result| {
                                 Action::App(Message::ResponsesModified(
                                     result.map_err(|e| e.to_string()),
@@ -812,58 +2531,45 @@ impl cosmic::Application for AppModel {
                     }
                 }
             }
-            Message::SetLightColor(update) => {
-                let _ = self.color_picker_model.update::<Message>(update.clone());
-                if let Some(light) = self.lights.iter_mut().find(|light| {
-                    Some(&light.id) == self.active_color_picker_item.as_ref().map(|(id, _)| id)
-                }) {
-                    match update {
-                        ColorPickerUpdate::ActiveColor(color) => {
-                            // Update the local state immediately for the UI
-                            light.color = Some(hsv_palette_to_rgb(color));
-                            
-                            // Convert the HSV values
-                            let (hue, saturation, brightness) = hsv_palette_to_hsv_lib(color);
-                            
-                            // Increment the counter and store the value in pending
-                            let light_id = light.id.clone();
-                            self.debounce_counter += 1;
-                            let counter = self.debounce_counter;
-                            self.pending_light_color.insert(light_id.clone(), ((hue, saturation, brightness), counter));
-                            
-                            // Create a task that will wait 300ms then apply the change
-                            return Task::perform(
-                                async move {
-                                    tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
-                                    (light_id, counter)
-                                },
-                                |(id, cnt)| Action::App(Message::ApplyLightColor(id, cnt)),
-                            );
-                        }
-                        _ => {}
-                    }
-                }
+            Message::SetLightColorTemperature(light_id, color_temperature, brightness) => {
+                // Increment the counter and store the value in pending
+                self.debounce_counter += 1;
+                let counter = self.debounce_counter;
+                self.pending_light_color_temperature
+                    .insert(light_id.clone(), ((color_temperature, brightness), counter));
+
+                // Create a task that will wait for the debounce delay then apply the change
+                let delay = self.config.get_general().debounce_delay();
+                return Task::perform(
+                    async move {
+                        tokio::time::sleep(delay).await;
+                        (light_id, counter)
+                    },
+                    |(id, cnt)| Action::App(Message::ApplyLightColorTemperature(id, cnt)),
+                );
             }
-            Message::ApplyLightColor(light_id, counter) => {
+            Message::ApplyLightColorTemperature(light_id, counter) => {
                 // Check if it's still the last request
-                if let Some((color_values, current_counter)) = self.pending_light_color.get(&light_id) {
+                if let Some((values, current_counter)) = self.pending_light_color_temperature.get(&light_id) {
                     if *current_counter == counter {
                         // It's the last request, apply it
-                        let (hue, saturation, brightness) = *color_values;
-                        self.pending_light_color.remove(&light_id);
-                        
+                        let (color_temperature, brightness) = *values;
+                        self.pending_light_color_temperature.remove(&light_id);
+                        self.adaptive_applied
+                            .insert(light_id.clone(), (color_temperature, brightness));
+
                         let bridge = match get_bridge(&self.config) {
                             Some(bridge) => bridge,
                             None => return Task::none(),
                         };
-                        
-                        let modifier = huelib::resource::light::StateModifier::new()
-                            .with_hue(huelib::resource::Adjust::Override(hue))
-                            .with_saturation(huelib::resource::Adjust::Override(saturation))
-                            .with_brightness(huelib::resource::Adjust::Override(brightness));
-                        
+                        let mut modifier = huelib::resource::light::StateModifier::new()
+                            .with_color_temperature(huelib::resource::Adjust::Override(color_temperature))
+                            .with_transition_time(ADAPTIVE_TRANSITION_TIME);
+                        if let Some(brightness) = brightness {
+                            modifier = modifier.with_brightness(huelib::resource::Adjust::Override(brightness));
+                        }
                         return Task::perform(
-                            async move { bridge.set_light_state(light_id, &modifier) },
+                            async move { bridge.set_light_state(&light_id, &modifier) },
                             |result| {
                                 Action::App(Message::ResponsesModified(
                                     result.map_err(|e| e.to_string()),
@@ -873,437 +2579,4187 @@ impl cosmic::Application for AppModel {
                     }
                 }
             }
-            Message::SetGroupColor(update) => {
-                let _ = self.color_picker_model.update::<Message>(update.clone());
-                if let Some(group) = self.groups.iter_mut().find(|group| {
-                    Some(&group.id) == self.active_color_picker_item.as_ref().map(|(id, _)| id)
-                }) {
-                    match update {
-                        ColorPickerUpdate::ActiveColor(color) => {
-                            // Update the local state immediately for the UI
-                            let new_color = hsv_palette_to_rgb(color);
-                            for light_id in &group.lights {
-                                if let Some(light) =
-                                    self.lights.iter_mut().find(|light| light.id == *light_id)
-                                {
-                                    light.color = Some(new_color);
-                                }
-                            }
-                            group.color = Some(new_color);
+            Message::SetAdaptiveOptions(group_id, options) => {
+                let mut adaptive_groups = self.config.get_all_adaptive_options().clone();
+                match options {
+                    Some(options) => {
+                        adaptive_groups.insert(group_id.clone(), options);
+                    }
+                    None => {
+                        adaptive_groups.remove(&group_id);
+                    }
+                }
+                if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                    let _ = self.config.set_adaptive_groups(&ctx, adaptive_groups);
+                }
 
-                            // Convert the HSV values
-                            let (hue, saturation, brightness) = hsv_palette_to_hsv_lib(color);
-                            
-                            // Increment the counter and store the value in pending
-                            let group_id = group.id.clone();
-                            self.debounce_counter += 1;
-                            let counter = self.debounce_counter;
-                            self.pending_group_color.insert(group_id.clone(), ((hue, saturation, brightness), counter));
-                            
-                            // Create a task that will wait 300ms then apply the change
-                            return Task::perform(
-                                async move {
-                                    tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
-                                    (group_id, counter)
-                                },
-                                |(id, cnt)| Action::App(Message::ApplyGroupColor(id, cnt)),
+                // Enabling the adaptive lighting takes over the lights changed by hand
+                if options.is_some() {
+                    for light_id in self.group_light_ids(&group_id) {
+                        self.adaptive_overrides.remove(&light_id);
+                        self.adaptive_applied.remove(&light_id);
+                    }
+                    return self.update(Message::AdaptiveTick);
+                }
+            }
+            Message::AdaptiveTick => {
+                if self.config.get_all_adaptive_options().is_empty() {
+                    return Task::none();
+                }
+                let bridge = match get_bridge(&self.config) {
+                    Some(bridge) => bridge,
+                    None => return Task::none(),
+                };
+                return Task::perform(
+                    async move {
+                        let groups = bridge.get_all_groups().map_err(|e| e.to_string())?;
+                        let lights = bridge.get_all_lights().map_err(|e| e.to_string())?;
+                        Ok((groups, lights))
+                    },
+                    |result| Action::App(Message::AdaptiveLightsLoaded(result)),
+                );
+            }
+            Message::AdaptiveLightsLoaded(Ok((groups, lights))) => {
+                let target = adaptive::adaptive_state(chrono::Local::now(), self.config.get_location());
+
+                let mut tasks = Vec::new();
+                for light in &lights {
+                    // The adaptive lighting takes over again once the light is turned off
+                    if light.state.on != Some(true) {
+                        self.adaptive_overrides.remove(&light.id);
+                        self.adaptive_applied.remove(&light.id);
+                        continue;
+                    }
+
+                    let Some(options) = groups
+                        .iter()
+                        .filter(|group| group.lights.contains(&light.id))
+                        .find_map(|group| self.config.get_adaptive_options(&group.id))
+                    else {
+                        continue;
+                    };
+                    // Only the lights supporting a color temperature can follow the day curve
+                    let Some(color_temperature) = light.state.color_temperature else {
+                        continue;
+                    };
+
+                    // A light which moved away from the last values sent was changed by hand
+                    if let Some((applied_color_temperature, applied_brightness)) =
+                        self.adaptive_applied.get(&light.id)
+                    {
+                        let changed = color_temperature.abs_diff(*applied_color_temperature)
+                            > ADAPTIVE_TOLERANCE as u16
+                            || applied_brightness.zip(light.state.brightness).is_some_and(
+                                |(applied, current)| applied.abs_diff(current) > ADAPTIVE_TOLERANCE,
                             );
+                        if changed {
+                            self.adaptive_overrides.insert(light.id.clone());
                         }
-                        _ => {}
+                    }
+                    if self.adaptive_overrides.contains(&light.id) {
+                        continue;
+                    }
+
+                    let brightness = options.adjust_brightness.then_some(target.brightness);
+                    let up_to_date = color_temperature.abs_diff(target.color_temperature)
+                        <= ADAPTIVE_TOLERANCE as u16
+                        && brightness
+                            .zip(light.state.brightness)
+                            .is_none_or(|(target, current)| target.abs_diff(current) <= ADAPTIVE_TOLERANCE);
+                    if !up_to_date {
+                        tasks.push(self.update(Message::SetLightColorTemperature(
+                            light.id.clone(),
+                            target.color_temperature,
+                            brightness,
+                        )));
                     }
                 }
+                return Task::batch(tasks);
             }
-            Message::ApplyGroupColor(group_id, counter) => {
-                // Check if it's still the last request
-                if let Some((color_values, current_counter)) = self.pending_group_color.get(&group_id) {
-                    if *current_counter == counter {
-                        // C'est la dernière demande, on l'applique
-                        let (hue, saturation, brightness) = *color_values;
-                        self.pending_group_color.remove(&group_id);
-                        
-                        let bridge = match get_bridge(&self.config) {
-                            Some(bridge) => bridge,
-                            None => return Task::none(),
-                        };
-                        
-                        let modifier = huelib::resource::group::StateModifier::new()
-                            .with_hue(huelib::resource::Adjust::Override(hue))
-                            .with_saturation(huelib::resource::Adjust::Override(saturation))
-                            .with_brightness(huelib::resource::Adjust::Override(brightness));
-                        
+            Message::AdaptiveLightsLoaded(Err(error)) => {
+                println!("Error loading lights for the adaptive lighting: {:?}", error);
+            }
+            Message::Logind(event) => {
+                let was_away = self.session_locked || self.session_sleeping;
+                let mut sleep_inhibitor = None;
+                match event {
+                    LogindEvent::Locked(locked) => self.session_locked = locked,
+                    LogindEvent::Sleeping(sleeping, inhibitor) => {
+                        self.session_sleeping = sleeping;
+                        sleep_inhibitor = inhibitor;
+                    }
+                }
+                let away = self.session_locked || self.session_sleeping;
+
+                // The lights come back on unlock, not on resume to the lock screen
+                if away && !was_away {
+                    let actions = self.config.get_all_lock_actions().clone();
+                    // Without lock actions there is nothing to capture, the computer suspends at once
+                    let bridge = get_bridge(&self.config).filter(|_| !actions.is_empty());
+                    if let Some(bridge) = bridge {
                         return Task::perform(
-                            async move { bridge.set_group_state(group_id, &modifier) },
+                            async move {
+                                let result = lock::apply(&bridge, &actions);
+                                // Let the computer suspend once the bridge answered
+                                if let Some(inhibitor) = sleep_inhibitor {
+                                    inhibitor.release();
+                                }
+                                result
+                            },
                             |result| {
-                                Action::App(Message::ResponsesModified(
-                                    result.map_err(|e| e.to_string()),
-                                ))
+                                Action::App(Message::LockActionsApplied(result.map_err(|e| e.to_string())))
                             },
                         );
                     }
+                } else if !away && was_away {
+                    return self.restore_lock_snapshot();
+                }
+                // The lights were already changed by the lock, or can't be
+                if let Some(inhibitor) = sleep_inhibitor {
+                    inhibitor.release();
                 }
             }
-            Message::TryToggleColorPicker => {
-                if self.color_button_rectangles.get(&0).is_none() {
-                    return Task::perform(async {}, |_| Action::App(Message::TryToggleColorPicker));
-                } else {
-                    return if let Some(p) = self.color_picker_popup.take() {
-                        self.color_button_rectangles.remove(&0);
-                        destroy_popup(p)
-                    } else {
-                        self.open_color_picker_popup()
-                    };
+            Message::LockActionsApplied(Ok(snapshot)) => {
+                self.lock_snapshot = Some(snapshot);
+                // The session may have been unlocked in the meantime
+                if !self.session_locked && !self.session_sleeping {
+                    return self.restore_lock_snapshot();
                 }
             }
-            Message::ToggleColorPicker((item_id, item_type)) => {
-                let i_id = item_id.clone();
-                let i_id_clone = i_id.clone();
-                let i_type = item_type.clone();
-                if self.active_color_picker_item == Some((i_id, i_type)) {
-                    self.active_color_picker_item = None;
-                } else {
-                    self.active_color_picker_item = Some((item_id, item_type));
+            Message::LockActionsApplied(Err(error)) => {
+                println!("Error turning the lights off on lock: {:?}", error);
+            }
+            Message::LockRestored(Ok(())) => {
+                return Task::batch(vec![
+                    Task::perform(async {}, |_| Action::App(Message::LoadLights)),
+                    Task::perform(async {}, |_| Action::App(Message::LoadGroups)),
+                ]);
+            }
+            Message::LockRestored(Err(error)) => {
+                println!("Error restoring the lights after the lock: {:?}", error);
+            }
+            Message::SetLockAction(group_id, action) => {
+                let mut lock_actions = self.config.get_all_lock_actions().clone();
+                match action {
+                    Some(action) => {
+                        lock_actions.insert(group_id, action);
+                    }
+                    None => {
+                        lock_actions.remove(&group_id);
+                    }
                 }
-                return if let Some(p) = self.color_picker_popup.take() {
-                    destroy_popup(p)
-                } else {
-                    // The RectanglesUpdated will trigger the popup opening
-                    if self.last_active_color_picker_item == Some(i_id_clone) {
-                        self.open_color_picker_popup()
-                    } else {
-                        Task::none()
+                if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                    let _ = self.config.set_lock_actions(&ctx, lock_actions);
+                }
+            }
+            Message::NotificationReceived(notification) => {
+                let light_ids: Vec<(String, NotificationAlert)> = self
+                    .config
+                    .get_notification_rules()
+                    .iter()
+                    .filter(|rule| rule.matches(&notification.app_name, notification.urgency))
+                    // A light alerted for a previous notification finishes its alert first
+                    .filter(|rule| !self.alerting_lights.contains(&rule.light_id))
+                    .map(|rule| (rule.light_id.clone(), rule.alert))
+                    .collect();
+
+                let mut tasks = Vec::new();
+                for (light_id, alert) in light_ids {
+                    let Some(bridge) = get_bridge(&self.config) else {
+                        break;
+                    };
+                    if !self.alerting_lights.insert(light_id.clone()) {
+                        continue;
                     }
+                    tasks.push(Task::perform(
+                        async move {
+                            let result = notifications::alert(&bridge, &light_id, alert).await;
+                            (light_id, result.map_err(|e| e.to_string()))
+                        },
+                        |(light_id, result)| {
+                            Action::App(Message::NotificationAlertFinished(light_id, result))
+                        },
+                    ));
+                }
+                return Task::batch(tasks);
+            }
+            Message::NotificationAlertFinished(light_id, result) => {
+                self.alerting_lights.remove(&light_id);
+                if let Err(error) = result {
+                    println!("Error alerting light {}: {:?}", light_id, error);
+                }
+            }
+            Message::NewNotificationRule => {
+                let mut editor = NotificationRuleEditor {
+                    id: None,
+                    app_name: String::new(),
+                    min_urgency: Urgency::Critical,
+                    lights: Vec::new(),
+                    light_labels: Vec::new(),
+                    light: None,
+                    flash_color: Some(0),
+                    error: None,
                 };
+                self.set_notification_rule_editor_lights(&mut editor, None);
+                self.notification_rule_editor = Some(editor);
             }
-            Message::ToggleMoreMenu => {
-                return if let Some(p) = self.more_menu_popup.take() {
-                    destroy_popup(p)
-                } else {
-                    let new_id = Id::unique();
-                    self.more_menu_popup.replace(new_id);
-                    let mut more_menu_popup_settings = self.core.applet.get_popup_settings(
-                        self.popup.unwrap(),
-                        new_id,
-                        None,
-                        None,
-                        None,
-                    );
-                    more_menu_popup_settings.positioner.size_limits = Limits::NONE
-                        .max_width(200.0)
-                        .min_width(120.0)
-                        .min_height(120.0)
-                        .max_height(200.0);
-
-                    more_menu_popup_settings.positioner.anchor = Anchor::TopRight;
-                    more_menu_popup_settings.positioner.gravity = Gravity::BottomRight;
-                    more_menu_popup_settings.positioner.offset = (100, 50);
+            Message::EditNotificationRule(rule_id) => {
+                let Some(rule) = self
+                    .config
+                    .get_notification_rules()
+                    .iter()
+                    .find(|rule| rule.id == rule_id)
+                    .cloned()
+                else {
+                    return Task::none();
+                };
+                let mut editor = NotificationRuleEditor {
+                    id: Some(rule.id),
+                    app_name: rule.app_name,
+                    min_urgency: rule.min_urgency,
+                    lights: Vec::new(),
+                    light_labels: Vec::new(),
+                    light: None,
+                    flash_color: match rule.alert {
+                        NotificationAlert::Breathe => None,
+                        NotificationAlert::Flash { hue, saturation } => Some(
+                            FLASH_COLORS
+                                .iter()
+                                .position(|color| *color == (hue, saturation))
+                                .unwrap_or(0),
+                        ),
+                    },
+                    error: None,
+                };
+                self.set_notification_rule_editor_lights(&mut editor, Some(&rule.light_id));
+                self.notification_rule_editor = Some(editor);
+            }
+            Message::NotificationRuleEditor(message) => {
+                let Some(editor) = self.notification_rule_editor.as_mut() else {
+                    return Task::none();
+                };
+                match message {
+                    NotificationRuleEditorMessage::AppName(app_name) => editor.app_name = app_name,
+                    NotificationRuleEditorMessage::MinUrgency(urgency) => editor.min_urgency = urgency,
+                    NotificationRuleEditorMessage::Light(index) => editor.light = Some(index),
+                    NotificationRuleEditorMessage::FlashColor(color) => editor.flash_color = color,
+                }
+            }
+            Message::CancelNotificationRuleEditor => {
+                self.notification_rule_editor = None;
+            }
+            Message::SaveNotificationRule => {
+                let Some(editor) = self.notification_rule_editor.as_mut() else {
+                    return Task::none();
+                };
+                let Some((light_id, _)) = editor.light.and_then(|index| editor.lights.get(index)).cloned() else {
+                    editor.error = Some(fl!("no-target-selected"));
+                    return Task::none();
+                };
+                let rule = NotificationRule {
+                    id: editor
+                        .id
+                        .clone()
+                        .unwrap_or_else(|| chrono::Utc::now().timestamp_millis().to_string()),
+                    app_name: editor.app_name.trim().to_string(),
+                    min_urgency: editor.min_urgency,
+                    light_id,
+                    alert: match editor.flash_color.and_then(|index| FLASH_COLORS.get(index)) {
+                        Some(&(hue, saturation)) => NotificationAlert::Flash { hue, saturation },
+                        None => NotificationAlert::Breathe,
+                    },
+                };
+                self.notification_rule_editor = None;
 
-                    get_popup(more_menu_popup_settings)
+                let mut rules = self.config.get_notification_rules().to_vec();
+                match rules.iter_mut().find(|existing| existing.id == rule.id) {
+                    Some(existing) => *existing = rule,
+                    None => rules.push(rule),
+                }
+                if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                    let _ = self.config.set_notification_rules(&ctx, rules);
+                }
+            }
+            Message::DeleteNotificationRule(rule_id) => {
+                let mut rules = self.config.get_notification_rules().to_vec();
+                rules.retain(|rule| rule.id != rule_id);
+                if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                    let _ = self.config.set_notification_rules(&ctx, rules);
+                }
+            }
+            Message::ServiceChanged(ServiceChange::Started(service)) => {
+                self.service = Some(service);
+            }
+            Message::ServiceChanged(ServiceChange::LightsChanged(light_ids)) => {
+                // Lights changed by a script are left alone by the adaptive lighting
+                self.adaptive_overrides.extend(light_ids);
+                return Task::batch([
+                    Task::perform(async {}, |_| Action::App(Message::LoadLights)),
+                    Task::perform(async {}, |_| Action::App(Message::LoadGroups)),
+                ]);
+            }
+            Message::OpenSettings => {
+                let close_menu = match self.more_menu_popup.take() {
+                    Some(id) => destroy_popup(id),
+                    None => Task::none(),
                 };
+                if let Some(id) = self.settings_window {
+                    return Task::batch([close_menu, cosmic::iced::window::gain_focus(id)]);
+                }
+                let (id, open) = cosmic::iced::window::open(cosmic::iced::window::Settings {
+                    size: cosmic::iced::Size::new(640.0, 720.0),
+                    min_size: Some(cosmic::iced::Size::new(480.0, 360.0)),
+                    exit_on_close_request: false,
+                    ..Default::default()
+                });
+                self.settings_window = Some(id);
+                return Task::batch([
+                    close_menu,
+                    open.map(|id| Action::App(Message::SettingsOpened(id))),
+                ]);
             }
-            Message::RectanglesUpdated(update) => match update {
-                RectangleUpdate::Init(tracker) => {
-                    self.color_button_tracker = Some(tracker);
+            Message::SettingsOpened(id) => {
+                let mut tasks = vec![self.set_window_title(fl!("settings"), id)];
+                if self.config.get_username().is_some() {
+                    tasks.push(Task::perform(async {}, |_| Action::App(Message::LoadLights)));
+                    tasks.push(Task::perform(async {}, |_| Action::App(Message::LoadGroups)));
+                    tasks.push(Task::perform(async {}, |_| Action::App(Message::LoadScenes)));
                 }
-                RectangleUpdate::Rectangle((id, rectangle)) => {
-                    self.color_button_rectangles.insert(id, rectangle);
-                    return Task::perform(async {}, |_| Action::App(Message::TryToggleColorPicker));
+                return Task::batch(tasks);
+            }
+            Message::SetBridgeIpInput(input) => {
+                self.bridge_ip_input = input;
+                self.bridge_ip_error = None;
+            }
+            Message::SaveBridgeIp => {
+                match self.bridge_ip_input.trim().parse::<IpAddr>() {
+                    Ok(bridge_ip) => {
+                        self.bridge_ip_error = None;
+                        return Task::perform(
+                            async move { config::read_bridge_id(bridge_ip) },
+                            move |result| Action::App(Message::BridgeIdRead(bridge_ip, result)),
+                        );
+                    }
+                    Err(_) => {
+                        self.bridge_ip_error = Some(fl!("invalid-bridge-ip"));
+                    }
                 }
-            },
-            Message::UnpairBridge => {
+            }
+            Message::BridgeIdRead(bridge_ip, Ok(bridge_id)) => {
                 if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
-                    let _ = self.config.set_bridge_ip(&ctx, None);
-                    let _ = self.config.set_username(&ctx, None);
-                    self.lights = Vec::new();
-                    self.groups = Vec::new();
-                    self.scenes = Vec::new();
-                    if let Some(p) = self.more_menu_popup.take() {
-                        return destroy_popup(p);
+                    let _ = self.config.set_bridge_ip(&ctx, Some(bridge_ip));
+                    // The user only stays valid when the same bridge got a new address
+                    if self.config.get_bridge_id().is_some_and(|paired_id| paired_id != bridge_id) {
+                        let _ = self.config.set_username(&ctx, None);
                     }
-                };
+                    let _ = self.config.set_bridge_id(&ctx, Some(bridge_id));
+                }
+                return Task::perform(async {}, |_| Action::App(Message::Refresh));
+            }
+            Message::BridgeIdRead(_, Err(error)) => {
+                println!("Error reading the id of the bridge: {}", error);
+                self.bridge_ip_error = Some(fl!("no-bridge-at-address"));
+            }
+            Message::SetRefreshInterval(secs) => {
+                let mut options = self.config.get_general().clone();
+                options.refresh_interval_secs = secs.clamp(MIN_REFRESH_INTERVAL_SECS, MAX_REFRESH_INTERVAL_SECS);
+                if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                    let _ = self.config.set_general(&ctx, options);
+                }
             }
+            Message::SetDebounceDelay(millis) => {
+                let mut options = self.config.get_general().clone();
+                options.debounce_millis = millis.min(MAX_DEBOUNCE_MILLIS);
+                if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                    let _ = self.config.set_general(&ctx, options);
+                }
+            }
+            Message::SetSectionVisible(section, visible) => {
+                let mut options = self.config.get_general().clone();
+                options.hidden_sections.retain(|other| *other != section);
+                if !visible {
+                    options.hidden_sections.push(section);
+                }
+                if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                    let _ = self.config.set_general(&ctx, options);
+                }
+            }
+            Message::SetItemHidden(item, hidden) => {
+                let mut hidden_items = self.config.get_hidden_items().to_vec();
+                hidden_items.retain(|other| *other != item);
+                if hidden {
+                    hidden_items.push(item);
+                }
+                if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                    let _ = self.config.set_hidden_items(&ctx, hidden_items);
+                }
+                self.update_search_results();
+            }
+            Message::ToggleEditMode => {
+                self.edit_mode = !self.edit_mode;
+                self.dragged_item = None;
+                self.update_search_results();
+            }
+            Message::StartDrag(item) => {
+                self.dragged_item = Some(item);
+            }
+            Message::CancelDrag => {
+                self.dragged_item = None;
+            }
+            Message::SetSearch(query) => {
+                self.search_query = query;
+                self.update_search_results();
+                // Enter picks the first result
+                self.selected_item = self.search_results.first().cloned();
+            }
+            Message::FocusSearch => {
+                return widget::text_input::focus(search_input_id());
+            }
+            Message::PopupKey(window, message) => {
+                // The keys pressed in the settings window or the color picker stay there
+                if self.popup != Some(window) {
+                    return Task::none();
+                }
+                return self.update(*message);
+            }
+            Message::MoveSelection(change) => {
+                let items = self.keyboard_items();
+                let position = self
+                    .selected_item
+                    .as_ref()
+                    .and_then(|selected| items.iter().position(|item| item == selected));
+                let index = match position {
+                    Some(index) => index.saturating_add_signed(change).min(items.len().saturating_sub(1)),
+                    // Start from the first row going down, from the last one going up
+                    None if change > 0 => 0,
+                    None => items.len().saturating_sub(1),
+                };
+                self.selected_item = items.get(index).cloned();
+                return self.scroll_to_selection();
+            }
+            Message::PressSelection => {
+                // Toggle the chosen light or group, or activate the chosen scene, like a pin
+                if let Some(item) = self.selected_item.clone() {
+                    return self.update(Message::PressPin(item));
+                }
+            }
+            Message::AdjustSelectionBrightness(steps) => {
+                let step = f32::from(brightness_step(self.config.get_shortcuts().brightness_step_percent));
+                let change = step * steps as f32;
+                match self.selected_item.clone() {
+                    Some(ItemRef::Light(light_id)) => {
+                        let Some(brightness) = self
+                            .lights
+                            .iter()
+                            .find(|light| light.id == light_id)
+                            .and_then(|light| light.brightness)
+                        else {
+                            return Task::none();
+                        };
+                        let brightness = (f32::from(brightness) + change).clamp(1.0, 254.0);
+                        return self.update(Message::SetLightBrightness(light_id, brightness));
+                    }
+                    Some(ItemRef::Group(group_id)) => {
+                        let Some(brightness) = self
+                            .groups
+                            .iter()
+                            .find(|group| group.id == group_id)
+                            .and_then(|group| group.brightness)
+                        else {
+                            return Task::none();
+                        };
+                        let brightness = (f32::from(brightness) + change).clamp(1.0, 254.0);
+                        return self.update(Message::SetGroupBrightness(group_id, brightness));
+                    }
+                    _ => {}
+                }
+            }
+            Message::OpenSelectionColorPicker => {
+                return match self.selected_item.clone() {
+                    Some(ItemRef::Light(light_id)) => {
+                        self.update(Message::ToggleColorPicker((light_id, "light".to_string())))
+                    }
+                    Some(ItemRef::Group(group_id)) => {
+                        self.update(Message::ToggleColorPicker((group_id, "group".to_string())))
+                    }
+                    _ => Task::none(),
+                };
+            }
+            Message::StartRenameLight(light_id) => {
+                let Some(light) = self.lights.iter().find(|light| light.id == light_id) else {
+                    return Task::none();
+                };
+                // The bridge name is edited, a local alias stays shown over it
+                self.renaming_light = Some((light_id, light.bridge_name.clone()));
+                self.light_name_error = None;
+                return widget::text_input::focus(light_name_input_id());
+            }
+            Message::SetLightNameInput(name) => {
+                if let Some((_, input)) = self.renaming_light.as_mut() {
+                    *input = name;
+                }
+            }
+            Message::SaveLightName => {
+                let Some((light_id, name)) = self.renaming_light.clone() else {
+                    return Task::none();
+                };
+                let name = name.trim().to_string();
+                if name.is_empty() {
+                    self.light_name_error = Some(fl!("light-name-empty"));
+                    return Task::none();
+                }
+                let bridge = match get_bridge(&self.config) {
+                    Some(bridge) => bridge,
+                    None => return Task::none(),
+                };
+                return Task::perform(
+                    async move {
+                        let modifier = huelib::resource::light::AttributeModifier::new().with_name(name.clone());
+                        bridge
+                            .set_light_attribute(&light_id, &modifier)
+                            .map_err(|e| e.to_string())?;
+                        Ok((light_id, name))
+                    },
+                    |result| Action::App(Message::LightRenamed(result)),
+                );
+            }
+            Message::CancelRenameLight => {
+                self.renaming_light = None;
+                self.light_name_error = None;
+            }
+            Message::LightRenamed(Ok((light_id, name))) => {
+                if let Some(light) = self.lights.iter_mut().find(|light| light.id == light_id) {
+                    light.bridge_name = name;
+                }
+                self.renaming_light = None;
+                self.light_name_error = None;
+                // Move the light to its new place in the list
+                self.arrange_lights();
+            }
+            Message::LightRenamed(Err(error)) => {
+                println!("Error renaming the light: {}", error);
+                self.light_name_error = Some(fl!("error", error = error));
+            }
+            Message::ActivateSceneNumber(index) => {
+                let scene_id = self.listed_scenes().get(index).map(|scene| scene.id.clone());
+                if let Some(scene_id) = scene_id {
+                    return self.update(Message::ActivateScene(scene_id));
+                }
+            }
+            Message::CloseTopPopup => {
+                // Close the nested popups and menus first, then the popup itself
+                if let Some(p) = self.color_picker_popup.take() {
+                    self.active_color_picker_item = None;
+                    return destroy_popup(p);
+                }
+                if let Some(p) = self.more_menu_popup.take() {
+                    return destroy_popup(p);
+                }
+                if self.renaming_light.is_some() {
+                    return self.update(Message::CancelRenameLight);
+                }
+                if self.row_menu.is_some() || self.scene_options_expanded.is_some() {
+                    self.row_menu = None;
+                    self.scene_options_expanded = None;
+                    return Task::none();
+                }
+                if let Some(p) = self.popup.take() {
+                    return destroy_popup(p);
+                }
+            }
+            Message::DropItem(target) => {
+                let Some(dragged) = self.dragged_item.take() else {
+                    return Task::none();
+                };
+                if dragged == target
+                    || std::mem::discriminant(&dragged) != std::mem::discriminant(&target)
+                {
+                    return Task::none();
+                }
+
+                // The displayed order of the dragged kind of items, with the dragged one moved
+                let mut items: Vec<ItemRef> = match &target {
+                    ItemRef::Light(_) => self.lights.iter().map(|light| ItemRef::Light(light.id.clone())).collect(),
+                    ItemRef::Group(_) => self.groups.iter().map(|group| ItemRef::Group(group.id.clone())).collect(),
+                    ItemRef::Scene(_) => self.scenes.iter().map(|scene| ItemRef::Scene(scene.id.clone())).collect(),
+                };
+                let (Some(from), Some(to)) = (
+                    items.iter().position(|item| *item == dragged),
+                    items.iter().position(|item| *item == target),
+                ) else {
+                    return Task::none();
+                };
+                // Dropped after the target when moving down, before it when moving up
+                let dragged = items.remove(from);
+                items.insert(to, dragged);
+
+                let mut item_order: Vec<ItemRef> = self
+                    .config
+                    .get_item_order()
+                    .iter()
+                    .filter(|item| std::mem::discriminant(*item) != std::mem::discriminant(&target))
+                    .cloned()
+                    .collect();
+                item_order.extend(items);
+                if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                    let _ = self.config.set_item_order(&ctx, item_order);
+                }
+                match target {
+                    ItemRef::Light(_) => self.arrange_lights(),
+                    ItemRef::Group(_) => self.arrange_groups(),
+                    ItemRef::Scene(_) => self.arrange_scenes(),
+                }
+            }
+            Message::SetAlias(item, alias) => {
+                // Increment the counter and store the value in pending
+                self.debounce_counter += 1;
+                let counter = self.debounce_counter;
+                self.pending_aliases.insert(item.clone(), (alias, counter));
+
+                // Create a task that will wait for the debounce delay then save the change
+                let delay = self.config.get_general().debounce_delay();
+                return Task::perform(
+                    async move {
+                        tokio::time::sleep(delay).await;
+                        (item, counter)
+                    },
+                    |(item, counter)| Action::App(Message::ApplyAlias(item, counter)),
+                );
+            }
+            Message::ApplyAlias(item, counter) => {
+                // Check if it's still the last request
+                if self.pending_aliases.get(&item).is_none_or(|(_, current)| *current != counter) {
+                    return Task::none();
+                }
+                let Some((alias, _)) = self.pending_aliases.remove(&item) else {
+                    return Task::none();
+                };
+                let mut aliases = self.config.get_aliases().clone();
+                let alias = alias.trim();
+                if alias.is_empty() {
+                    aliases.remove(&item);
+                } else {
+                    aliases.insert(item.clone(), alias.to_string());
+                }
+                if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                    let _ = self.config.set_aliases(&ctx, aliases);
+                }
+                match item {
+                    ItemRef::Light(_) => self.arrange_lights(),
+                    ItemRef::Group(_) => self.arrange_groups(),
+                    ItemRef::Scene(_) => self.arrange_scenes(),
+                }
+            }
+            Message::Refresh => {
+                let mut tasks = vec![Task::perform(async {}, |_| Action::App(Message::RefreshPanelIcon))];
+                // Reload the lists while they are shown
+                if self.popup.is_some() || self.settings_window.is_some() {
+                    tasks.push(Task::perform(async {}, |_| Action::App(Message::LoadLights)));
+                    tasks.push(Task::perform(async {}, |_| Action::App(Message::LoadGroups)));
+                }
+                // Signal the changes made elsewhere on the D-Bus interface
+                if let Some(service) = &self.service {
+                    service.signal_changes();
+                }
+                return Task::batch(tasks);
+            }
+            Message::RefreshPanelIcon => {
+                let bridge = match get_bridge(&self.config) {
+                    Some(bridge) => bridge,
+                    None => return Task::none(),
+                };
+                let group_id = self.config.get_shortcuts().default_group.clone();
+
+                // The pins of the panel show the state of their lights and groups
+                let load_pins = if self.panel_has_room_for_pins() && !self.config.get_pins().is_empty() {
+                    Task::batch([
+                        Task::perform(async {}, |_| Action::App(Message::LoadLights)),
+                        Task::perform(async {}, |_| Action::App(Message::LoadGroups)),
+                        Task::perform(async {}, |_| Action::App(Message::LoadScenes)),
+                    ])
+                } else {
+                    Task::none()
+                };
+                let load_icon = Task::perform(
+                    async move {
+                        let lights = bridge.get_all_lights().map_err(|e| e.to_string())?;
+                        let group_lights = match group_id {
+                            Some(group_id) => bridge
+                                .get_group(&group_id)
+                                .map(|group| group.lights)
+                                .unwrap_or_default(),
+                            None => Vec::new(),
+                        };
+                        Ok((lights, group_lights))
+                    },
+                    |result| Action::App(Message::PanelIconLoaded(result)),
+                );
+                return Task::batch([load_icon, load_pins]);
+            }
+            Message::PanelIconLoaded(Ok((lights, group_lights))) => {
+                // Tint with the color of the brightest light of the default group, whatever its
+                // color mode
+                let tint = self
+                    .config
+                    .get_tint_panel_icon()
+                    .then(|| {
+                        lights
+                            .iter()
+                            .filter(|light| group_lights.contains(&light.id))
+                            .filter(|light| light.state.on == Some(true))
+                            .max_by_key(|light| light.state.brightness)
+                    })
+                    .flatten()
+                    .and_then(|light| light_tint(&light.state));
+                self.set_panel_icon(PanelIconState {
+                    on: lights.iter().any(|light| light.state.on == Some(true)),
+                    tint,
+                    unreachable: false,
+                    ..self.panel_icon
+                });
+                self.last_refresh = Some((chrono::Local::now(), None));
+            }
+            Message::PanelIconLoaded(Err(error)) => {
+                println!("Error refreshing the panel icon: {}", error);
+                self.last_refresh = Some((chrono::Local::now(), Some(error)));
+                self.set_panel_icon(PanelIconState {
+                    unreachable: true,
+                    ..self.panel_icon
+                });
+            }
+            Message::SetTintPanelIcon(tint) => {
+                if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                    let _ = self.config.set_tint_panel_icon(&ctx, tint);
+                }
+                return Task::perform(async {}, |_| Action::App(Message::RefreshPanelIcon));
+            }
+            Message::SetPinned(pin, pinned) => {
+                let mut pins = self.config.get_pins().to_vec();
+                pins.retain(|other| *other != pin);
+                if pinned {
+                    if pins.len() >= MAX_PINS {
+                        println!("Cannot pin more than {} items", MAX_PINS);
+                        return Task::none();
+                    }
+                    pins.push(pin);
+                }
+                if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                    let _ = self.config.set_pins(&ctx, pins);
+                }
+            }
+            Message::PressPin(pin) => {
+                return match pin {
+                    ItemRef::Light(light_id) => {
+                        let Some(light) = self.lights.iter().find(|light| light.id == light_id) else {
+                            return Task::none();
+                        };
+                        let on = light.on.unwrap_or(false);
+                        self.update(Message::ToggleLight(light_id, !on))
+                    }
+                    ItemRef::Group(group_id) => {
+                        let Some(group) = self.groups.iter().find(|group| group.id == group_id) else {
+                            return Task::none();
+                        };
+                        let on = group.on.unwrap_or(false);
+                        self.update(Message::ToggleGroup(group_id, !on))
+                    }
+                    ItemRef::Scene(scene_id) => self.update(Message::ActivateScene(scene_id)),
+                };
+            }
+            Message::SetShowPinsInPanel(show) => {
+                if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                    let _ = self.config.set_show_pins_in_panel(&ctx, show);
+                }
+                return Task::perform(async {}, |_| Action::App(Message::RefreshPanelIcon));
+            }
+            Message::PanelScrolled(delta) => {
+                // Scrolling up brightens, a mouse wheel notch or some touchpad travel makes a step
+                self.panel_scroll += match delta {
+                    ScrollDelta::Lines { y, .. } => y,
+                    ScrollDelta::Pixels { y, .. } => y / SCROLL_PIXELS_PER_STEP,
+                };
+                let steps = self.panel_scroll.trunc();
+                self.panel_scroll -= steps;
+                let steps = steps as i32;
+                let Some(group_id) = self.config.get_shortcuts().default_group.clone() else {
+                    return Task::none();
+                };
+                if steps == 0 {
+                    return Task::none();
+                }
+                let light_ids = self.group_light_ids(&group_id);
+                self.adaptive_overrides.extend(light_ids);
+
+                // Show the expected level right away, the bridge tells the real one afterwards
+                let step = brightness_step(self.config.get_shortcuts().brightness_step_percent);
+                let mut show_level = Task::none();
+                if let Some(level) = self.panel_level.as_mut().filter(|level| level.group_id == group_id) {
+                    let change = steps.saturating_mul(i32::from(step));
+                    level.brightness = (i32::from(level.brightness) + change).clamp(1, 254) as u8;
+                    level.on |= steps > 0;
+                    show_level = self.show_panel_level();
+                }
+
+                // Increment the counter and add the steps to the pending ones
+                self.debounce_counter += 1;
+                let counter = self.debounce_counter;
+                let pending_steps = self.pending_panel_steps.map_or(0, |(steps, _)| steps);
+                self.pending_panel_steps = Some((pending_steps + steps, counter));
+
+                // Create a task that will wait for the debounce delay then apply the change
+                let delay = self.config.get_general().debounce_delay();
+                return Task::batch([
+                    show_level,
+                    Task::perform(
+                        async move {
+                            tokio::time::sleep(delay).await;
+                            counter
+                        },
+                        |counter| Action::App(Message::ApplyPanelBrightness(counter)),
+                    ),
+                ]);
+            }
+            Message::ApplyPanelBrightness(counter) => {
+                // Check if it's still the last request
+                let Some((steps, current_counter)) = self.pending_panel_steps else {
+                    return Task::none();
+                };
+                if current_counter != counter {
+                    return Task::none();
+                }
+                self.pending_panel_steps = None;
+
+                let Some(group_id) = self.config.get_shortcuts().default_group.clone() else {
+                    return Task::none();
+                };
+                let bridge = match get_bridge(&self.config) {
+                    Some(bridge) => bridge,
+                    None => return Task::none(),
+                };
+                let step = brightness_step(self.config.get_shortcuts().brightness_step_percent);
+                let change = (steps.unsigned_abs() * u32::from(step)).min(254) as u8;
+                let modifier = if steps > 0 {
+                    // Brightening a group which is off turns it on
+                    huelib::resource::group::StateModifier::new()
+                        .with_on(true)
+                        .with_brightness(huelib::resource::Adjust::Increment(change))
+                } else {
+                    huelib::resource::group::StateModifier::new()
+                        .with_brightness(huelib::resource::Adjust::Decrement(change))
+                };
+                return Task::perform(
+                    async move {
+                        bridge.set_group_state(&group_id, &modifier)?;
+                        panel_group_level(&bridge, &group_id)
+                    },
+                    |result| Action::App(Message::PanelGroupChanged(result.map_err(|e| e.to_string()))),
+                );
+            }
+            Message::TogglePanelGroup => {
+                let Some(group_id) = self.config.get_shortcuts().default_group.clone() else {
+                    return Task::none();
+                };
+                let bridge = match get_bridge(&self.config) {
+                    Some(bridge) => bridge,
+                    None => return Task::none(),
+                };
+                return Task::perform(
+                    async move {
+                        // Turn the group off if one of its lights is on, like the command-line tool
+                        let group = bridge.get_group(&group_id)?;
+                        let on = !group.state.is_some_and(|state| state.any_on);
+                        let modifier = huelib::resource::group::StateModifier::new().with_on(on);
+                        bridge.set_group_state(&group_id, &modifier)?;
+                        panel_group_level(&bridge, &group_id)
+                    },
+                    |result| Action::App(Message::PanelGroupChanged(result.map_err(|e| e.to_string()))),
+                );
+            }
+            Message::PanelGroupChanged(Ok(level)) => {
+                // Keep the lists of the popup in sync
+                if let Some(group) = self.groups.iter_mut().find(|group| group.id == level.group_id) {
+                    group.on = Some(level.on);
+                    if group.brightness.is_some() {
+                        group.brightness = Some(level.brightness);
+                    }
+                    for light_id in &group.lights {
+                        if let Some(light) = self.lights.iter_mut().find(|light| light.id == *light_id) {
+                            light.on = Some(level.on);
+                        }
+                    }
+                }
+                if !level.on {
+                    for light_id in self.group_light_ids(&level.group_id) {
+                        self.adaptive_overrides.remove(&light_id);
+                    }
+                }
+                self.panel_level = Some(level);
+                return Task::batch([
+                    self.show_panel_level(),
+                    Task::perform(async {}, |_| Action::App(Message::RefreshPanelIcon)),
+                ]);
+            }
+            Message::PanelGroupChanged(Err(error)) => {
+                println!("Error changing the default group: {}", error);
+                self.set_panel_icon(PanelIconState {
+                    unreachable: true,
+                    ..self.panel_icon
+                });
+            }
+            Message::HidePanelLevel(counter) => {
+                if counter == self.level_counter {
+                    if let Some(id) = self.level_popup.take() {
+                        return destroy_popup(id);
+                    }
+                }
+            }
+            Message::SetShortcutDefaultGroup(index) => {
+                let Some(group) = self.groups.get(index) else {
+                    return Task::none();
+                };
+                let mut options = self.config.get_shortcuts().clone();
+                options.default_group = Some(group.id.clone());
+                if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                    let _ = self.config.set_shortcuts(&ctx, options);
+                }
+            }
+            Message::SetBrightnessStep(percent) => {
+                let mut options = self.config.get_shortcuts().clone();
+                options.brightness_step_percent = percent.clamp(1, 50);
+                if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                    let _ = self.config.set_shortcuts(&ctx, options);
+                }
+            }
+            Message::SetColorTemperatureStep(mireds) => {
+                let mut options = self.config.get_shortcuts().clone();
+                options.color_temperature_step = mireds.clamp(5, 150);
+                if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                    let _ = self.config.set_shortcuts(&ctx, options);
+                }
+            }
+            Message::StartFocusTimer => {
+                let minutes = self.config.get_focus_timer().work_minutes;
+                self.focus_timer = Some(FocusTimer {
+                    phase: FocusPhase::Work,
+                    ends_at: Instant::now() + Duration::from_secs(u64::from(minutes) * 60),
+                });
+                return self.start_focus_light();
+            }
+            Message::StopFocusTimer => {
+                self.focus_timer = None;
+                return self.restore_focus_snapshot();
+            }
+            Message::SkipFocusPhase => {
+                if let Some(timer) = self.focus_timer.as_mut() {
+                    timer.ends_at = Instant::now();
+                }
+                return Task::perform(async {}, |_| Action::App(Message::FocusTick));
+            }
+            Message::FocusTick => {
+                let Some(timer) = self.focus_timer.as_mut() else {
+                    return Task::none();
+                };
+                let now = Instant::now();
+                if now < timer.ends_at {
+                    return Task::none();
+                }
+
+                let options = self.config.get_focus_timer().clone();
+                let minutes = match timer.phase {
+                    FocusPhase::Work => {
+                        timer.phase = FocusPhase::Break;
+                        options.break_minutes
+                    }
+                    FocusPhase::Break => {
+                        timer.phase = FocusPhase::Work;
+                        options.work_minutes
+                    }
+                };
+                timer.ends_at = now + Duration::from_secs(u64::from(minutes) * 60);
+                let phase = timer.phase;
+
+                // A work session is over
+                if phase == FocusPhase::Break {
+                    let mut sessions = self.config.get_focus_sessions().clone();
+                    *sessions.entry(chrono::Local::now().format("%Y-%m-%d").to_string()).or_default() += 1;
+                    if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                        let _ = self.config.set_focus_sessions(&ctx, sessions);
+                    }
+                }
+
+                let Some(light_id) = options.light_id else {
+                    return Task::none();
+                };
+                let bridge = match get_bridge(&self.config) {
+                    Some(bridge) => bridge,
+                    None => return Task::none(),
+                };
+                return Task::perform(
+                    async move { focus::show_phase(&bridge, &light_id, phase, options.signal).await },
+                    |result| Action::App(Message::FocusPhaseShown(result.map_err(|e| e.to_string()))),
+                );
+            }
+            Message::FocusStarted(Ok(snapshot)) => {
+                self.focus_snapshot = snapshot;
+                // The timer may have been stopped in the meantime
+                if self.focus_timer.is_none() {
+                    return self.restore_focus_snapshot();
+                }
+            }
+            Message::FocusStarted(Err(error)) => {
+                println!("Error showing the focus timer with the light: {:?}", error);
+            }
+            Message::FocusPhaseShown(Ok(())) => {}
+            Message::FocusPhaseShown(Err(error)) => {
+                println!("Error showing the focus phase with the light: {:?}", error);
+            }
+            Message::FocusRestored(Ok(())) => {}
+            Message::FocusRestored(Err(error)) => {
+                println!("Error restoring the focus light: {:?}", error);
+            }
+            Message::SetFocusLight(index) => {
+                let Some(light) = self.lights.get(index) else {
+                    return Task::none();
+                };
+                let mut options = self.config.get_focus_timer().clone();
+                options.light_id = Some(light.id.clone());
+                // Give the previous light back before showing the timer with the new one
+                let restore = self.restore_focus_snapshot();
+                if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                    let _ = self.config.set_focus_timer(&ctx, options);
+                }
+                return restore.chain(self.start_focus_light());
+            }
+            Message::SetFocusSignal(signal) => {
+                let mut options = self.config.get_focus_timer().clone();
+                options.signal = signal;
+                let restore = self.restore_focus_snapshot();
+                if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                    let _ = self.config.set_focus_timer(&ctx, options);
+                }
+                return restore.chain(self.start_focus_light());
+            }
+            Message::SetFocusWorkMinutes(minutes) => {
+                // The running phase keeps its duration
+                let mut options = self.config.get_focus_timer().clone();
+                options.work_minutes = minutes.clamp(1, MAX_FOCUS_MINUTES);
+                if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                    let _ = self.config.set_focus_timer(&ctx, options);
+                }
+            }
+            Message::SetFocusBreakMinutes(minutes) => {
+                let mut options = self.config.get_focus_timer().clone();
+                options.break_minutes = minutes.clamp(1, MAX_FOCUS_MINUTES);
+                if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                    let _ = self.config.set_focus_timer(&ctx, options);
+                }
+            }
+            Message::CalendarTick => {
+                let Some(calendar_path) = self
+                    .config
+                    .get_busy_light()
+                    .and_then(|options| options.calendar_path.clone())
+                else {
+                    return Task::none();
+                };
+                return Task::perform(
+                    async move {
+                        let events = calendar::load(Path::new(&calendar_path))?;
+                        Ok(calendar::current_event(&events, &chrono::Local::now()))
+                    },
+                    |result: std::io::Result<Option<calendar::Occurrence>>| {
+                        Action::App(Message::CalendarLoaded(result.map_err(|e| e.to_string())))
+                    },
+                );
+            }
+            Message::CalendarLoaded(Ok(event)) => {
+                // A new event, or the end of one, takes over the state set by hand
+                let same_event = match (&event, &self.calendar_event) {
+                    (Some(event), Some(current)) => event.is_same(current),
+                    (None, None) => true,
+                    _ => false,
+                };
+                if !same_event {
+                    self.busy_override = None;
+                }
+                self.calendar_event = event;
+                return self.update_busy_light();
+            }
+            Message::CalendarLoaded(Err(error)) => {
+                println!("Error reading the calendar: {:?}", error);
+            }
+            Message::SetBusy(busy) => {
+                self.busy_override = Some(busy);
+                return self.update_busy_light();
+            }
+            Message::BusyLightSet(Ok(snapshot)) => {
+                self.busy_snapshot = Some(snapshot);
+                // The user may have become available in the meantime
+                if !self.busy_light_on {
+                    return self.restore_busy_snapshot();
+                }
+            }
+            Message::BusyLightSet(Err(error)) => {
+                println!("Error turning the busy light on: {:?}", error);
+                self.busy_light_on = false;
+            }
+            Message::BusyLightRestored(Ok(())) => {}
+            Message::BusyLightRestored(Err(error)) => {
+                println!("Error restoring the busy light: {:?}", error);
+            }
+            Message::EditBusyLight => {
+                let options = self.config.get_busy_light();
+                let lights: Vec<(String, String)> = self
+                    .lights
+                    .iter()
+                    .map(|light| (light.id.clone(), light.name.clone()))
+                    .collect();
+                self.busy_light_editor = Some(BusyLightEditor {
+                    calendar_path: options
+                        .and_then(|options| options.calendar_path.clone())
+                        .unwrap_or_default(),
+                    light_labels: lights.iter().map(|(_, name)| name.clone()).collect(),
+                    light: options.and_then(|options| lights.iter().position(|(id, _)| *id == options.light_id)),
+                    lights,
+                    error: None,
+                });
+            }
+            Message::BusyLightEditor(message) => {
+                let Some(editor) = self.busy_light_editor.as_mut() else {
+                    return Task::none();
+                };
+                match message {
+                    BusyLightEditorMessage::CalendarPath(calendar_path) => editor.calendar_path = calendar_path,
+                    BusyLightEditorMessage::Light(index) => editor.light = Some(index),
+                }
+            }
+            Message::CancelBusyLightEditor => {
+                self.busy_light_editor = None;
+            }
+            Message::SaveBusyLight => {
+                let Some(editor) = self.busy_light_editor.as_mut() else {
+                    return Task::none();
+                };
+                let Some((light_id, _)) = editor.light.and_then(|index| editor.lights.get(index)).cloned() else {
+                    editor.error = Some(fl!("no-target-selected"));
+                    return Task::none();
+                };
+                let calendar_path = editor.calendar_path.trim();
+                let options = BusyLightOptions {
+                    light_id,
+                    calendar_path: (!calendar_path.is_empty()).then(|| calendar_path.to_string()),
+                };
+                self.busy_light_editor = None;
+
+                let mut tasks = Vec::new();
+                // Give the previous light back before turning the new one red
+                if self.config.get_busy_light().is_some_and(|previous| previous.light_id != options.light_id)
+                    && self.busy_light_on
+                {
+                    self.busy_light_on = false;
+                    tasks.push(self.restore_busy_snapshot());
+                }
+                let has_calendar = options.calendar_path.is_some();
+                if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                    let _ = self.config.set_busy_light(&ctx, Some(options));
+                }
+                if has_calendar {
+                    tasks.push(Task::perform(async {}, |_| Action::App(Message::CalendarTick)));
+                } else {
+                    self.calendar_event = None;
+                }
+                tasks.push(self.update_busy_light());
+                return Task::batch(tasks);
+            }
+            Message::RemoveBusyLight => {
+                self.busy_light_editor = None;
+                self.calendar_event = None;
+                self.busy_override = None;
+                self.busy_light_on = false;
+                if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                    let _ = self.config.set_busy_light(&ctx, None);
+                }
+                return self.restore_busy_snapshot();
+            }
+            Message::IdleChanged(IdleEvent::Unavailable) => {
+                self.idle_unavailable = true;
+            }
+            Message::IdleChanged(IdleEvent::Idle(idle)) => {
+                self.idle_unavailable = false;
+                let Some(options) = self.config.get_idle_dim().cloned() else {
+                    return Task::none();
+                };
+
+                if idle && !self.idle_dimmed {
+                    let bridge = match get_bridge(&self.config) {
+                        Some(bridge) => bridge,
+                        None => return Task::none(),
+                    };
+                    self.idle_dimmed = true;
+                    return Task::perform(
+                        async move { idle::dim(&bridge, &options.group_id, options.brightness_percent) },
+                        |result| Action::App(Message::IdleDimmed(result.map_err(|e| e.to_string()))),
+                    );
+                } else if !idle && self.idle_dimmed {
+                    self.idle_dimmed = false;
+                    return self.restore_idle_snapshot();
+                }
+            }
+            Message::IdleDimmed(Ok(snapshot)) => {
+                self.idle_snapshot = Some(snapshot);
+                // The user may have come back in the meantime
+                if !self.idle_dimmed {
+                    return self.restore_idle_snapshot();
+                }
+            }
+            Message::IdleDimmed(Err(error)) => {
+                println!("Error dimming the idle group: {:?}", error);
+                self.idle_dimmed = false;
+            }
+            Message::IdleRestored(Ok(())) => {}
+            Message::IdleRestored(Err(error)) => {
+                println!("Error restoring the idle group: {:?}", error);
+            }
+            Message::SetIdleDim(options) => {
+                // Increment the counter and store the value in pending
+                self.debounce_counter += 1;
+                let counter = self.debounce_counter;
+                self.pending_idle_dim = Some((options, counter));
+
+                // Create a task that will wait for the debounce delay then save the change
+                let delay = self.config.get_general().debounce_delay();
+                return Task::perform(
+                    async move {
+                        tokio::time::sleep(delay).await;
+                        counter
+                    },
+                    |cnt| Action::App(Message::ApplyIdleDim(cnt)),
+                );
+            }
+            Message::ApplyIdleDim(counter) => {
+                // Check if it's still the last request
+                if let Some((options, current_counter)) = &self.pending_idle_dim {
+                    if *current_counter == counter {
+                        let options = options.clone();
+                        self.pending_idle_dim = None;
+                        let group_changed = self.config.get_idle_dim().map(|options| &options.group_id)
+                            != options.as_ref().map(|options| &options.group_id);
+                        if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                            let _ = self.config.set_idle_dim(&ctx, options);
+                        }
+                        // Give the dimmed lights back when the idle group changes
+                        if group_changed && self.idle_dimmed {
+                            self.idle_dimmed = false;
+                            return self.restore_idle_snapshot();
+                        }
+                    }
+                }
+            }
+            Message::SetGroupBrightness(group_id, new_brightness) => {
+                let light_ids = self.group_light_ids(&group_id);
+                self.adaptive_overrides.extend(light_ids);
+                // Update the local state immediately
+                if let Some(group) = self.groups.iter_mut().find(|group| group.id == group_id) {
+                    if group.brightness.is_some() {
+                        // Find all lights in the group and set their brightness to the new brightness
+                        for light_id in &group.lights {
+                            if let Some(light) =
+                                self.lights.iter_mut().find(|light| light.id == *light_id)
+                            {
+                                light.brightness = Some(new_brightness as u8);
+                            }
+                        }
+
+                        group.brightness = Some(new_brightness as u8);
+                    }
+                }
+                
+                // Increment the counter and store the value in pending
+                self.debounce_counter += 1;
+                let counter = self.debounce_counter;
+                self.pending_group_brightness.insert(group_id.clone(), (new_brightness, counter));
+                
+                // Create a task that will wait for the debounce delay then apply the change
+                let delay = self.config.get_general().debounce_delay();
+                return Task::perform(
+                    async move {
+                        tokio::time::sleep(delay).await;
+                        (group_id, counter)
+                    },
+                    |(id, cnt)| Action::App(Message::ApplyGroupBrightness(id, cnt)),
+                );
+            }
+            Message::ApplyGroupBrightness(group_id, counter) => {
+                // Check if it's still the last request
+                if let Some((brightness, current_counter)) = self.pending_group_brightness.get(&group_id) {
+                    if *current_counter == counter {
+                        // It's the last request, apply it
+                        let brightness = *brightness;
+                        self.pending_group_brightness.remove(&group_id);
+                        
+                        let bridge = match get_bridge(&self.config) {
+                            Some(bridge) => bridge,
+                            None => return Task::none(),
+                        };
+                        let modifier = huelib::resource::group::StateModifier::new().with_brightness(
+                            huelib::resource::Adjust::Override((brightness as u8).into()),
+                        );
+                        return Task::perform(
+                            async move { bridge.set_group_state(&group_id, &modifier) },
+                            |result| {
+                                Action::App(Message::ResponsesModified(
+                                    result.map_err(|e| e.to_string()),
+                                ))
+                            },
+                        );
+                    }
+                }
+            }
+            Message::SetLightColor(update) => {
+                let _ = self.color_picker_model.update::<Message>(update.clone());
+                if let Some(light) = self.lights.iter_mut().find(|light| {
+                    Some(&light.id) == self.active_color_picker_item.as_ref().map(|(id, _)| id)
+                }) {
+                    match update {
+                        ColorPickerUpdate::ActiveColor(color) => {
+                            // Update the local state immediately for the UI
+                            light.color = Some(hsv_palette_to_rgb(color));
+                            
+                            // Convert the HSV values
+                            let (hue, saturation, brightness) = hsv_palette_to_hsv_lib(color);
+                            
+                            // Increment the counter and store the value in pending
+                            let light_id = light.id.clone();
+                            self.adaptive_overrides.insert(light_id.clone());
+                            self.debounce_counter += 1;
+                            let counter = self.debounce_counter;
+                            self.pending_light_color.insert(light_id.clone(), ((hue, saturation, brightness), counter));
+                            
+                            // Create a task that will wait for the debounce delay then apply the change
+                            let delay = self.config.get_general().debounce_delay();
+                            return Task::perform(
+                                async move {
+                                    tokio::time::sleep(delay).await;
+                                    (light_id, counter)
+                                },
+                                |(id, cnt)| Action::App(Message::ApplyLightColor(id, cnt)),
+                            );
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Message::ApplyLightColor(light_id, counter) => {
+                // Check if it's still the last request
+                if let Some((color_values, current_counter)) = self.pending_light_color.get(&light_id) {
+                    if *current_counter == counter {
+                        // It's the last request, apply it
+                        let (hue, saturation, brightness) = *color_values;
+                        self.pending_light_color.remove(&light_id);
+                        
+                        let bridge = match get_bridge(&self.config) {
+                            Some(bridge) => bridge,
+                            None => return Task::none(),
+                        };
+                        
+                        let modifier = huelib::resource::light::StateModifier::new()
+                            .with_hue(huelib::resource::Adjust::Override(hue))
+                            .with_saturation(huelib::resource::Adjust::Override(saturation))
+                            .with_brightness(huelib::resource::Adjust::Override(brightness));
+                        
+                        return Task::perform(
+                            async move { bridge.set_light_state(light_id, &modifier) },
+                            |result| {
+                                Action::App(Message::ResponsesModified(
+                                    result.map_err(|e| e.to_string()),
+                                ))
+                            },
+                        );
+                    }
+                }
+            }
+            Message::SetGroupColor(update) => {
+                let _ = self.color_picker_model.update::<Message>(update.clone());
+                if let Some(group) = self.groups.iter_mut().find(|group| {
+                    Some(&group.id) == self.active_color_picker_item.as_ref().map(|(id, _)| id)
+                }) {
+                    match update {
+                        ColorPickerUpdate::ActiveColor(color) => {
+                            // Update the local state immediately for the UI
+                            let new_color = hsv_palette_to_rgb(color);
+                            for light_id in &group.lights {
+                                if let Some(light) =
+                                    self.lights.iter_mut().find(|light| light.id == *light_id)
+                                {
+                                    light.color = Some(new_color);
+                                }
+                            }
+                            group.color = Some(new_color);
+                            self.adaptive_overrides.extend(group.lights.iter().cloned());
+
+                            // Convert the HSV values
+                            let (hue, saturation, brightness) = hsv_palette_to_hsv_lib(color);
+                            
+                            // Increment the counter and store the value in pending
+                            let group_id = group.id.clone();
+                            self.debounce_counter += 1;
+                            let counter = self.debounce_counter;
+                            self.pending_group_color.insert(group_id.clone(), ((hue, saturation, brightness), counter));
+                            
+                            // Create a task that will wait for the debounce delay then apply the change
+                            let delay = self.config.get_general().debounce_delay();
+                            return Task::perform(
+                                async move {
+                                    tokio::time::sleep(delay).await;
+                                    (group_id, counter)
+                                },
+                                |(id, cnt)| Action::App(Message::ApplyGroupColor(id, cnt)),
+                            );
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Message::ApplyGroupColor(group_id, counter) => {
+                // Check if it's still the last request
+                if let Some((color_values, current_counter)) = self.pending_group_color.get(&group_id) {
+                    if *current_counter == counter {
+                        // C'est la dernière demande, on l'applique
+                        let (hue, saturation, brightness) = *color_values;
+                        self.pending_group_color.remove(&group_id);
+                        
+                        let bridge = match get_bridge(&self.config) {
+                            Some(bridge) => bridge,
+                            None => return Task::none(),
+                        };
+                        
+                        let modifier = huelib::resource::group::StateModifier::new()
+                            .with_hue(huelib::resource::Adjust::Override(hue))
+                            .with_saturation(huelib::resource::Adjust::Override(saturation))
+                            .with_brightness(huelib::resource::Adjust::Override(brightness));
+                        
+                        return Task::perform(
+                            async move { bridge.set_group_state(group_id, &modifier) },
+                            |result| {
+                                Action::App(Message::ResponsesModified(
+                                    result.map_err(|e| e.to_string()),
+                                ))
+                            },
+                        );
+                    }
+                }
+            }
+            Message::TryToggleColorPicker => {
+                if self.color_button_rectangles.get(&0).is_none() {
+                    return Task::perform(async {}, |_| Action::App(Message::TryToggleColorPicker));
+                } else {
+                    return if let Some(p) = self.color_picker_popup.take() {
+                        self.color_button_rectangles.remove(&0);
+                        destroy_popup(p)
+                    } else {
+                        self.open_color_picker_popup()
+                    };
+                }
+            }
+            Message::ToggleColorPicker((item_id, item_type)) => {
+                let i_id = item_id.clone();
+                let i_id_clone = i_id.clone();
+                let i_type = item_type.clone();
+                if self.active_color_picker_item == Some((i_id, i_type)) {
+                    self.active_color_picker_item = None;
+                } else {
+                    self.active_color_picker_item = Some((item_id, item_type));
+                }
+                return if let Some(p) = self.color_picker_popup.take() {
+                    destroy_popup(p)
+                } else {
+                    // The RectanglesUpdated will trigger the popup opening
+                    if self.last_active_color_picker_item == Some(i_id_clone) {
+                        self.open_color_picker_popup()
+                    } else {
+                        Task::none()
+                    }
+                };
+            }
+            Message::ToggleMoreMenu => {
+                return if let Some(p) = self.more_menu_popup.take() {
+                    destroy_popup(p)
+                } else {
+                    let new_id = Id::unique();
+                    self.more_menu_popup.replace(new_id);
+                    let mut more_menu_popup_settings = self.core.applet.get_popup_settings(
+                        self.popup.unwrap(),
+                        new_id,
+                        None,
+                        None,
+                        None,
+                    );
+                    more_menu_popup_settings.positioner.size_limits = Limits::NONE
+                        .max_width(200.0)
+                        .min_width(120.0)
+                        .min_height(120.0)
+                        .max_height(600.0);
+
+                    more_menu_popup_settings.positioner.anchor = Anchor::TopRight;
+                    more_menu_popup_settings.positioner.gravity = Gravity::BottomRight;
+                    more_menu_popup_settings.positioner.offset = (100, 50);
+
+                    get_popup(more_menu_popup_settings)
+                };
+            }
+            Message::RectanglesUpdated(update) => match update {
+                RectangleUpdate::Init(tracker) => {
+                    self.color_button_tracker = Some(tracker);
+                }
+                RectangleUpdate::Rectangle((id, rectangle)) => {
+                    self.color_button_rectangles.insert(id, rectangle);
+                    return Task::perform(async {}, |_| Action::App(Message::TryToggleColorPicker));
+                }
+            },
+            Message::UnpairBridge => {
+                if let Ok(ctx) = cosmic_config::Config::new(Self::APP_ID, Config::VERSION) {
+                    let _ = self.config.set_bridge_ip(&ctx, None);
+                    let _ = self.config.set_username(&ctx, None);
+                    self.bridge_ip_input = String::new();
+                    self.lights = Vec::new();
+                    self.groups = Vec::new();
+                    self.scenes = Vec::new();
+                    self.search_results.clear();
+                    self.scene_palettes.clear();
+                    self.schedules = Vec::new();
+                    self.schedule_editor = None;
+                    self.wake_ups = Vec::new();
+                    self.wake_up_editor = None;
+                    self.sleep_timers.clear();
+                    if let Some(p) = self.more_menu_popup.take() {
+                        return destroy_popup(p);
+                    }
+                };
+            }
+        }
+        Task::none()
+    }
+
+    fn style(&self) -> Option<cosmic::iced_runtime::Appearance> {
+        Some(cosmic::applet::style())
+    }
+
+    fn system_theme_update(
+        &mut self,
+        _keys: &[&'static str],
+        new_theme: &cosmic::cosmic_theme::Theme,
+    ) -> Task<cosmic::Action<Self::Message>> {
+        // Draw the panel icon in the colors of the new theme
+        self.set_panel_icon(PanelIconState {
+            foreground: theme_foreground(new_theme),
+            ..self.panel_icon
+        });
+        Task::none()
+    }
+}
+
+impl AppModel {
+    /// Build the lights section with header and light controls
+    fn build_lights_section<'a>(&'a self) -> Element<'a, Message> {
+        let lights_header = widget::flex_row(vec![
+            widget::text::heading(fl!("lights"))
+                .align_y(Alignment::Center)
+                .height(30.0)
+                .into(),
+            widget::horizontal_space().into(),
+            widget::button::icon(widget::icon::from_name(if self.lights_menu_expanded {
+                "pan-up-symbolic"
+            } else {
+                "pan-down-symbolic"
+            }))
+            .labelled(if self.lights_menu_expanded { fl!("collapse-section") } else { fl!("expand-section") })
+            .on_press(Message::ToggleLightsMenu)
+            .into(),
+        ]);
+
+        if self.lights_menu_expanded {
+            if self.lights.is_empty() {
+                return widget::flex_row(vec![lights_header.into(), widget::text(fl!("no-lights-found")).into()]).into();
+            }
+
+            let children: Vec<_> = self
+                .lights
+                .iter()
+                .filter(|light| self.edit_mode || !self.config.is_hidden(&ItemRef::Light(light.id.clone())))
+                .map(|light| self.build_selectable_row(ItemRef::Light(light.id.clone()), self.build_light_item(light)))
+                .collect();
+
+            let content =
+                widget::scrollable(widget::column::with_children(children).spacing(0)).spacing(10)
+                    .height(Length::Fixed(600.0));
+
+            widget::flex_row(vec![lights_header.into(), content.into()]).into()
+        } else {
+            widget::flex_row(vec![lights_header.into()]).into()
+        }
+    }
+
+    /// Build a single light item with controls
+    fn build_light_item<'a>(&'a self, light: &'a LightVm) -> widget::Column<'a, Message> {
+        if self.edit_mode {
+            return widget::column::column()
+                .push(self.build_edit_item(ItemRef::Light(light.id.clone()), &light.bridge_name));
+        }
+        if let Some(on) = light.on {
+            let item = (light.id.clone(), "light".to_string());
+            let renaming = self
+                .renaming_light
+                .as_ref()
+                .filter(|(light_id, _)| *light_id == light.id);
+            let mut name_toggle_row = match renaming {
+                Some((_, name)) => vec![
+                    widget::text_input(light.bridge_name.as_str(), name.as_str())
+                        .id(light_name_input_id())
+                        .on_input(Message::SetLightNameInput)
+                        .on_submit(|_| Message::SaveLightName)
+                        .into(),
+                    widget::button::icon(widget::icon::from_name("object-select-symbolic"))
+                        .labelled(fl!("save-light-name"))
+                        .on_press(Message::SaveLightName)
+                        .into(),
+                    widget::button::icon(widget::icon::from_name("window-close-symbolic"))
+                        .labelled(fl!("cancel"))
+                        .on_press(Message::CancelRenameLight)
+                        .into(),
+                ],
+                None => vec![
+                    widget::text(&light.name).align_y(Alignment::Center).height(30.0).into(),
+                    widget::button::icon(widget::icon::from_name("document-edit-symbolic"))
+                        .labelled(fl!("rename-light"))
+                        .on_press(Message::StartRenameLight(light.id.clone()))
+                        .into(),
+                    widget::horizontal_space().into(),
+                ],
+            };
+            name_toggle_row.extend(self.build_sleep_timer_status(&item));
+            name_toggle_row.push(
+                widget::button::icon(widget::icon::from_name("view-more-symbolic"))
+                    .labelled(fl!("more-options"))
+                    .on_press(Message::ToggleRowMenu(item.clone()))
+                    .into(),
+            );
+            name_toggle_row.push(
+                widget::toggler(on)
+                    .name(light.name.as_str())
+                    .on_toggle(|new_state| Message::ToggleLight(light.id.clone(), new_state))
+                    .into(),
+            );
+            let name_toggle_row = widget::flex_row(name_toggle_row);
+
+            let (light_brightness, light_brightness_percent) = match light.brightness {
+                Some(bri) => (
+                    bri as f32,
+                    ((bri.saturating_sub(1) as f32) / 253.0 * 100.0).round(),
+                ),
+                None => (0.0, 0.0),
+            };
+
+            // Get the current color as RGB (if available)
+            let (r, g, b) = if let Some((r, g, b)) = light.color {
+                (r, g, b)
+            } else {
+                (0.0, 0.0, 0.0)
+            };
+
+            let color_button = widget::color_picker::color_button(
+                Some(Message::ToggleColorPicker((
+                    light.id.clone(),
+                    "light".to_string(),
+                ))),
+                Some(iced_core::Color::from_rgb(r, g, b)),
+                Length::Fixed(32.0),
+            );
+
+            let color_button =
+                if self.active_color_picker_item == Some((light.id.clone(), "light".to_string())) {
+                    if let Some(tracker) = &self.color_button_tracker {
+                        tracker.container(0u32, color_button).into()
+                    } else {
+                        color_button.into()
+                    }
+                } else {
+                    color_button.into()
+                };
+
+            let slider_color_row = widget::flex_row(vec![
+                widget::slider(1.0..=254.0, light_brightness, |new_brightness| {
+                    Message::SetLightBrightness(light.id.clone(), new_brightness)
+                })
+                .into(),
+                widget::text(format!("{}%", light_brightness_percent)).into(),
+                widget::horizontal_space().into(),
+                color_button,
+            ]);
+
+            let mut column = widget::column::column()
+                .width(Length::Fill)
+                .spacing(10.0)
+                .push(name_toggle_row);
+            if renaming.is_some() {
+                if let Some(error) = &self.light_name_error {
+                    column = column.push(widget::text::caption(error));
+                } else if let Some(alias) = self.config.get_alias(&ItemRef::Light(light.id.clone())) {
+                    column = column.push(widget::text::caption(fl!("light-alias-kept", alias = alias)));
+                }
+            }
+            let column = column.push(slider_color_row);
+
+            if self.row_menu.as_ref() == Some(&item) {
+                column.push(self.build_row_menu(item))
+            } else {
+                column
+            }
+        } else {
+            widget::column::column().push(widget::settings::item(
+                &light.name,
+                widget::text(fl!("light-has-no-state", name = light.name.clone())),
+            ))
+        }
+    }
+
+    /// Build the remaining time and cancel button of the sleep timer of a light or group
+    fn build_sleep_timer_status<'a>(&'a self, item: &(String, String)) -> Vec<Element<'a, Message>> {
+        let Some(timer) = self.sleep_timers.get(item) else {
+            return Vec::new();
+        };
+        let remaining = timer.ends_at.saturating_duration_since(Instant::now()).as_secs();
+        let remaining = format!("{}:{:02}", remaining / 60, remaining % 60);
+
+        vec![
+            widget::text::caption(fl!("turns-off-in", remaining = remaining))
+                .align_y(Alignment::Center)
+                .height(30.0)
+                .into(),
+            widget::button::icon(widget::icon::from_name("window-close-symbolic"))
+                .labelled(fl!("cancel-sleep-timer"))
+                .on_press(Message::CancelSleepTimer(item.clone()))
+                .into(),
+        ]
+    }
+
+    /// Build the menu of a light or group row
+    fn build_row_menu<'a>(&'a self, item: (String, String)) -> Element<'a, Message> {
+        let mut sleep_timer_row = vec![
+            widget::text(fl!("turn-off-in"))
+                .align_y(Alignment::Center)
+                .height(30.0)
+                .into(),
+            widget::horizontal_space().into(),
+        ];
+        sleep_timer_row.extend(SLEEP_TIMER_PRESETS.iter().map(|&minutes| {
+            widget::button::standard(fl!("minutes-short", minutes = minutes))
+                .on_press(Message::StartSleepTimer(item.clone(), minutes))
+                .into()
+        }));
+
+        let pin = if item.1 == "group" {
+            ItemRef::Group(item.0.clone())
+        } else {
+            ItemRef::Light(item.0.clone())
+        };
+        let mut column = widget::column::column()
+            .spacing(10.0)
+            .push(widget::divider::horizontal::default())
+            .push(widget::flex_row(sleep_timer_row).spacing(4))
+            .push(self.build_pin_toggle(pin));
+
+        if item.1 == "group" {
+            let group_id = item.0.clone();
+            let options = self.config.get_adaptive_options(&group_id);
+            column = column.push(widget::flex_row(vec![
+                widget::text(fl!("adaptive-lighting")).into(),
+                widget::horizontal_space().into(),
+                widget::toggler(options.is_some())
+                    .on_toggle(move |enabled| {
+                        Message::SetAdaptiveOptions(
+                            group_id.clone(),
+                            enabled.then(AdaptiveOptions::default),
+                        )
+                    })
+                    .into(),
+            ]));
+
+            if let Some(options) = options {
+                let group_id = item.0.clone();
+                column = column.push(widget::flex_row(vec![
+                    widget::text(fl!("adaptive-brightness")).into(),
+                    widget::horizontal_space().into(),
+                    widget::toggler(options.adjust_brightness)
+                        .on_toggle(move |adjust_brightness| {
+                            Message::SetAdaptiveOptions(
+                                group_id.clone(),
+                                Some(AdaptiveOptions { adjust_brightness }),
+                            )
+                        })
+                        .into(),
+                ]));
+            }
+
+            column = column
+                .push(self.build_idle_dim_settings(&item.0))
+                .push(self.build_lock_action_choices(&item.0));
+        }
+
+        column.into()
+    }
+
+    /// Put the lights back as they were before the session was locked or suspended
+    fn restore_lock_snapshot(&mut self) -> Task<cosmic::Action<Message>> {
+        let Some(snapshot) = self.lock_snapshot.take() else {
+            return Task::none();
+        };
+        let bridge = match get_bridge(&self.config) {
+            Some(bridge) => bridge,
+            None => return Task::none(),
+        };
+        Task::perform(
+            async move { lock::restore(&bridge, &snapshot).await },
+            |result| Action::App(Message::LockRestored(result.map_err(|e| e.to_string()))),
+        )
+    }
+
+    /// Put the idle group back as it was before dimming it
+    fn restore_idle_snapshot(&mut self) -> Task<cosmic::Action<Message>> {
+        let Some(snapshot) = self.idle_snapshot.take() else {
+            return Task::none();
+        };
+        let bridge = match get_bridge(&self.config) {
+            Some(bridge) => bridge,
+            None => return Task::none(),
+        };
+        Task::perform(
+            async move { idle::restore(&bridge, &snapshot) },
+            |result| Action::App(Message::IdleRestored(result.map_err(|e| e.to_string()))),
+        )
+    }
+
+    /// Show the phase of the running focus timer with its light
+    fn start_focus_light(&mut self) -> Task<cosmic::Action<Message>> {
+        let Some(phase) = self.focus_timer.as_ref().map(|timer| timer.phase) else {
+            return Task::none();
+        };
+        let options = self.config.get_focus_timer().clone();
+        let Some(light_id) = options.light_id else {
+            return Task::none();
+        };
+        let bridge = match get_bridge(&self.config) {
+            Some(bridge) => bridge,
+            None => return Task::none(),
+        };
+        Task::perform(
+            async move { focus::start(&bridge, &light_id, phase, options.signal).await },
+            |result| Action::App(Message::FocusStarted(result.map_err(|e| e.to_string()))),
+        )
+    }
+
+    /// Build the quick toggles of the pinned lights, groups and scenes
+    fn build_pins_row<'a>(&'a self) -> Element<'a, Message> {
+        let button = |label: String, on: bool, message: Message| -> Element<'a, Message> {
+            if on {
+                widget::button::suggested(label).on_press(message).into()
+            } else {
+                widget::button::standard(label).on_press(message).into()
+            }
+        };
+        let buttons = self
+            .config
+            .get_pins()
+            .iter()
+            .filter_map(|pin| match pin {
+                ItemRef::Light(light_id) => {
+                    let light = self.lights.iter().find(|light| light.id == *light_id)?;
+                    let on = light.on.unwrap_or(false);
+                    Some(button(light.name.clone(), on, Message::ToggleLight(light.id.clone(), !on)))
+                }
+                ItemRef::Group(group_id) => {
+                    let group = self.groups.iter().find(|group| group.id == *group_id)?;
+                    let on = group.on.unwrap_or(false);
+                    Some(button(group.name.clone(), on, Message::ToggleGroup(group.id.clone(), !on)))
+                }
+                ItemRef::Scene(scene_id) => {
+                    let scene = self.scenes.iter().find(|scene| scene.id == *scene_id)?;
+                    Some(button(scene.name.clone(), false, Message::ActivateScene(scene.id.clone())))
+                }
+            })
+            .collect();
+        widget::flex_row(buttons).spacing(8).into()
+    }
+
+    /// The name of a pin and what its panel button shows, `None` until the pinned item is loaded
+    fn pin_icon_state(&self, pin: &ItemRef) -> Option<(String, PanelIconState)> {
+        let (name, on, color) = match pin {
+            ItemRef::Light(light_id) => {
+                let light = self.lights.iter().find(|light| light.id == *light_id)?;
+                (&light.name, light.on.unwrap_or(false), light.color)
+            }
+            ItemRef::Group(group_id) => {
+                let group = self.groups.iter().find(|group| group.id == *group_id)?;
+                (&group.name, group.on.unwrap_or(false), group.color)
+            }
+            ItemRef::Scene(scene_id) => {
+                let scene = self.scenes.iter().find(|scene| scene.id == *scene_id)?;
+                let color = self.scene_palettes.get(scene_id).and_then(|palette| palette.first().copied());
+                (&scene.name, true, color)
+            }
+        };
+        Some((
+            name.clone(),
+            PanelIconState {
+                on,
+                tint: color,
+                scene: matches!(pin, ItemRef::Scene(_)),
+                ..self.panel_icon
+            },
+        ))
+    }
+
+    /// Whether the panel has room for the pins next to the main icon
+    fn panel_has_room_for_pins(&self) -> bool {
+        self.config.get_show_pins_in_panel()
+            && self.core.applet.is_horizontal()
+            // The smallest panels only have room for the main icon
+            && !matches!(
+                self.core.applet.size,
+                Size::PanelSize(PanelSize::XS | PanelSize::S)
+            )
+    }
+
+    /// Redraw the panel icon when what it shows changed
+    fn set_panel_icon(&mut self, state: PanelIconState) {
+        if state != self.panel_icon {
+            self.panel_icon = state;
+            self.panel_icon_handle = icon::from_svg_bytes(state.svg());
+        }
+    }
+
+    /// Show the level of the default group for a moment, unless the popup is open
+    fn show_panel_level(&mut self) -> Task<cosmic::Action<Message>> {
+        self.level_counter += 1;
+        let counter = self.level_counter;
+        let hide = Task::perform(
+            async move {
+                tokio::time::sleep(PANEL_LEVEL_DURATION).await;
+                counter
+            },
+            |counter| Action::App(Message::HidePanelLevel(counter)),
+        );
+        if self.level_popup.is_some() || self.popup.is_some() {
+            return hide;
+        }
+
+        let new_id = Id::unique();
+        self.level_popup.replace(new_id);
+        let mut popup_settings = self.core.applet.get_popup_settings(
+            self.core.main_window_id().unwrap(),
+            new_id,
+            None,
+            None,
+            None,
+        );
+        popup_settings.positioner.size_limits = Limits::NONE
+            .min_width(240.0)
+            .max_width(240.0)
+            .min_height(60.0)
+            .max_height(120.0);
+
+        Task::batch([get_popup(popup_settings), hide])
+    }
+
+    /// Put the focus light back as it was before the timer
+    fn restore_focus_snapshot(&mut self) -> Task<cosmic::Action<Message>> {
+        let Some(snapshot) = self.focus_snapshot.take() else {
+            return Task::none();
+        };
+        let bridge = match get_bridge(&self.config) {
+            Some(bridge) => bridge,
+            None => return Task::none(),
+        };
+        Task::perform(
+            async move { focus::restore(&bridge, &snapshot) },
+            |result| Action::App(Message::FocusRestored(result.map_err(|e| e.to_string()))),
+        )
+    }
+
+    /// Build the focus timer section with its controls and settings
+    fn build_focus_section<'a>(&'a self) -> Element<'a, Message> {
+        let mut header: Vec<Element<'a, Message>> = vec![
+            widget::text::heading(fl!("focus-timer"))
+                .align_y(Alignment::Center)
+                .height(30.0)
+                .into(),
+            widget::horizontal_space().into(),
+        ];
+        match &self.focus_timer {
+            Some(timer) => {
+                let remaining = timer.ends_at.saturating_duration_since(Instant::now()).as_secs();
+                let phase = match timer.phase {
+                    FocusPhase::Work => fl!("focus-work"),
+                    FocusPhase::Break => fl!("focus-break"),
+                };
+                header.push(
+                    widget::text(format!("{} {:02}:{:02}", phase, remaining / 60, remaining % 60))
+                        .align_y(Alignment::Center)
+                        .height(30.0)
+                        .into(),
+                );
+                header.push(
+                    widget::button::icon(widget::icon::from_name("media-skip-forward-symbolic"))
+                        .labelled(fl!("skip-focus-phase"))
+                        .on_press(Message::SkipFocusPhase)
+                        .into(),
+                );
+                header.push(
+                    widget::button::icon(widget::icon::from_name("media-playback-stop-symbolic"))
+                        .labelled(fl!("stop-focus-timer"))
+                        .on_press(Message::StopFocusTimer)
+                        .into(),
+                );
+            }
+            None => header.push(
+                widget::button::icon(widget::icon::from_name("media-playback-start-symbolic"))
+                    .labelled(fl!("start-focus-timer"))
+                    .on_press(Message::StartFocusTimer)
+                    .into(),
+            ),
+        }
+        header.push(
+            widget::button::icon(widget::icon::from_name(if self.focus_menu_expanded {
+                "pan-up-symbolic"
+            } else {
+                "pan-down-symbolic"
+            }))
+            .labelled(if self.focus_menu_expanded { fl!("collapse-section") } else { fl!("expand-section") })
+            .on_press(Message::ToggleFocusMenu)
+            .into(),
+        );
+        let header = widget::flex_row(header);
+
+        if !self.focus_menu_expanded {
+            return widget::flex_row(vec![header.into()]).into();
+        }
+
+        let options = self.config.get_focus_timer();
+        let sessions = self.config.get_focus_sessions();
+        let today = sessions
+            .get(&chrono::Local::now().format("%Y-%m-%d").to_string())
+            .copied()
+            .unwrap_or(0);
+        let total: u32 = sessions.values().sum();
+
+        let signal_row: Vec<_> = [(FocusSignal::Color, fl!("focus-signal-color")), (FocusSignal::Pulse, fl!("focus-signal-pulse"))]
+            .into_iter()
+            .map(|(signal, label)| {
+                widget::radio(widget::text(label), signal, Some(options.signal), Message::SetFocusSignal).into()
+            })
+            .collect();
+
+        let minutes_row = |label: String, minutes: u16, message: fn(u16) -> Message| {
+            build_step_row(
+                label,
+                fl!("focus-minutes", minutes = minutes),
+                message(minutes.saturating_sub(1)),
+                message(minutes.saturating_add(1)),
+            )
+        };
+
+        let selected_light = options
+            .light_id
+            .as_ref()
+            .and_then(|light_id| self.lights.iter().position(|light| light.id == *light_id));
+
+        let content = widget::column::column()
+            .spacing(10.0)
+            .padding(10)
+            .push(widget::text::caption(fl!("focus-sessions", today = today, total = total)))
+            .push(widget::dropdown(&self.light_labels, selected_light, Message::SetFocusLight))
+            .push(widget::flex_row(signal_row).spacing(10))
+            .push(minutes_row(fl!("focus-work"), options.work_minutes, Message::SetFocusWorkMinutes))
+            .push(minutes_row(fl!("focus-break"), options.break_minutes, Message::SetFocusBreakMinutes));
+
+        widget::flex_row(vec![header.into(), content.into()]).into()
+    }
+
+    /// Turn the busy light red or put it back, following the calendar and the state set by hand
+    fn update_busy_light(&mut self) -> Task<cosmic::Action<Message>> {
+        let busy = self.busy_override.unwrap_or(self.calendar_event.is_some());
+        if busy && !self.busy_light_on {
+            let Some(light_id) = self.config.get_busy_light().map(|options| options.light_id.clone()) else {
+                return Task::none();
+            };
+            let bridge = match get_bridge(&self.config) {
+                Some(bridge) => bridge,
+                None => return Task::none(),
+            };
+            self.busy_light_on = true;
+            Task::perform(
+                async move { busy::set_busy(&bridge, &light_id) },
+                |result| Action::App(Message::BusyLightSet(result.map_err(|e| e.to_string()))),
+            )
+        } else if !busy && self.busy_light_on {
+            self.busy_light_on = false;
+            self.restore_busy_snapshot()
+        } else {
+            Task::none()
+        }
+    }
+
+    /// Put the busy light back as it was before turning it red
+    fn restore_busy_snapshot(&mut self) -> Task<cosmic::Action<Message>> {
+        let Some(snapshot) = self.busy_snapshot.take() else {
+            return Task::none();
+        };
+        let bridge = match get_bridge(&self.config) {
+            Some(bridge) => bridge,
+            None => return Task::none(),
+        };
+        Task::perform(
+            async move { busy::restore(&bridge, &snapshot) },
+            |result| Action::App(Message::BusyLightRestored(result.map_err(|e| e.to_string()))),
+        )
+    }
+
+    /// Build the busy and available buttons of the popup
+    fn build_busy_light_row<'a>(&'a self) -> Element<'a, Message> {
+        let busy = self.busy_override.unwrap_or(self.calendar_event.is_some());
+        let status = match (&self.calendar_event, self.busy_override) {
+            (Some(event), None) => fl!("busy-with", summary = event.summary.clone()),
+            _ => fl!("busy-light"),
+        };
+        let button = |label: String, selected: bool, value: bool| -> Element<'a, Message> {
+            if selected {
+                widget::button::suggested(label).on_press(Message::SetBusy(value)).into()
+            } else {
+                widget::button::standard(label).on_press(Message::SetBusy(value)).into()
+            }
+        };
+        widget::flex_row(vec![
+            widget::text(status).align_y(Alignment::Center).height(30.0).into(),
+            widget::horizontal_space().into(),
+            button(fl!("busy"), busy, true),
+            button(fl!("available"), !busy, false),
+        ])
+        .spacing(8)
+        .into()
+    }
+
+    /// Build the settings of the busy light, or the form editing them
+    fn build_busy_light_settings<'a>(&'a self) -> Element<'a, Message> {
+        let options = self.config.get_busy_light();
+        let edit_icon = if options.is_some() { "document-edit-symbolic" } else { "list-add-symbolic" };
+        let header = widget::flex_row(vec![
+            widget::text::heading(fl!("busy-light"))
+                .align_y(Alignment::Center)
+                .height(30.0)
+                .into(),
+            widget::horizontal_space().into(),
+            widget::button::icon(widget::icon::from_name(edit_icon))
+                .labelled(fl!("edit"))
+                .on_press(Message::EditBusyLight)
+                .into(),
+        ]);
+        let mut column = widget::column::column().spacing(10.0).push(header);
+
+        if let Some(editor) = &self.busy_light_editor {
+            column = column
+                .push(widget::dropdown(&editor.light_labels, editor.light, |index| {
+                    Message::BusyLightEditor(BusyLightEditorMessage::Light(index))
+                }))
+                .push(
+                    widget::text_input(fl!("calendar-file"), &editor.calendar_path).on_input(|calendar_path| {
+                        Message::BusyLightEditor(BusyLightEditorMessage::CalendarPath(calendar_path))
+                    }),
+                )
+                .push(widget::text::caption(fl!("calendar-file-description")));
+            if let Some(error) = &editor.error {
+                column = column.push(widget::text(fl!("error", error = error.clone())));
+            }
+            let mut buttons = vec![widget::horizontal_space().into()];
+            if options.is_some() {
+                buttons.push(
+                    widget::button::destructive(fl!("remove"))
+                        .on_press(Message::RemoveBusyLight)
+                        .into(),
+                );
+            }
+            buttons.push(
+                widget::button::standard(fl!("cancel"))
+                    .on_press(Message::CancelBusyLightEditor)
+                    .into(),
+            );
+            buttons.push(
+                widget::button::suggested(fl!("save"))
+                    .on_press(Message::SaveBusyLight)
+                    .into(),
+            );
+            return column.push(widget::flex_row(buttons).spacing(10)).into();
+        }
+
+        match options {
+            Some(options) => {
+                let light_name = self
+                    .lights
+                    .iter()
+                    .find(|light| light.id == options.light_id)
+                    .map(|light| light.name.clone())
+                    .unwrap_or_default();
+                column = column.push(widget::text(light_name)).push(widget::text::caption(
+                    options.calendar_path.clone().unwrap_or_else(|| fl!("no-calendar")),
+                ));
+            }
+            None => column = column.push(widget::text::caption(fl!("no-busy-light"))),
+        }
+        column.into()
+    }
+
+    /// Build the dimming settings of a group while the user is away
+    fn build_idle_dim_settings<'a>(&'a self, group_id: &str) -> Element<'a, Message> {
+        let options = match &self.pending_idle_dim {
+            Some((options, _)) => options.clone(),
+            None => self.config.get_idle_dim().cloned(),
+        }
+        .filter(|options| options.group_id == group_id);
+
+        let enabled_group_id = group_id.to_string();
+        let mut column = widget::column::column().spacing(10.0).push(widget::flex_row(vec![
+            widget::text(fl!("dim-when-idle")).into(),
+            widget::horizontal_space().into(),
+            widget::toggler(options.is_some())
+                .on_toggle(move |enabled| {
+                    Message::SetIdleDim(enabled.then(|| IdleDimOptions {
+                        group_id: enabled_group_id.clone(),
+                        minutes: DEFAULT_IDLE_DIM_MINUTES,
+                        brightness_percent: DEFAULT_IDLE_DIM_BRIGHTNESS_PERCENT,
+                    }))
+                })
+                .into(),
+        ]));
+
+        if let Some(options) = options {
+            if self.idle_unavailable {
+                column = column.push(widget::text::caption(fl!("idle-source-unavailable")));
+            }
+            let minutes_options = options.clone();
+            let brightness_options = options.clone();
+            column = column
+                .push(widget::flex_row(vec![
+                    widget::text(fl!("idle-after")).into(),
+                    widget::slider(1.0..=60.0, options.minutes as f32, move |minutes| {
+                        Message::SetIdleDim(Some(IdleDimOptions {
+                            minutes: minutes as u16,
+                            ..minutes_options.clone()
+                        }))
+                    })
+                    .into(),
+                    widget::text(fl!("minutes-short", minutes = options.minutes)).into(),
+                ]))
+                .push(widget::flex_row(vec![
+                    widget::text(fl!("scene-brightness")).into(),
+                    widget::slider(1.0..=100.0, options.brightness_percent as f32, move |percent| {
+                        Message::SetIdleDim(Some(IdleDimOptions {
+                            brightness_percent: percent as u8,
+                            ..brightness_options.clone()
+                        }))
+                    })
+                    .into(),
+                    widget::text(format!("{}%", options.brightness_percent)).into(),
+                ]));
+        }
+
+        column.into()
+    }
+
+    /// Build the choice of the action run on a group when the session is locked
+    fn build_lock_action_choices<'a>(&'a self, group_id: &str) -> Element<'a, Message> {
+        let mut choices = vec![
+            (fl!("do-nothing"), None),
+            (fl!("turn-off"), Some(LockAction::TurnOff)),
+        ];
+        choices.extend(self.group_scenes(group_id).into_iter().map(|scene| {
+            (scene.name.clone(), Some(LockAction::Scene(scene.id.clone())))
+        }));
+
+        let current = self.config.get_lock_action(group_id);
+        let selected = choices.iter().position(|(_, action)| action.as_ref() == current);
+
+        let radios: Vec<Element<'a, Message>> = choices
+            .into_iter()
+            .enumerate()
+            .map(|(index, (label, action))| {
+                let group_id = group_id.to_string();
+                widget::radio(widget::text(label), index, selected, move |_| {
+                    Message::SetLockAction(group_id, action)
+                })
+                .into()
+            })
+            .collect();
+
+        widget::column::column()
+            .spacing(10.0)
+            .push(widget::text(fl!("when-locked")))
+            .push(widget::flex_row(radios).spacing(10))
+            .into()
+    }
+
+    /// Build the list of the notification rules, or the form editing one of them
+    fn build_notification_rules_settings<'a>(&'a self) -> Element<'a, Message> {
+        let header = widget::flex_row(vec![
+            widget::text::heading(fl!("notification-alerts"))
+                .align_y(Alignment::Center)
+                .height(30.0)
+                .into(),
+            widget::horizontal_space().into(),
+            widget::button::icon(widget::icon::from_name("list-add-symbolic"))
+                .labelled(fl!("add"))
+                .on_press(Message::NewNotificationRule)
+                .into(),
+        ]);
+
+        if let Some(editor) = &self.notification_rule_editor {
+            return widget::column::column()
+                .spacing(10.0)
+                .push(header)
+                .push(self.build_notification_rule_editor(editor))
+                .into();
+        }
+
+        let mut column = widget::column::column().spacing(10.0).push(header);
+        if self.config.get_notification_rules().is_empty() {
+            column = column.push(widget::text::caption(fl!("no-notification-alerts")));
+        }
+        for rule in self.config.get_notification_rules() {
+            let light_name = self
+                .lights
+                .iter()
+                .find(|light| light.id == rule.light_id)
+                .map(|light| light.name.clone())
+                .unwrap_or_default();
+            let app_name = if rule.app_name.is_empty() {
+                fl!("any-app")
+            } else {
+                rule.app_name.clone()
+            };
+            column = column.push(widget::flex_row(vec![
+                widget::text::caption(fl!(
+                    "notification-alert-description",
+                    app_name = app_name,
+                    urgency = urgency_name(rule.min_urgency),
+                    light_name = light_name
+                ))
+                .into(),
+                widget::horizontal_space().into(),
+                widget::button::icon(widget::icon::from_name("document-edit-symbolic"))
+                    .labelled(fl!("edit"))
+                    .on_press(Message::EditNotificationRule(rule.id.clone()))
+                    .into(),
+                widget::button::icon(widget::icon::from_name("user-trash-symbolic"))
+                    .labelled(fl!("delete"))
+                    .on_press(Message::DeleteNotificationRule(rule.id.clone()))
+                    .into(),
+            ]));
+        }
+        column.into()
+    }
+
+    /// Build the form to create or edit a notification rule
+    fn build_notification_rule_editor<'a>(
+        &'a self,
+        editor: &'a NotificationRuleEditor,
+    ) -> widget::Column<'a, Message> {
+        let urgency_row: Vec<_> = [Urgency::Low, Urgency::Normal, Urgency::Critical]
+            .into_iter()
+            .map(|urgency| {
+                widget::radio(
+                    widget::text(urgency_name(urgency)),
+                    urgency,
+                    Some(editor.min_urgency),
+                    |urgency| {
+                        Message::NotificationRuleEditor(NotificationRuleEditorMessage::MinUrgency(urgency))
+                    },
+                )
+                .into()
+            })
+            .collect();
+
+        let mut alert_row = vec![widget::radio(
+            widget::text(fl!("breathe")),
+            None,
+            Some(editor.flash_color),
+            |color| Message::NotificationRuleEditor(NotificationRuleEditorMessage::FlashColor(color)),
+        )
+        .into()];
+        alert_row.extend(flash_color_names().into_iter().enumerate().map(|(index, name)| {
+            widget::radio(
+                widget::text(name),
+                Some(index),
+                Some(editor.flash_color),
+                |color| Message::NotificationRuleEditor(NotificationRuleEditorMessage::FlashColor(color)),
+            )
+            .into()
+        }));
+
+        let mut column = widget::column::column()
+            .spacing(10.0)
+            .push(
+                widget::text_input(fl!("any-app"), &editor.app_name).on_input(|app_name| {
+                    Message::NotificationRuleEditor(NotificationRuleEditorMessage::AppName(app_name))
+                }),
+            )
+            .push(widget::text(fl!("minimum-urgency")))
+            .push(widget::flex_row(urgency_row).spacing(10))
+            .push(widget::dropdown(&editor.light_labels, editor.light, |index| {
+                Message::NotificationRuleEditor(NotificationRuleEditorMessage::Light(index))
+            }))
+            .push(widget::flex_row(alert_row).spacing(10));
+
+        if let Some(error) = &editor.error {
+            column = column.push(widget::text(fl!("error", error = error.clone())));
+        }
+
+        column.push(widget::flex_row(vec![
+            widget::horizontal_space().into(),
+            widget::button::standard(fl!("cancel"))
+                .on_press(Message::CancelNotificationRuleEditor)
+                .into(),
+            widget::button::suggested(fl!("save"))
+                .on_press(Message::SaveNotificationRule)
+                .into(),
+        ]).spacing(10))
+    }
+
+    /// Fill the lights which can be alerted by the edited notification rule
+    fn set_notification_rule_editor_lights(&self, editor: &mut NotificationRuleEditor, light_id: Option<&str>) {
+        editor.lights = self
+            .lights
+            .iter()
+            .map(|light| (light.id.clone(), light.name.clone()))
+            .collect();
+        editor.light_labels = editor.lights.iter().map(|(_, name)| name.clone()).collect();
+        editor.light = light_id.and_then(|light_id| editor.lights.iter().position(|(id, _)| id == light_id));
+    }
+
+    /// Build the settings of the keyboard shortcut actions
+    fn build_shortcut_settings<'a>(&'a self) -> Element<'a, Message> {
+        let options = self.config.get_shortcuts();
+        let selected_group = options
+            .default_group
+            .as_ref()
+            .and_then(|group_id| self.groups.iter().position(|group| group.id == *group_id));
+        let brightness_step = options.brightness_step_percent;
+        let color_temperature_step = options.color_temperature_step;
+
+        let mut column = widget::column::column()
+            .spacing(10.0)
+            .push(widget::text::heading(fl!("keyboard-shortcuts")))
+            .push(widget::text(fl!("default-group")))
+            .push(widget::dropdown(&self.group_labels, selected_group, Message::SetShortcutDefaultGroup))
+            .push(widget::text::caption(fl!("panel-icon-description")))
+            .push(widget::flex_row(vec![
+                widget::text(fl!("tint-panel-icon")).into(),
+                widget::horizontal_space().into(),
+                widget::toggler(self.config.get_tint_panel_icon())
+                    .on_toggle(Message::SetTintPanelIcon)
+                    .into(),
+            ]))
+            .push(widget::flex_row(vec![
+                widget::text(fl!("show-pins-in-panel")).into(),
+                widget::horizontal_space().into(),
+                widget::toggler(self.config.get_show_pins_in_panel())
+                    .on_toggle(Message::SetShowPinsInPanel)
+                    .into(),
+            ]))
+            .push(build_step_row(
+                fl!("brightness-step"),
+                fl!("percent", percent = brightness_step),
+                Message::SetBrightnessStep(brightness_step.saturating_sub(5)),
+                Message::SetBrightnessStep(brightness_step.saturating_add(5)),
+            ))
+            .push(build_step_row(
+                fl!("color-temperature-step"),
+                fl!("mireds", mireds = color_temperature_step),
+                Message::SetColorTemperatureStep(color_temperature_step.saturating_sub(5)),
+                Message::SetColorTemperatureStep(color_temperature_step.saturating_add(5)),
+            ))
+            .push(widget::text::caption(fl!("keyboard-shortcuts-description")));
+        for command in SHORTCUT_COMMANDS {
+            column = column.push(widget::text::caption(command));
+        }
+        column.into()
+    }
+
+    /// The ids of the lights of a group, all the lights for the group `0`
+    fn group_light_ids(&self, group_id: &str) -> Vec<String> {
+        if group_id == "0" {
+            return self.lights.iter().map(|light| light.id.clone()).collect();
+        }
+        self.groups
+            .iter()
+            .find(|group| group.id == group_id)
+            .map(|group| group.lights.clone())
+            .unwrap_or_default()
+    }
+
+    /// Build the groups section with header and group controls
+    fn build_groups_section<'a>(&'a self) -> Element<'a, Message> {
+        let groups_header = widget::flex_row(vec![
+            widget::text::heading(fl!("groups"))
+                .align_y(Alignment::Center)
+                .height(30.0)
+                .into(),
+            widget::horizontal_space().into(),
+            widget::button::icon(widget::icon::from_name(if self.groups_menu_expanded {
+                "pan-up-symbolic"
+            } else {
+                "pan-down-symbolic"
+            }))
+            .labelled(if self.groups_menu_expanded { fl!("collapse-section") } else { fl!("expand-section") })
+            .on_press(Message::ToggleGroupsMenu)
+            .into(),
+        ]);
+
+        if self.groups_menu_expanded {
+            if self.groups.is_empty() {
+                return widget::flex_row(vec![groups_header.into(), widget::text(fl!("no-groups-found")).into()]).into();
+            }
+
+            let children: Vec<_> = self
+                .groups
+                .iter()
+                .filter(|group| self.edit_mode || !self.config.is_hidden(&ItemRef::Group(group.id.clone())))
+                .map(|group| self.build_selectable_row(ItemRef::Group(group.id.clone()), self.build_group_item(group)))
+                .collect();
+
+            let content =
+                widget::scrollable(widget::column::with_children(children).spacing(0))
+                    .height(Length::Fixed(600.0));
+
+            widget::flex_row(vec![groups_header.into(), content.into()]).into()
+        } else {
+            widget::flex_row(vec![groups_header.into()]).into()
+        }
+    }
+
+    /// Build a single group item with controls
+    fn build_group_item<'a>(&'a self, group: &'a GroupVm) -> widget::Column<'a, Message> {
+        if self.edit_mode {
+            return widget::column::column()
+                .push(self.build_edit_item(ItemRef::Group(group.id.clone()), &group.bridge_name));
+        }
+        if let Some(on) = group.on {
+            let item = (group.id.clone(), "group".to_string());
+            let mut name_toggle_row = vec![
+                widget::text(&group.name).into(),
+                widget::horizontal_space().into(),
+            ];
+            name_toggle_row.extend(self.build_sleep_timer_status(&item));
+            name_toggle_row.push(
+                widget::button::icon(widget::icon::from_name("view-more-symbolic"))
+                    .labelled(fl!("more-options"))
+                    .on_press(Message::ToggleRowMenu(item.clone()))
+                    .into(),
+            );
+            name_toggle_row.push(
+                widget::toggler(on)
+                    .name(group.name.as_str())
+                    .on_toggle(|new_state| Message::ToggleGroup(group.id.clone(), new_state))
+                    .into(),
+            );
+            let name_toggle_row = widget::flex_row(name_toggle_row);
+
+            let (group_brightness, group_brightness_percent) = match group.brightness {
+                Some(bri) => (
+                    bri as f32,
+                    ((bri.saturating_sub(1) as f32) / 253.0 * 100.0).round(),
+                ),
+                None => (0.0, 0.0),
+            };
+
+            // Get the current color as RGB (if available)
+            let (r, g, b) = if let Some((r, g, b)) = group.color {
+                (r, g, b)
+            } else {
+                (0.0, 0.0, 0.0)
+            };
+
+            let color_button = widget::color_picker::color_button(
+                Some(Message::ToggleColorPicker((
+                    group.id.clone(),
+                    "group".to_string(),
+                ))),
+                Some(iced_core::Color::from_rgb(r, g, b)),
+                Length::Fixed(32.0),
+            );
+
+            let color_button =
+                if self.active_color_picker_item == Some((group.id.clone(), "group".to_string())) {
+                    if let Some(tracker) = &self.color_button_tracker {
+                        tracker.container(0u32, color_button).into()
+                    } else {
+                        color_button.into()
+                    }
+                } else {
+                    color_button.into()
+                };
+
+            let slider_color_row = widget::flex_row(vec![
+                widget::slider(1.0..=254.0, group_brightness, |new_brightness| {
+                    Message::SetGroupBrightness(group.id.clone(), new_brightness)
+                })
+                .into(),
+                widget::text(format!("{}%", group_brightness_percent)).into(),
+                widget::horizontal_space().into(),
+                color_button,
+            ]);
+
+            // Quick access to the scenes of the group
+            let scene_buttons: Vec<_> = self
+                .group_scenes(&group.id)
+                .into_iter()
+                .map(|scene| {
+                    widget::button::standard(&scene.name)
+                        .on_press(Message::ActivateScene(scene.id.clone()))
+                        .into()
+                })
+                .collect();
+
+            let mut column = widget::column::column()
+                .spacing(10.0)
+                .push(name_toggle_row)
+                .push(slider_color_row);
+
+            if self.row_menu.as_ref() == Some(&item) {
+                column = column.push(self.build_row_menu(item));
+            }
+
+            if scene_buttons.is_empty() {
+                column
+            } else {
+                column.push(widget::flex_row(scene_buttons).spacing(8))
+            }
+        } else {
+            widget::column::column().push(widget::settings::item(
+                &group.name,
+                widget::text(format!("Group {} has no state", group.name)),
+            ))
+        }
+    }
+
+    /// Build the scenes section with header and scene controls
+    fn build_scenes_section<'a>(&'a self) -> Element<'a, Message> {
+        let scenes_header = widget::flex_row(vec![
+            widget::text::heading(fl!("scenes"))
+                .align_y(Alignment::Center)
+                .height(30.0)
+                .into(),
+            widget::horizontal_space().into(),
+            widget::button::icon(widget::icon::from_name(if self.show_hidden_scenes {
+                "view-conceal-symbolic"
+            } else {
+                "view-reveal-symbolic"
+            }))
+            .labelled(if self.show_hidden_scenes {
+                fl!("hide-hidden-scenes")
+            } else {
+                fl!("show-hidden-scenes")
+            })
+            .on_press(Message::ToggleHiddenScenes)
+            .into(),
+            widget::button::icon(widget::icon::from_name(if self.scenes_menu_expanded {
+                "pan-up-symbolic"
+            } else {
+                "pan-down-symbolic"
+            }))
+            .labelled(if self.scenes_menu_expanded { fl!("collapse-section") } else { fl!("expand-section") })
+            .on_press(Message::ToggleScenesMenu)
+            .into(),
+        ]);
+
+        if self.scenes_menu_expanded {
+            if self.scenes.is_empty() {
+                return widget::flex_row(vec![scenes_header.into(), widget::text(fl!("no-scenes-found")).into()]).into();
+            }
+
+            // List the scenes under their room or zone, then the ones without a group
+            let mut children: Vec<Element<'a, Message>> = Vec::new();
+            for group in &self.groups {
+                let scenes = self.group_scenes(&group.id);
+                if scenes.is_empty() {
+                    continue;
+                }
+                children.push(self.build_scenes_group_heading(&group.name));
+                children.extend(
+                    scenes
+                        .into_iter()
+                        .map(|scene| self.build_selectable_row(ItemRef::Scene(scene.id.clone()), self.build_scene_item(scene))),
+                );
+            }
+
+            let global_scenes = self.global_scenes();
+            if !global_scenes.is_empty() {
+                children.push(self.build_scenes_group_heading(&fl!("global")));
+                children.extend(
+                    global_scenes
+                        .into_iter()
+                        .map(|scene| self.build_selectable_row(ItemRef::Scene(scene.id.clone()), self.build_scene_item(scene))),
+                );
+            }
+
+            let content =
+                widget::scrollable(widget::column::with_children(children).spacing(0))
+                    .height(Length::Fixed(600.0));
+
+            widget::flex_row(vec![scenes_header.into(), content.into()]).into()
+        } else {
+            widget::flex_row(vec![scenes_header.into()]).into()
+        }
+    }
+
+    /// Build the schedules section with header and schedule controls
+    fn build_schedules_section<'a>(&'a self) -> Element<'a, Message> {
+        let schedules_header = widget::flex_row(vec![
+            widget::text::heading(fl!("schedules"))
+                .align_y(Alignment::Center)
+                .height(30.0)
+                .into(),
+            widget::horizontal_space().into(),
+            widget::button::icon(widget::icon::from_name("list-add-symbolic"))
+                .labelled(fl!("add"))
+                .on_press(Message::NewSchedule)
+                .into(),
+            widget::button::icon(widget::icon::from_name(if self.schedules_menu_expanded {
+                "pan-up-symbolic"
+            } else {
+                "pan-down-symbolic"
+            }))
+            .labelled(if self.schedules_menu_expanded { fl!("collapse-section") } else { fl!("expand-section") })
+            .on_press(Message::ToggleSchedulesMenu)
+            .into(),
+        ]);
+
+        if let Some(editor) = &self.schedule_editor {
+            return widget::flex_row(vec![
+                schedules_header.into(),
+                self.build_schedule_editor(editor).padding(10).into(),
+            ])
+            .into();
+        }
+
+        if self.schedules_menu_expanded {
+            if self.schedules.is_empty() {
+                return widget::flex_row(vec![schedules_header.into(), widget::text(fl!("no-schedules-found")).into()]).into();
+            }
+
+            let children: Vec<_> = self
+                .schedules
+                .iter()
+                .map(|schedule| self.build_schedule_item(schedule).padding(10).into())
+                .collect();
+
+            let content =
+                widget::scrollable(widget::column::with_children(children).spacing(0))
+                    .height(Length::Fixed(600.0));
+
+            widget::flex_row(vec![schedules_header.into(), content.into()]).into()
+        } else {
+            widget::flex_row(vec![schedules_header.into()]).into()
+        }
+    }
+
+    /// Build a single schedule item with controls
+    fn build_schedule_item<'a>(&'a self, schedule: &'a ScheduleVm) -> widget::Column<'a, Message> {
+        let name_toggle_row = widget::flex_row(vec![
+            widget::text(&schedule.name).into(),
+            widget::horizontal_space().into(),
+            widget::toggler(schedule.enabled)
+                .on_toggle(|enabled| Message::ToggleSchedule(schedule.id.clone(), enabled))
+                .into(),
+        ]);
+
+        let mut description_row = vec![
+            widget::text::caption(self.describe_schedule(schedule)).into(),
+            widget::horizontal_space().into(),
+        ];
+        if schedule.time.is_some() && schedule.target.is_some() {
+            description_row.push(
+                widget::button::icon(widget::icon::from_name("document-edit-symbolic"))
+                    .labelled(fl!("edit"))
+                    .on_press(Message::EditSchedule(schedule.id.clone()))
+                    .into(),
+            );
+        }
+        description_row.push(
+            widget::button::icon(widget::icon::from_name("user-trash-symbolic"))
+                .labelled(fl!("delete"))
+                .on_press(Message::DeleteSchedule(schedule.id.clone()))
+                .into(),
+        );
+
+        widget::column::column()
+            .spacing(10.0)
+            .push(name_toggle_row)
+            .push(widget::flex_row(description_row))
+    }
+
+    /// Describe when a schedule is triggered and what it does
+    fn describe_schedule(&self, schedule: &ScheduleVm) -> String {
+        let time = match schedule.time {
+            Some(ScheduleTime::Recurring { weekdays, time }) => {
+                let days: Vec<String> = schedule::WEEKDAYS
+                    .iter()
+                    .zip(weekday_names())
+                    .filter(|(weekday, _)| weekdays & **weekday != 0)
+                    .map(|(_, name)| name)
+                    .collect();
+                fl!("schedule-recurring", days = days.join(", "), time = time.format("%H:%M").to_string())
+            }
+            Some(ScheduleTime::Alarm(date_time)) => {
+                fl!("schedule-alarm", date_time = date_time.format("%Y-%m-%d %H:%M").to_string())
+            }
+            Some(ScheduleTime::Timer(secs)) => fl!("schedule-timer", minutes = secs / 60),
+            None => fl!("schedule-unsupported"),
+        };
+
+        let target = match &schedule.target {
+            Some(ScheduleTarget::Light { id, on }) => self
+                .lights
+                .iter()
+                .find(|light| light.id == *id)
+                .map(|light| (light.name.clone(), *on)),
+            Some(ScheduleTarget::Group { id, on }) => self
+                .groups
+                .iter()
+                .find(|group| group.id == *id)
+                .map(|group| (group.name.clone(), *on)),
+            Some(ScheduleTarget::Scene { scene_id, .. }) => {
+                let name = self
+                    .scenes
+                    .iter()
+                    .find(|scene| scene.id == *scene_id)
+                    .map(|scene| scene.name.clone())
+                    .unwrap_or_else(|| scene_id.clone());
+                return format!("{} · {}", time, name);
+            }
+            None => None,
+        };
+
+        match target {
+            Some((name, true)) => format!("{} · {}", time, fl!("turn-on", name = name)),
+            Some((name, false)) => format!("{} · {}", time, fl!("turn-off", name = name)),
+            None => time,
+        }
+    }
+
+    /// Build the form to create or edit a schedule
+    fn build_schedule_editor<'a>(&'a self, editor: &'a ScheduleEditor) -> widget::Column<'a, Message> {
+        let kind_row = widget::flex_row(vec![
+            widget::radio(
+                widget::text(fl!("schedule-kind-recurring")),
+                ScheduleKind::Recurring,
+                Some(editor.kind),
+                |kind| Message::ScheduleEditor(ScheduleEditorMessage::Kind(kind)),
+            )
+            .into(),
+            widget::radio(
+                widget::text(fl!("schedule-kind-alarm")),
+                ScheduleKind::Alarm,
+                Some(editor.kind),
+                |kind| Message::ScheduleEditor(ScheduleEditorMessage::Kind(kind)),
+            )
+            .into(),
+            widget::radio(
+                widget::text(fl!("schedule-kind-timer")),
+                ScheduleKind::Timer,
+                Some(editor.kind),
+                |kind| Message::ScheduleEditor(ScheduleEditorMessage::Kind(kind)),
+            )
+            .into(),
+        ])
+        .spacing(10);
+
+        let mut column = widget::column::column()
+            .spacing(10.0)
+            .push(
+                widget::text_input(fl!("schedule-name"), &editor.name)
+                    .on_input(|name| Message::ScheduleEditor(ScheduleEditorMessage::Name(name))),
+            )
+            .push(kind_row);
+
+        match editor.kind {
+            ScheduleKind::Recurring => {
+                column = column
+                    .push(build_weekday_buttons(editor.weekdays, |weekday| {
+                        Message::ScheduleEditor(ScheduleEditorMessage::ToggleWeekday(weekday))
+                    }))
+                    .push(
+                        widget::text_input(fl!("schedule-time"), &editor.time)
+                            .on_input(|time| Message::ScheduleEditor(ScheduleEditorMessage::Time(time))),
+                    );
+            }
+            ScheduleKind::Alarm => {
+                column = column.push(widget::flex_row(vec![
+                    widget::text_input(fl!("schedule-date"), &editor.date)
+                        .on_input(|date| Message::ScheduleEditor(ScheduleEditorMessage::Date(date)))
+                        .into(),
+                    widget::text_input(fl!("schedule-time"), &editor.time)
+                        .on_input(|time| Message::ScheduleEditor(ScheduleEditorMessage::Time(time)))
+                        .into(),
+                ]).spacing(10));
+            }
+            ScheduleKind::Timer => {
+                column = column.push(widget::flex_row(vec![
+                    widget::text_input(fl!("schedule-minutes"), &editor.timer_minutes)
+                        .on_input(|minutes| {
+                            Message::ScheduleEditor(ScheduleEditorMessage::TimerMinutes(minutes))
+                        })
+                        .into(),
+                    widget::text(fl!("minutes")).into(),
+                ]).spacing(10));
+            }
+        }
+
+        let target_kind_row = widget::flex_row(vec![
+            widget::radio(
+                widget::text(fl!("light")),
+                ScheduleTargetKind::Light,
+                Some(editor.target_kind),
+                |kind| Message::ScheduleEditor(ScheduleEditorMessage::TargetKind(kind)),
+            )
+            .into(),
+            widget::radio(
+                widget::text(fl!("group")),
+                ScheduleTargetKind::Group,
+                Some(editor.target_kind),
+                |kind| Message::ScheduleEditor(ScheduleEditorMessage::TargetKind(kind)),
+            )
+            .into(),
+            widget::radio(
+                widget::text(fl!("scene")),
+                ScheduleTargetKind::Scene,
+                Some(editor.target_kind),
+                |kind| Message::ScheduleEditor(ScheduleEditorMessage::TargetKind(kind)),
+            )
+            .into(),
+        ])
+        .spacing(10);
+
+        column = column.push(target_kind_row).push(widget::dropdown(
+            &editor.target_labels,
+            editor.target,
+            |index| Message::ScheduleEditor(ScheduleEditorMessage::Target(index)),
+        ));
+
+        if editor.target_kind != ScheduleTargetKind::Scene {
+            column = column.push(widget::flex_row(vec![
+                widget::text(fl!("turn-on-off")).into(),
+                widget::horizontal_space().into(),
+                widget::toggler(editor.on)
+                    .on_toggle(|on| Message::ScheduleEditor(ScheduleEditorMessage::On(on)))
+                    .into(),
+            ]));
+        }
+
+        if let Some(error) = &editor.error {
+            column = column.push(widget::text(fl!("error", error = error.clone())));
+        }
+
+        column.push(widget::flex_row(vec![
+            widget::horizontal_space().into(),
+            widget::button::standard(fl!("cancel"))
+                .on_press(Message::CancelScheduleEditor)
+                .into(),
+            widget::button::suggested(fl!("save"))
+                .on_press(Message::SaveSchedule)
+                .into(),
+        ]).spacing(10))
+    }
+
+    /// Build the wake-up alarms section with header and alarm controls
+    fn build_wake_ups_section<'a>(&'a self) -> Element<'a, Message> {
+        let wake_ups_header = widget::flex_row(vec![
+            widget::text::heading(fl!("wake-ups"))
+                .align_y(Alignment::Center)
+                .height(30.0)
+                .into(),
+            widget::horizontal_space().into(),
+            widget::button::icon(widget::icon::from_name("list-add-symbolic"))
+                .labelled(fl!("add"))
+                .on_press(Message::NewWakeUp)
+                .into(),
+            widget::button::icon(widget::icon::from_name(if self.wake_ups_menu_expanded {
+                "pan-up-symbolic"
+            } else {
+                "pan-down-symbolic"
+            }))
+            .labelled(if self.wake_ups_menu_expanded { fl!("collapse-section") } else { fl!("expand-section") })
+            .on_press(Message::ToggleWakeUpsMenu)
+            .into(),
+        ]);
+
+        if let Some(editor) = &self.wake_up_editor {
+            return widget::flex_row(vec![
+                wake_ups_header.into(),
+                self.build_wake_up_editor(editor).padding(10).into(),
+            ])
+            .into();
+        }
+
+        if self.wake_ups_menu_expanded {
+            if self.wake_ups.is_empty() {
+                return widget::flex_row(vec![wake_ups_header.into(), widget::text(fl!("no-wake-ups-found")).into()]).into();
+            }
+
+            let children: Vec<_> = self
+                .wake_ups
+                .iter()
+                .map(|wake_up| self.build_wake_up_item(wake_up).padding(10).into())
+                .collect();
+
+            let content =
+                widget::scrollable(widget::column::with_children(children).spacing(0))
+                    .height(Length::Fixed(600.0));
+
+            widget::flex_row(vec![wake_ups_header.into(), content.into()]).into()
+        } else {
+            widget::flex_row(vec![wake_ups_header.into()]).into()
+        }
+    }
+
+    /// Build a single wake-up alarm item with controls
+    fn build_wake_up_item<'a>(&'a self, wake_up: &'a WakeUp) -> widget::Column<'a, Message> {
+        let name_toggle_row = widget::flex_row(vec![
+            widget::text(&wake_up.name).into(),
+            widget::horizontal_space().into(),
+            widget::toggler(wake_up.enabled)
+                .on_toggle(|enabled| Message::ToggleWakeUp(wake_up.id.clone(), enabled))
+                .into(),
+        ]);
+
+        let days: Vec<String> = schedule::WEEKDAYS
+            .iter()
+            .zip(weekday_names())
+            .filter(|(weekday, _)| wake_up.weekdays & **weekday != 0)
+            .map(|(_, name)| name)
+            .collect();
+        let group_name = self
+            .groups
+            .iter()
+            .find(|group| group.id == wake_up.group_id)
+            .map(|group| group.name.clone())
+            .unwrap_or_default();
+        let description = fl!(
+            "wake-up-description",
+            days = days.join(", "),
+            time = wake_up.time.format("%H:%M").to_string(),
+            minutes = wake_up.duration_minutes,
+            group_name = group_name
+        );
+
+        widget::column::column()
+            .spacing(10.0)
+            .push(name_toggle_row)
+            .push(widget::flex_row(vec![
+                widget::text::caption(description).into(),
+                widget::horizontal_space().into(),
+                widget::button::icon(widget::icon::from_name("document-edit-symbolic"))
+                    .labelled(fl!("edit"))
+                    .on_press(Message::EditWakeUp(wake_up.id.clone()))
+                    .into(),
+                widget::button::icon(widget::icon::from_name("user-trash-symbolic"))
+                    .labelled(fl!("delete"))
+                    .on_press(Message::DeleteWakeUp(wake_up.id.clone()))
+                    .into(),
+            ]))
+    }
+
+    /// Build the form to create or edit a wake-up alarm
+    fn build_wake_up_editor<'a>(&'a self, editor: &'a WakeUpEditor) -> widget::Column<'a, Message> {
+        let mut column = widget::column::column()
+            .spacing(10.0)
+            .push(
+                widget::text_input(fl!("schedule-name"), &editor.name)
+                    .on_input(|name| Message::WakeUpEditor(WakeUpEditorMessage::Name(name))),
+            )
+            .push(widget::dropdown(&editor.group_labels, editor.group, |index| {
+                Message::WakeUpEditor(WakeUpEditorMessage::Group(index))
+            }))
+            .push(build_weekday_buttons(editor.weekdays, |weekday| {
+                Message::WakeUpEditor(WakeUpEditorMessage::ToggleWeekday(weekday))
+            }))
+            .push(
+                widget::text_input(fl!("schedule-time"), &editor.time)
+                    .on_input(|time| Message::WakeUpEditor(WakeUpEditorMessage::Time(time))),
+            )
+            .push(widget::text(fl!("wake-up-duration")))
+            .push(widget::flex_row(vec![
+                widget::slider(
+                    5.0..=wake_up::MAX_DURATION_MINUTES as f32,
+                    editor.duration_minutes as f32,
+                    |minutes| Message::WakeUpEditor(WakeUpEditorMessage::Duration(minutes as u16)),
+                )
+                .into(),
+                widget::text(fl!("minutes-short", minutes = editor.duration_minutes)).into(),
+            ]));
+
+        if let Some(error) = &editor.error {
+            column = column.push(widget::text(fl!("error", error = error.clone())));
         }
-        Task::none()
-    }
 
-    fn style(&self) -> Option<cosmic::iced_runtime::Appearance> {
-        Some(cosmic::applet::style())
+        column.push(widget::flex_row(vec![
+            widget::horizontal_space().into(),
+            widget::button::standard(fl!("cancel"))
+                .on_press(Message::CancelWakeUpEditor)
+                .into(),
+            widget::button::suggested(fl!("save"))
+                .on_press(Message::SaveWakeUp)
+                .into(),
+        ]).spacing(10))
     }
-}
 
-impl AppModel {
-    /// Build the lights section with header and light controls
-    fn build_lights_section<'a>(&'a self) -> Element<'a, Message> {
-        let lights_header = widget::flex_row(vec![
-            widget::text::heading(fl!("lights"))
+    /// Build the automation rules section with the location and rule controls
+    fn build_automations_section<'a>(&'a self) -> Element<'a, Message> {
+        let automations_header = widget::flex_row(vec![
+            widget::text::heading(fl!("automations"))
                 .align_y(Alignment::Center)
                 .height(30.0)
                 .into(),
             widget::horizontal_space().into(),
-            widget::button::icon(widget::icon::from_name(if self.lights_menu_expanded {
+            widget::button::icon(widget::icon::from_name("list-add-symbolic"))
+                .labelled(fl!("add"))
+                .on_press(Message::NewAutomation)
+                .into(),
+            widget::button::icon(widget::icon::from_name(if self.automations_menu_expanded {
                 "pan-up-symbolic"
             } else {
                 "pan-down-symbolic"
             }))
-            .on_press(Message::ToggleLightsMenu)
+            .labelled(if self.automations_menu_expanded { fl!("collapse-section") } else { fl!("expand-section") })
+            .on_press(Message::ToggleAutomationsMenu)
             .into(),
         ]);
 
-        if self.lights_menu_expanded {
-            if self.lights.is_empty() {
-                return widget::flex_row(vec![lights_header.into(), widget::text(fl!("no-lights-found")).into()]).into();
-            }
+        if let Some(editor) = &self.automation_editor {
+            return widget::flex_row(vec![
+                automations_header.into(),
+                self.build_automation_editor(editor).padding(10).into(),
+            ])
+            .into();
+        }
 
-            let children: Vec<_> = self
-                .lights
+        if !self.automations_menu_expanded {
+            return widget::flex_row(vec![automations_header.into()]).into();
+        }
+
+        let mut children: Vec<Element<'a, Message>> = vec![self.build_location_form().padding(10).into()];
+        if self.config.get_automation_rules().is_empty() {
+            children.push(widget::text(fl!("no-automations-found")).into());
+        }
+        children.extend(
+            self.config
+                .get_automation_rules()
                 .iter()
-                .map(|light| self.build_light_item(light).padding(10).into())
-                .collect();
+                .map(|rule| self.build_automation_item(rule).padding(10).into()),
+        );
 
-            let content =
-                widget::scrollable(widget::column::with_children(children).spacing(0)).spacing(10)
-                    .height(Length::Fixed(600.0));
+        let content = widget::scrollable(widget::column::with_children(children).spacing(0))
+            .height(Length::Fixed(600.0));
 
-            widget::flex_row(vec![lights_header.into(), content.into()]).into()
-        } else {
-            widget::flex_row(vec![lights_header.into()]).into()
+        widget::flex_row(vec![automations_header.into(), content.into()]).into()
+    }
+
+    /// Build the form of the location used to compute the sunrise and sunset
+    fn build_location_form<'a>(&'a self) -> widget::Column<'a, Message> {
+        let mut column = widget::column::column().spacing(10.0).push(
+            widget::flex_row(vec![
+                widget::text_input(fl!("latitude"), &self.latitude_input)
+                    .on_input(Message::SetLatitude)
+                    .into(),
+                widget::text_input(fl!("longitude"), &self.longitude_input)
+                    .on_input(Message::SetLongitude)
+                    .into(),
+                widget::button::standard(fl!("save"))
+                    .on_press(Message::SaveLocation)
+                    .into(),
+            ])
+            .spacing(10),
+        );
+
+        if let Some(error) = &self.location_error {
+            return column.push(widget::text(fl!("error", error = error.clone())));
         }
+
+        let sun_times_text = match self.config.get_location() {
+            Some((latitude, longitude)) => {
+                match sun::sun_times(chrono::Local::now().date_naive(), latitude, longitude) {
+                    Some(sun_times) => fl!(
+                        "sun-times",
+                        sunrise = sun_times
+                            .sunrise
+                            .with_timezone(&chrono::Local)
+                            .format("%H:%M")
+                            .to_string(),
+                        sunset = sun_times
+                            .sunset
+                            .with_timezone(&chrono::Local)
+                            .format("%H:%M")
+                            .to_string()
+                    ),
+                    None => fl!("no-sun-times"),
+                }
+            }
+            None => fl!("location-required"),
+        };
+        column = column.push(widget::text::caption(sun_times_text));
+        column
     }
 
-    /// Build a single light item with controls
-    fn build_light_item<'a>(&'a self, light: &'a LightVm) -> widget::Column<'a, Message> {
-        if let Some(on) = light.on {
-            let name_toggle_row = widget::flex_row(vec![
-                widget::text(&light.name).into(),
+    /// Build a single automation rule item with controls
+    fn build_automation_item<'a>(&'a self, rule: &'a AutomationRule) -> widget::Column<'a, Message> {
+        let name_toggle_row = widget::flex_row(vec![
+            widget::text(&rule.name).into(),
+            widget::horizontal_space().into(),
+            widget::toggler(rule.enabled)
+                .on_toggle(|enabled| Message::ToggleAutomation(rule.id.clone(), enabled))
+                .into(),
+        ]);
+
+        let group_name = |group_id: &str| {
+            self.groups
+                .iter()
+                .find(|group| group.id == group_id)
+                .map(|group| group.name.clone())
+                .unwrap_or_default()
+        };
+        let action = match &rule.action {
+            AutomationAction::Scene { group_id, scene_id } => {
+                let scene_name = self
+                    .scenes
+                    .iter()
+                    .find(|scene| scene.id == *scene_id)
+                    .map(|scene| scene.name.clone())
+                    .unwrap_or_default();
+                format!("{} - {}", scene_name, group_name(group_id))
+            }
+            AutomationAction::Group { group_id, on: true } => {
+                format!("{} - {}", fl!("turn-on"), group_name(group_id))
+            }
+            AutomationAction::Group { group_id, on: false } => {
+                format!("{} - {}", fl!("turn-off"), group_name(group_id))
+            }
+        };
+        let description = format!(
+            "{} · {}",
+            describe_sun_event(rule.event, rule.offset_minutes),
+            action
+        );
+
+        widget::column::column()
+            .spacing(10.0)
+            .push(name_toggle_row)
+            .push(widget::flex_row(vec![
+                widget::text::caption(description).into(),
                 widget::horizontal_space().into(),
-                widget::toggler(on)
-                    .on_toggle(|new_state| Message::ToggleLight(light.id.clone(), new_state))
+                widget::button::icon(widget::icon::from_name("document-edit-symbolic"))
+                    .labelled(fl!("edit"))
+                    .on_press(Message::EditAutomation(rule.id.clone()))
                     .into(),
-            ]);
+                widget::button::icon(widget::icon::from_name("user-trash-symbolic"))
+                    .labelled(fl!("delete"))
+                    .on_press(Message::DeleteAutomation(rule.id.clone()))
+                    .into(),
+            ]))
+    }
 
-            let (light_brightness, light_brightness_percent) = match light.brightness {
-                Some(bri) => (
-                    bri as f32,
-                    ((bri.saturating_sub(1) as f32) / 253.0 * 100.0).round(),
-                ),
-                None => (0.0, 0.0),
-            };
+    /// Build the form to create or edit an automation rule
+    fn build_automation_editor<'a>(&'a self, editor: &'a AutomationEditor) -> widget::Column<'a, Message> {
+        let event_row = widget::flex_row(vec![
+            widget::radio(
+                widget::text(fl!("sunrise")),
+                SunEvent::Sunrise,
+                Some(editor.event),
+                |event| Message::AutomationEditor(AutomationEditorMessage::Event(event)),
+            )
+            .into(),
+            widget::radio(
+                widget::text(fl!("sunset")),
+                SunEvent::Sunset,
+                Some(editor.event),
+                |event| Message::AutomationEditor(AutomationEditorMessage::Event(event)),
+            )
+            .into(),
+        ])
+        .spacing(10);
 
-            // Get the current color as RGB (if available)
-            let (r, g, b) = if let Some((r, g, b)) = light.color {
-                (r, g, b)
-            } else {
-                (0.0, 0.0, 0.0)
-            };
+        let action_kind_row = widget::flex_row(vec![
+            widget::radio(
+                widget::text(fl!("scene")),
+                AutomationActionKind::Scene,
+                Some(editor.action_kind),
+                |kind| Message::AutomationEditor(AutomationEditorMessage::ActionKind(kind)),
+            )
+            .into(),
+            widget::radio(
+                widget::text(fl!("turn-on")),
+                AutomationActionKind::TurnOn,
+                Some(editor.action_kind),
+                |kind| Message::AutomationEditor(AutomationEditorMessage::ActionKind(kind)),
+            )
+            .into(),
+            widget::radio(
+                widget::text(fl!("turn-off")),
+                AutomationActionKind::TurnOff,
+                Some(editor.action_kind),
+                |kind| Message::AutomationEditor(AutomationEditorMessage::ActionKind(kind)),
+            )
+            .into(),
+        ])
+        .spacing(10);
 
-            let color_button = widget::color_picker::color_button(
-                Some(Message::ToggleColorPicker((
-                    light.id.clone(),
-                    "light".to_string(),
-                ))),
-                Some(iced_core::Color::from_rgb(r, g, b)),
-                Length::Fixed(32.0),
-            );
+        let mut column = widget::column::column()
+            .spacing(10.0)
+            .push(
+                widget::text_input(fl!("schedule-name"), &editor.name)
+                    .on_input(|name| Message::AutomationEditor(AutomationEditorMessage::Name(name))),
+            )
+            .push(event_row)
+            .push(widget::flex_row(vec![
+                widget::slider(
+                    -(MAX_AUTOMATION_OFFSET_MINUTES as f32)..=MAX_AUTOMATION_OFFSET_MINUTES as f32,
+                    editor.offset_minutes as f32,
+                    |offset| Message::AutomationEditor(AutomationEditorMessage::Offset(offset as i16)),
+                )
+                .step(5.0)
+                .into(),
+            ]))
+            .push(widget::text::caption(describe_sun_event(editor.event, editor.offset_minutes)))
+            .push(action_kind_row)
+            .push(widget::dropdown(&editor.target_labels, editor.target, |index| {
+                Message::AutomationEditor(AutomationEditorMessage::Target(index))
+            }));
 
-            let color_button =
-                if self.active_color_picker_item == Some((light.id.clone(), "light".to_string())) {
-                    if let Some(tracker) = &self.color_button_tracker {
-                        tracker.container(0u32, color_button).into()
+        if let Some(error) = &editor.error {
+            column = column.push(widget::text(fl!("error", error = error.clone())));
+        }
+
+        column.push(widget::flex_row(vec![
+            widget::horizontal_space().into(),
+            widget::button::standard(fl!("cancel"))
+                .on_press(Message::CancelAutomationEditor)
+                .into(),
+            widget::button::suggested(fl!("save"))
+                .on_press(Message::SaveAutomation)
+                .into(),
+        ]).spacing(10))
+    }
+
+    /// Fill the scenes or groups which can be targeted by the edited automation rule
+    fn set_automation_editor_targets(&self, editor: &mut AutomationEditor) {
+        editor.targets = match editor.action_kind {
+            AutomationActionKind::Scene => self
+                .scenes
+                .iter()
+                .filter(|scene| !scene.hidden)
+                .map(|scene| {
+                    let label = match self.groups.iter().find(|group| group.id == scene.group) {
+                        Some(group) => format!("{} - {}", scene.name, group.name),
+                        None => scene.name.clone(),
+                    };
+                    // Light scenes are not attached to a group, recall them on all the lights
+                    let group_id = if scene.group.is_empty() {
+                        "0".to_string()
                     } else {
-                        color_button.into()
-                    }
-                } else {
-                    color_button.into()
-                };
+                        scene.group.clone()
+                    };
+                    (group_id, scene.id.clone(), label)
+                })
+                .collect(),
+            AutomationActionKind::TurnOn | AutomationActionKind::TurnOff => self
+                .groups
+                .iter()
+                .map(|group| (group.id.clone(), String::new(), group.name.clone()))
+                .collect(),
+        };
+        editor.target_labels = editor.targets.iter().map(|(_, _, label)| label.clone()).collect();
+        editor.target = None;
+    }
 
-            let slider_color_row = widget::flex_row(vec![
-                widget::slider(1.0..=254.0, light_brightness, |new_brightness| {
-                    Message::SetLightBrightness(light.id.clone(), new_brightness)
+    /// Fill the groups which can be woken up by the edited alarm
+    fn set_wake_up_editor_groups(&self, editor: &mut WakeUpEditor, group_id: Option<&str>) {
+        editor.groups = self
+            .groups
+            .iter()
+            .map(|group| (group.id.clone(), group.name.clone()))
+            .collect();
+        editor.group_labels = editor.groups.iter().map(|(_, name)| name.clone()).collect();
+        editor.group = group_id.and_then(|group_id| editor.groups.iter().position(|(id, _)| id == group_id));
+    }
+
+    /// Fill the lights, groups or scenes which can be targeted by the edited schedule
+    fn set_schedule_editor_targets(&self, editor: &mut ScheduleEditor) {
+        editor.targets = match editor.target_kind {
+            ScheduleTargetKind::Light => self
+                .lights
+                .iter()
+                .map(|light| (light.id.clone(), light.name.clone()))
+                .collect(),
+            ScheduleTargetKind::Group => self
+                .groups
+                .iter()
+                .map(|group| (group.id.clone(), group.name.clone()))
+                .collect(),
+            ScheduleTargetKind::Scene => self
+                .scenes
+                .iter()
+                .filter(|scene| !scene.hidden)
+                .map(|scene| {
+                    let label = match self.groups.iter().find(|group| group.id == scene.group) {
+                        Some(group) => format!("{} - {}", scene.name, group.name),
+                        None => scene.name.clone(),
+                    };
+                    (scene.id.clone(), label)
                 })
-                .into(),
-                widget::text(format!("{}%", light_brightness_percent)).into(),
-                widget::horizontal_space().into(),
-                color_button,
-            ]);
+                .collect(),
+        };
+        editor.target_labels = editor.targets.iter().map(|(_, label)| label.clone()).collect();
+        editor.target = None;
+    }
 
-            widget::column::column()
-                .width(Length::Fill)
-                .spacing(10.0)
-                .push(name_toggle_row)
-                .push(slider_color_row)
+    /// Build the lights, groups and scenes matching the search, the chosen one outlined
+    fn build_search_results<'a>(&'a self) -> Element<'a, Message> {
+        let results = &self.search_results;
+        if results.is_empty() {
+            return widget::text(fl!("no-search-results")).into();
+        }
+
+        let mut children: Vec<Element<'a, Message>> = Vec::new();
+        for (index, item) in results.iter().enumerate() {
+            let (heading, row) = match item {
+                ItemRef::Light(light_id) => {
+                    let Some(light) = self.lights.iter().find(|light| light.id == *light_id) else {
+                        continue;
+                    };
+                    (fl!("lights"), self.build_light_item(light))
+                }
+                ItemRef::Group(group_id) => {
+                    let Some(group) = self.groups.iter().find(|group| group.id == *group_id) else {
+                        continue;
+                    };
+                    (fl!("groups"), self.build_group_item(group))
+                }
+                ItemRef::Scene(scene_id) => {
+                    let Some(scene) = self.scenes.iter().find(|scene| scene.id == *scene_id) else {
+                        continue;
+                    };
+                    (fl!("scenes"), self.build_scene_item(scene))
+                }
+            };
+            // Give each kind of result its heading
+            let first_of_kind = index == 0
+                || std::mem::discriminant(&results[index - 1]) != std::mem::discriminant(item);
+            if first_of_kind {
+                children.push(self.build_scenes_group_heading(&heading));
+            }
+
+            children.push(self.build_selectable_row(item.clone(), row));
+        }
+
+        widget::scrollable(widget::column::with_children(children).spacing(0))
+            .id(search_results_id())
+            .height(Length::Shrink)
+            .into()
+    }
+
+    /// Scroll the search results to the row chosen with the keyboard, in proportion to its
+    /// place among the results as the rows differ in height.
+    fn scroll_to_selection(&self) -> Task<Action<Message>> {
+        let Some(selected) = self.selected_item.as_ref() else {
+            return Task::none();
+        };
+        if self.search_query.trim().is_empty() {
+            return Task::none();
+        }
+        let Some(index) = self.search_results.iter().position(|item| item == selected) else {
+            return Task::none();
+        };
+        let last = self.search_results.len().saturating_sub(1).max(1);
+        widget::scrollable::snap_to(
+            search_results_id(),
+            widget::scrollable::RelativeOffset { x: 0.0, y: index as f32 / last as f32 },
+        )
+    }
+
+    /// Build a light, group or scene row, outlined while chosen with the keyboard
+    fn build_selectable_row<'a>(
+        &'a self,
+        item: ItemRef,
+        row: widget::Column<'a, Message>,
+    ) -> Element<'a, Message> {
+        let selected = self.selected_item.as_ref() == Some(&item);
+        widget::container(row.padding(10))
+            .style(move |theme| widget::container::Style {
+                border: cosmic::iced::Border {
+                    color: if selected { theme.cosmic().accent.base.into() } else { iced_core::Color::TRANSPARENT },
+                    width: 2.0,
+                    radius: 8.0.into(),
+                },
+                ..Default::default()
+            })
+            .into()
+    }
+
+    /// The rows reachable with the keyboard, in the order of the popup: the search results, or
+    /// the lights, groups and scenes of the expanded sections
+    fn keyboard_items(&self) -> Vec<ItemRef> {
+        if !self.search_query.trim().is_empty() {
+            return self.search_results.clone();
+        }
+        let visible = |item: &ItemRef| self.edit_mode || !self.config.is_hidden(item);
+        let mut items = Vec::new();
+        for section in Section::ALL {
+            if !self.config.is_section_visible(section) {
+                continue;
+            }
+            match section {
+                Section::Lights if self.lights_menu_expanded => items.extend(
+                    self.lights
+                        .iter()
+                        .map(|light| ItemRef::Light(light.id.clone()))
+                        .filter(visible),
+                ),
+                Section::Groups if self.groups_menu_expanded => items.extend(
+                    self.groups
+                        .iter()
+                        .map(|group| ItemRef::Group(group.id.clone()))
+                        .filter(visible),
+                ),
+                Section::Scenes if self.scenes_menu_expanded => items.extend(
+                    self.listed_scenes()
+                        .into_iter()
+                        .map(|scene| ItemRef::Scene(scene.id.clone())),
+                ),
+                _ => {}
+            }
+        }
+        items
+    }
+
+    /// Match the search again, after it or the lights, groups and scenes changed
+    fn update_search_results(&mut self) {
+        self.search_results = if self.search_query.trim().is_empty() {
+            Vec::new()
         } else {
-            widget::column::column().push(widget::settings::item(
-                &light.name,
-                widget::text(fl!("light-has-no-state", name = light.name.clone())),
-            ))
+            self.find_search_results()
+        };
+    }
+
+    /// The lights, then the groups, then the scenes matching every word of the search by name,
+    /// room or state
+    fn find_search_results(&self) -> Vec<ItemRef> {
+        let words: Vec<String> = self
+            .search_query
+            .to_lowercase()
+            .split_whitespace()
+            .map(str::to_string)
+            .collect();
+        let keyword_on = fl!("search-keyword-on").to_lowercase();
+        let keyword_off = fl!("search-keyword-off").to_lowercase();
+        let keyword_unreachable = fl!("search-keyword-unreachable").to_lowercase();
+        let matches = |names: &[&str], on: Option<bool>, reachable: bool| {
+            words.iter().all(|word| {
+                names.iter().any(|name| name.to_lowercase().contains(word.as_str()))
+                    || (*word == keyword_on && on == Some(true))
+                    || (*word == keyword_off && on == Some(false))
+                    || (*word == keyword_unreachable && !reachable)
+            })
+        };
+        let visible = |item: &ItemRef| self.edit_mode || !self.config.is_hidden(item);
+        let is_reachable = |light_id: &String| {
+            self.lights
+                .iter()
+                .find(|light| light.id == *light_id)
+                .is_none_or(|light| light.reachable)
+        };
+
+        let mut results = Vec::new();
+        for light in &self.lights {
+            // A light is in the rooms and zones of the groups holding it
+            let mut names = vec![light.name.as_str()];
+            names.extend(
+                self.groups
+                    .iter()
+                    .filter(|group| group.lights.contains(&light.id))
+                    .map(|group| group.name.as_str()),
+            );
+            let item = ItemRef::Light(light.id.clone());
+            if visible(&item) && matches(&names, light.on, light.reachable) {
+                results.push(item);
+            }
+        }
+        for group in &self.groups {
+            let reachable = group.lights.iter().all(is_reachable);
+            let item = ItemRef::Group(group.id.clone());
+            if visible(&item) && matches(&[group.name.as_str()], group.on, reachable) {
+                results.push(item);
+            }
+        }
+        for scene in self.scenes.iter().filter(|scene| self.show_hidden_scenes || !scene.hidden) {
+            let mut names = vec![scene.name.as_str()];
+            names.extend(
+                self.groups
+                    .iter()
+                    .find(|group| group.id == scene.group)
+                    .map(|group| group.name.as_str()),
+            );
+            let item = ItemRef::Scene(scene.id.clone());
+            if visible(&item) && matches(&names, None, true) {
+                results.push(item);
+            }
         }
+        results
     }
 
-    /// Build the groups section with header and group controls
-    fn build_groups_section<'a>(&'a self) -> Element<'a, Message> {
-        let groups_header = widget::flex_row(vec![
-            widget::text::heading(fl!("groups"))
+    /// Build the heading of the scenes of a group
+    fn build_scenes_group_heading<'a>(&'a self, name: &str) -> Element<'a, Message> {
+        widget::text::caption_heading(name.to_string())
+            .align_y(Alignment::Center)
+            .height(30.0)
+            .into()
+    }
+
+    /// Build a single scene item with controls
+    fn build_scene_item<'a>(&'a self, scene: &'a SceneVm) -> widget::Column<'a, Message> {
+        if self.edit_mode {
+            return widget::column::column()
+                .push(self.build_edit_item(ItemRef::Scene(scene.id.clone()), &scene.bridge_name));
+        }
+        let scene_row = widget::flex_row(vec![
+            widget::text(&scene.name)
                 .align_y(Alignment::Center)
                 .height(30.0)
                 .into(),
             widget::horizontal_space().into(),
-            widget::button::icon(widget::icon::from_name(if self.groups_menu_expanded {
-                "pan-up-symbolic"
-            } else {
-                "pan-down-symbolic"
-            }))
-            .on_press(Message::ToggleGroupsMenu)
-            .into(),
+            self.build_scene_palette(scene),
+            widget::button::icon(widget::icon::from_name("emblem-system-symbolic"))
+                .labelled(fl!("scene-options"))
+                .on_press(Message::ToggleSceneOptions(scene.id.clone()))
+                .into(),
+            widget::button::icon(widget::icon::from_name("pan-end-symbolic"))
+                .labelled(fl!("activate-scene"))
+                .description(scene.name.as_str())
+                .on_press(Message::ActivateScene(scene.id.clone()))
+                .into(),
         ]);
 
-        if self.groups_menu_expanded {
-            if self.groups.is_empty() {
-                return widget::flex_row(vec![groups_header.into(), widget::text(fl!("no-groups-found")).into()]).into();
-            }
-
-            let children: Vec<_> = self
-                .groups
-                .iter()
-                .map(|group| self.build_group_item(group).padding(10).into())
-                .collect();
+        let column = widget::column::column().spacing(10.0).push(scene_row);
+        if self.scene_options_expanded.as_ref() == Some(&scene.id) {
+            column.push(self.build_scene_options(scene))
+        } else {
+            column
+        }
+    }
 
-            let content =
-                widget::scrollable(widget::column::with_children(children).spacing(0))
-                    .height(Length::Fixed(600.0));
+    /// Build the content of the settings window
+    fn build_settings_window<'a>(&'a self) -> Element<'a, Message> {
+        widget::column::with_children(vec![
+            self.build_bridge_settings(),
+            widget::divider::horizontal::default().into(),
+            self.build_general_settings(),
+            widget::divider::horizontal::default().into(),
+            self.build_section_settings(),
+            widget::divider::horizontal::default().into(),
+            self.build_hidden_item_settings(),
+            widget::divider::horizontal::default().into(),
+            self.build_automation_settings(),
+            widget::divider::horizontal::default().into(),
+            self.build_diagnostics(),
+        ])
+        .spacing(20)
+        .padding(20)
+        .into()
+    }
 
-            widget::flex_row(vec![groups_header.into(), content.into()]).into()
+    /// Build the address, discovery and pairing of the bridge
+    fn build_bridge_settings<'a>(&'a self) -> Element<'a, Message> {
+        let discover_button = if self.is_scanning {
+            widget::button::standard(fl!("searching-for-bridges"))
         } else {
-            widget::flex_row(vec![groups_header.into()]).into()
+            widget::button::standard(fl!("discover-bridge")).on_press(Message::DiscoverBridge)
+        };
+        let mut column = widget::column::column()
+            .spacing(10.0)
+            .push(widget::text::heading(fl!("bridge-settings")))
+            .push(
+                widget::flex_row(vec![
+                    widget::text_input(fl!("bridge-ip"), &self.bridge_ip_input)
+                        .on_input(Message::SetBridgeIpInput)
+                        .into(),
+                    widget::button::standard(fl!("save"))
+                        .on_press(Message::SaveBridgeIp)
+                        .into(),
+                    discover_button.into(),
+                ])
+                .spacing(10),
+            );
+        if let Some(error) = &self.bridge_ip_error {
+            column = column.push(widget::text(fl!("error", error = error.clone())));
+        } else if let Some(Err(error)) = &self.last_discovery {
+            column = column.push(widget::text(fl!("error", error = error.clone())));
         }
+
+        let pairing: Element<'a, Message> = if self.config.get_username().is_some() {
+            widget::flex_row(vec![
+                widget::text(fl!("bridge-paired")).into(),
+                widget::horizontal_space().into(),
+                widget::button::destructive(fl!("unpair-bridge"))
+                    .on_press(Message::UnpairBridge)
+                    .into(),
+            ])
+            .into()
+        } else if self.config.get_bridge_ip().is_some() {
+            widget::flex_row(vec![
+                widget::text(fl!("bridge-found-description")).into(),
+                widget::horizontal_space().into(),
+                widget::button::suggested(fl!("pair-bridge"))
+                    .on_press(Message::PairBridge)
+                    .into(),
+            ])
+            .into()
+        } else {
+            widget::text(fl!("no-bridge-configured")).into()
+        };
+        column.push(pairing).into()
     }
 
-    /// Build a single group item with controls
-    fn build_group_item<'a>(&'a self, group: &'a GroupVm) -> widget::Column<'a, Message> {
-        if let Some(on) = group.on {
-            let name_toggle_row = widget::flex_row(vec![
-                widget::text(&group.name).into(),
+    /// Build the default group, refresh interval and debounce delay settings
+    fn build_general_settings<'a>(&'a self) -> Element<'a, Message> {
+        let options = self.config.get_general();
+        let selected_group = self
+            .config
+            .get_shortcuts()
+            .default_group
+            .as_ref()
+            .and_then(|group_id| self.groups.iter().position(|group| group.id == *group_id));
+        let refresh_interval = options.refresh_interval_secs;
+        let debounce_millis = options.debounce_millis;
+
+        widget::column::column()
+            .spacing(10.0)
+            .push(widget::text::heading(fl!("general")))
+            .push(widget::flex_row(vec![
+                widget::text(fl!("default-group")).into(),
                 widget::horizontal_space().into(),
-                widget::toggler(on)
-                    .on_toggle(|new_state| Message::ToggleGroup(group.id.clone(), new_state))
+                widget::dropdown(&self.group_labels, selected_group, Message::SetShortcutDefaultGroup).into(),
+            ]))
+            .push(build_step_row(
+                fl!("refresh-interval"),
+                fl!("seconds", seconds = refresh_interval),
+                Message::SetRefreshInterval(refresh_interval.saturating_sub(REFRESH_INTERVAL_STEP_SECS)),
+                Message::SetRefreshInterval(refresh_interval.saturating_add(REFRESH_INTERVAL_STEP_SECS)),
+            ))
+            .push(build_step_row(
+                fl!("debounce-delay"),
+                fl!("milliseconds", milliseconds = debounce_millis),
+                Message::SetDebounceDelay(debounce_millis.saturating_sub(DEBOUNCE_STEP_MILLIS)),
+                Message::SetDebounceDelay(debounce_millis.saturating_add(DEBOUNCE_STEP_MILLIS)),
+            ))
+            .push(widget::text::caption(fl!("debounce-delay-description")))
+            .into()
+    }
+
+    /// Build the toggles of the sections of the popup
+    fn build_section_settings<'a>(&'a self) -> Element<'a, Message> {
+        let mut column = widget::column::column()
+            .spacing(10.0)
+            .push(widget::text::heading(fl!("visible-sections")));
+        for section in Section::ALL {
+            column = column.push(widget::flex_row(vec![
+                widget::text(section_name(section)).into(),
+                widget::horizontal_space().into(),
+                widget::toggler(self.config.is_section_visible(section))
+                    .on_toggle(move |visible| Message::SetSectionVisible(section, visible))
                     .into(),
-            ]);
+            ]));
+        }
+        column.into()
+    }
 
-            let (group_brightness, group_brightness_percent) = match group.brightness {
-                Some(bri) => (
-                    bri as f32,
-                    ((bri.saturating_sub(1) as f32) / 253.0 * 100.0).round(),
-                ),
-                None => (0.0, 0.0),
-            };
+    /// Build the toggles showing or hiding each light, group and scene
+    fn build_hidden_item_settings<'a>(&'a self) -> Element<'a, Message> {
+        let item_row = |name: &str, item: ItemRef| -> Element<'a, Message> {
+            let visible = !self.config.is_hidden(&item);
+            widget::flex_row(vec![
+                widget::text(name.to_string()).into(),
+                widget::horizontal_space().into(),
+                widget::toggler(visible)
+                    .on_toggle(move |visible| Message::SetItemHidden(item.clone(), !visible))
+                    .into(),
+            ])
+            .into()
+        };
 
-            // Get the current color as RGB (if available)
-            let (r, g, b) = if let Some((r, g, b)) = group.color {
-                (r, g, b)
-            } else {
-                (0.0, 0.0, 0.0)
+        let mut column = widget::column::column()
+            .spacing(10.0)
+            .push(widget::text::heading(fl!("visible-items")))
+            .push(widget::text::caption_heading(fl!("lights")));
+        for light in &self.lights {
+            column = column.push(item_row(&light.name, ItemRef::Light(light.id.clone())));
+        }
+        column = column.push(widget::text::caption_heading(fl!("groups")));
+        for group in &self.groups {
+            column = column.push(item_row(&group.name, ItemRef::Group(group.id.clone())));
+        }
+        column = column.push(widget::text::caption_heading(fl!("scenes")));
+        for scene in self.scenes.iter().filter(|scene| !scene.hidden) {
+            let label = match self.groups.iter().find(|group| group.id == scene.group) {
+                Some(group) => format!("{} - {}", scene.name, group.name),
+                None => scene.name.clone(),
             };
+            column = column.push(item_row(&label, ItemRef::Scene(scene.id.clone())));
+        }
+        column.into()
+    }
 
-            let color_button = widget::color_picker::color_button(
-                Some(Message::ToggleColorPicker((
-                    group.id.clone(),
-                    "group".to_string(),
-                ))),
-                Some(iced_core::Color::from_rgb(r, g, b)),
-                Length::Fixed(32.0),
-            );
+    /// Build the location and the automation rules, with the rule being edited
+    fn build_automation_settings<'a>(&'a self) -> Element<'a, Message> {
+        let mut column = widget::column::column()
+            .spacing(10.0)
+            .push(widget::flex_row(vec![
+                widget::text::heading(fl!("automations"))
+                    .align_y(Alignment::Center)
+                    .height(30.0)
+                    .into(),
+                widget::horizontal_space().into(),
+                widget::button::icon(widget::icon::from_name("list-add-symbolic"))
+                    .labelled(fl!("add"))
+                    .on_press(Message::NewAutomation)
+                    .into(),
+            ]))
+            .push(self.build_location_form());
 
-            let color_button =
-                if self.active_color_picker_item == Some((group.id.clone(), "group".to_string())) {
-                    if let Some(tracker) = &self.color_button_tracker {
-                        tracker.container(0u32, color_button).into()
-                    } else {
-                        color_button.into()
-                    }
-                } else {
-                    color_button.into()
-                };
+        if let Some(editor) = &self.automation_editor {
+            return column.push(self.build_automation_editor(editor)).into();
+        }
+        if self.config.get_automation_rules().is_empty() {
+            column = column.push(widget::text(fl!("no-automations-found")));
+        }
+        for rule in self.config.get_automation_rules() {
+            column = column.push(self.build_automation_item(rule));
+        }
+        column.into()
+    }
 
-            let slider_color_row = widget::flex_row(vec![
-                widget::slider(1.0..=254.0, group_brightness, |new_brightness| {
-                    Message::SetGroupBrightness(group.id.clone(), new_brightness)
-                })
-                .into(),
-                widget::text(format!("{}%", group_brightness_percent)).into(),
+    /// Build the state of the applet, to help finding out why the lights do not respond
+    fn build_diagnostics<'a>(&'a self) -> Element<'a, Message> {
+        let row = |label: String, value: String| -> Element<'a, Message> {
+            widget::flex_row(vec![
+                widget::text(label).into(),
                 widget::horizontal_space().into(),
-                color_button,
-            ]);
+                widget::text(value).into(),
+            ])
+            .into()
+        };
+        let bridge_ip = match self.config.get_bridge_ip() {
+            Some(bridge_ip) => bridge_ip.to_string(),
+            None => fl!("none"),
+        };
+        let last_refresh = match &self.last_refresh {
+            Some((time, None)) => time.format("%H:%M:%S").to_string(),
+            Some((time, Some(error))) => format!(
+                "{} - {}",
+                time.format("%H:%M:%S"),
+                fl!("error", error = error.clone())
+            ),
+            None => fl!("none"),
+        };
 
-            widget::column::column()
-                .spacing(10.0)
-                .push(name_toggle_row)
-                .push(slider_color_row)
-        } else {
-            widget::column::column().push(widget::settings::item(
-                &group.name,
-                widget::text(format!("Group {} has no state", group.name)),
+        widget::column::column()
+            .spacing(10.0)
+            .push(widget::text::heading(fl!("diagnostics")))
+            .push(row(fl!("applet-version"), env!("CARGO_PKG_VERSION").to_string()))
+            .push(row(fl!("settings-version"), Config::VERSION.to_string()))
+            .push(row(fl!("bridge-ip"), bridge_ip))
+            .push(row(
+                fl!("bridge-reachable"),
+                if self.panel_icon.unreachable { fl!("no") } else { fl!("yes") },
             ))
-        }
+            .push(row(
+                fl!("bridge-paired"),
+                if self.config.get_username().is_some() { fl!("yes") } else { fl!("no") },
+            ))
+            .push(row(fl!("last-refresh"), last_refresh))
+            .push(row(fl!("lights"), self.lights.len().to_string()))
+            .push(row(fl!("groups"), self.groups.len().to_string()))
+            .push(row(fl!("scenes"), self.scenes.len().to_string()))
+            .push(row(fl!("dbus-service"), service::BUS_NAME.to_string()))
+            .into()
     }
 
-    /// Build the scenes section with header and scene controls
-    fn build_scenes_section<'a>(&'a self) -> Element<'a, Message> {
-        let scenes_header = widget::flex_row(vec![
-            widget::text::heading(fl!("scenes"))
-                .align_y(Alignment::Center)
-                .height(30.0)
+    /// Build a light, group or scene in edit mode, with its drag handle, alias and visibility
+    fn build_edit_item<'a>(&'a self, item: ItemRef, bridge_name: &'a str) -> Element<'a, Message> {
+        let alias = match self.pending_aliases.get(&item) {
+            Some((alias, _)) => alias.as_str(),
+            None => self.config.get_alias(&item).unwrap_or(""),
+        };
+        let hidden = self.config.is_hidden(&item);
+        let dragged = self.dragged_item.as_ref() == Some(&item);
+
+        let handle = cosmic::iced::widget::mouse_area(
+            widget::icon::from_name("list-drag-handle-symbolic").size(16),
+        )
+        .on_press(Message::StartDrag(item.clone()));
+        let alias_item = item.clone();
+        let row = widget::flex_row(vec![
+            handle.into(),
+            widget::text_input(bridge_name, alias)
+                .on_input(move |alias| Message::SetAlias(alias_item.clone(), alias))
                 .into(),
-            widget::horizontal_space().into(),
-            widget::button::icon(widget::icon::from_name(if self.scenes_menu_expanded {
-                "pan-up-symbolic"
+            widget::button::icon(widget::icon::from_name(if hidden {
+                "view-conceal-symbolic"
             } else {
-                "pan-down-symbolic"
+                "view-reveal-symbolic"
             }))
-            .on_press(Message::ToggleScenesMenu)
+            .labelled(if hidden { fl!("show-item") } else { fl!("hide-item") })
+            .on_press(Message::SetItemHidden(item.clone(), !hidden))
             .into(),
-        ]);
+        ])
+        .spacing(10);
 
-        if self.scenes_menu_expanded {
-            if self.scenes.is_empty() {
-                return widget::flex_row(vec![scenes_header.into(), widget::text(fl!("no-scenes-found")).into()]).into();
-            }
+        // The dragged item is outlined until it is dropped on another row
+        let row = widget::container(row).padding(4).style(move |theme| widget::container::Style {
+            border: cosmic::iced::Border {
+                color: if dragged { theme.cosmic().accent.base.into() } else { iced_core::Color::TRANSPARENT },
+                width: 2.0,
+                radius: 8.0.into(),
+            },
+            ..Default::default()
+        });
+        cosmic::iced::widget::mouse_area(row)
+            .on_release(Message::DropItem(item))
+            .into()
+    }
 
-            let children: Vec<_> = self
-                .scenes
-                .iter()
-                .map(|scene| self.build_scene_item(scene).padding(10).into())
-                .collect();
+    /// Show the lights with their local names, in the manual order then by name
+    fn arrange_lights(&mut self) {
+        let config = &self.config;
+        for light in &mut self.lights {
+            let item = ItemRef::Light(light.id.clone());
+            light.name = config.get_alias(&item).unwrap_or(&light.bridge_name).to_string();
+        }
+        self.lights.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+        self.lights.sort_by_key(|light| order_position(config, &ItemRef::Light(light.id.clone())));
+        self.light_labels = self.lights.iter().map(|light| light.name.clone()).collect();
+        self.update_search_results();
+    }
 
-            let content =
-                widget::scrollable(widget::column::with_children(children).spacing(0))
-                    .height(Length::Fixed(600.0));
+    /// Show the groups with their local names, in the manual order then by name
+    fn arrange_groups(&mut self) {
+        let config = &self.config;
+        for group in &mut self.groups {
+            let item = ItemRef::Group(group.id.clone());
+            group.name = config.get_alias(&item).unwrap_or(&group.bridge_name).to_string();
+        }
+        self.groups.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+        self.groups.sort_by_key(|group| order_position(config, &ItemRef::Group(group.id.clone())));
+        self.group_labels = self.groups.iter().map(|group| group.name.clone()).collect();
+        self.update_search_results();
+    }
 
-            widget::flex_row(vec![scenes_header.into(), content.into()]).into()
-        } else {
-            widget::flex_row(vec![scenes_header.into()]).into()
+    /// Show the scenes with their local names, in the manual order then by name
+    fn arrange_scenes(&mut self) {
+        let config = &self.config;
+        for scene in &mut self.scenes {
+            let item = ItemRef::Scene(scene.id.clone());
+            scene.name = config.get_alias(&item).unwrap_or(&scene.bridge_name).to_string();
         }
+        self.scenes.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+        self.scenes.sort_by_key(|scene| order_position(config, &ItemRef::Scene(scene.id.clone())));
+        self.update_search_results();
     }
 
-    /// Build a single scene item with controls
-    fn build_scene_item<'a>(&'a self, scene: &'a SceneVm) -> widget::FlexRow<'a, Message> {
-        let group_name =
-            if let Some(group) = self.groups.iter().find(|group| group.id == scene.group) {
-                group.name.clone()
-            } else {
-                fl!("global").to_string()
-            };
-        let display_text = fl!("scene-name-group-name", name = scene.name.clone(), group_name = group_name);
+    /// Build the toggle pinning a light, group or scene at the top of the popup
+    fn build_pin_toggle<'a>(&'a self, pin: ItemRef) -> Element<'a, Message> {
+        let pinned = self.config.get_pins().contains(&pin);
         widget::flex_row(vec![
-            widget::text(display_text)
-                .align_y(Alignment::Center)
-                .height(30.0)
-                .into(),
+            widget::text(fl!("pin-to-top")).into(),
             widget::horizontal_space().into(),
-            widget::button::icon(widget::icon::from_name("pan-end-symbolic"))
-                .on_press(Message::ActivateScene(scene.id.clone()))
+            widget::toggler(pinned)
+                .on_toggle(move |pinned| Message::SetPinned(pin.clone(), pinned))
                 .into(),
         ])
+        .into()
+    }
+
+    /// Build the recall options of a scene
+    fn build_scene_options<'a>(&'a self, scene: &'a SceneVm) -> Element<'a, Message> {
+        let options = match self.pending_scene_options.get(&scene.id) {
+            Some((options, _)) => *options,
+            None => self.config.get_scene_recall_options(&scene.id),
+        };
+
+        let transition_secs = options.transition_secs.unwrap_or(DEFAULT_SCENE_TRANSITION_SECS);
+        let transition_row = widget::flex_row(vec![
+            widget::text(fl!("scene-transition")).into(),
+            widget::horizontal_space().into(),
+            widget::toggler(options.transition_secs.is_some())
+                .on_toggle(move |enabled| {
+                    Message::SetSceneRecallOptions(
+                        scene.id.clone(),
+                        SceneRecallOptions {
+                            transition_secs: enabled.then_some(transition_secs),
+                            ..options
+                        },
+                    )
+                })
+                .into(),
+        ]);
+
+        let brightness_percent = options
+            .brightness_percent
+            .unwrap_or(DEFAULT_SCENE_BRIGHTNESS_PERCENT);
+        let brightness_row = widget::flex_row(vec![
+            widget::text(fl!("scene-brightness")).into(),
+            widget::horizontal_space().into(),
+            widget::toggler(options.brightness_percent.is_some())
+                .on_toggle(move |enabled| {
+                    Message::SetSceneRecallOptions(
+                        scene.id.clone(),
+                        SceneRecallOptions {
+                            brightness_percent: enabled.then_some(brightness_percent),
+                            ..options
+                        },
+                    )
+                })
+                .into(),
+        ]);
+
+        let mut column = widget::column::column()
+            .spacing(10.0)
+            .push(self.build_pin_toggle(ItemRef::Scene(scene.id.clone())))
+            .push(transition_row);
+        if options.transition_secs.is_some() {
+            column = column.push(widget::flex_row(vec![
+                widget::slider(0.0..=300.0, transition_secs as f32, move |secs| {
+                    Message::SetSceneRecallOptions(
+                        scene.id.clone(),
+                        SceneRecallOptions {
+                            transition_secs: Some(secs as u16),
+                            ..options
+                        },
+                    )
+                })
+                .into(),
+                widget::text(fl!("seconds", seconds = transition_secs)).into(),
+            ]));
+        }
+        column = column.push(brightness_row);
+        if options.brightness_percent.is_some() {
+            column = column.push(widget::flex_row(vec![
+                widget::slider(1.0..=100.0, brightness_percent as f32, move |percent| {
+                    Message::SetSceneRecallOptions(
+                        scene.id.clone(),
+                        SceneRecallOptions {
+                            brightness_percent: Some(percent as u8),
+                            ..options
+                        },
+                    )
+                })
+                .into(),
+                widget::text(format!("{}%", brightness_percent)).into(),
+            ]));
+        }
+
+        column.into()
+    }
+
+    /// The visible scenes of a group, recently activated ones first
+    fn group_scenes<'a>(&'a self, group_id: &str) -> Vec<&'a SceneVm> {
+        let mut scenes: Vec<&SceneVm> = self
+            .scenes
+            .iter()
+            .filter(|scene| scene.group == group_id)
+            .filter(|scene| self.show_hidden_scenes || !scene.hidden)
+            .filter(|scene| self.edit_mode || !self.config.is_hidden(&ItemRef::Scene(scene.id.clone())))
+            .collect();
+        self.sort_recent_scenes_first(&mut scenes);
+        scenes
+    }
+
+    /// The scenes which don't belong to a known group
+    fn global_scenes(&self) -> Vec<&SceneVm> {
+        let mut scenes: Vec<&SceneVm> = self
+            .scenes
+            .iter()
+            .filter(|scene| !self.groups.iter().any(|group| group.id == scene.group))
+            .filter(|scene| self.show_hidden_scenes || !scene.hidden)
+            .filter(|scene| self.edit_mode || !self.config.is_hidden(&ItemRef::Scene(scene.id.clone())))
+            .collect();
+        self.sort_recent_scenes_first(&mut scenes);
+        scenes
+    }
+
+    /// The scenes in the order of the scenes section, the number keys activate the first nine
+    fn listed_scenes(&self) -> Vec<&SceneVm> {
+        let mut scenes: Vec<&SceneVm> = self
+            .groups
+            .iter()
+            .flat_map(|group| self.group_scenes(&group.id))
+            .collect();
+        scenes.extend(self.global_scenes());
+        scenes
+    }
+
+    /// Move the recently activated scenes first, unless the scenes were ordered by hand
+    fn sort_recent_scenes_first(&self, scenes: &mut [&SceneVm]) {
+        if self.edit_mode
+            || self
+                .config
+                .get_item_order()
+                .iter()
+                .any(|item| matches!(item, ItemRef::Scene(_)))
+        {
+            return;
+        }
+        let recent_scenes = self.config.get_recent_scenes();
+        scenes.sort_by_key(|scene| {
+            recent_scenes
+                .iter()
+                .position(|id| *id == scene.id)
+                .unwrap_or(usize::MAX)
+        });
     }
 
+    /// Build the strip of color swatches of a scene
+    fn build_scene_palette<'a>(&'a self, scene: &'a SceneVm) -> Element<'a, Message> {
+        let Some(palette) = self.scene_palettes.get(&scene.id) else {
+            return widget::Space::with_width(Length::Shrink).into();
+        };
+
+        let swatches: Vec<_> = palette
+            .iter()
+            .take(MAX_SCENE_SWATCHES)
+            .map(|&(r, g, b)| {
+                widget::container(widget::Space::new(Length::Fixed(12.0), Length::Fixed(12.0)))
+                    .style(move |_theme| widget::container::Style {
+                        background: Some(iced_core::Color::from_rgb(r, g, b).into()),
+                        border: cosmic::iced::Border {
+                            radius: 3.0.into(),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    })
+                    .into()
+            })
+            .collect();
 
+        widget::row::with_children(swatches)
+            .spacing(4)
+            .height(30.0)
+            .align_y(Alignment::Center)
+            .into()
+    }
 
     fn open_color_picker_popup(&mut self) -> Task<cosmic::Action<Message>> {
         let new_id = Id::unique();
@@ -1340,16 +6796,316 @@ impl AppModel {
     }
 }
 
-fn get_bridge(config: &Config) -> Option<huelib::Bridge> {
-    let bridge_ip: IpAddr = match config.get_bridge_ip() {
-        Some(bridge_ip) => *bridge_ip,
-        None => return None,
+/// Durations proposed for the sleep timers, in minutes.
+const SLEEP_TIMER_PRESETS: [u32; 4] = [15, 30, 60, 90];
+
+/// Build the toggle buttons of the weekdays of a recurring schedule
+/// Build a row changing a value with a decrease and an increase button.
+fn build_step_row<'a>(label: String, value: String, decrease: Message, increase: Message) -> Element<'a, Message> {
+    widget::flex_row(vec![
+        widget::text(label).align_y(Alignment::Center).height(30.0).into(),
+        widget::horizontal_space().into(),
+        widget::button::icon(widget::icon::from_name("list-remove-symbolic"))
+            .labelled(fl!("decrease"))
+            .on_press(decrease)
+            .into(),
+        widget::text(value).align_y(Alignment::Center).height(30.0).into(),
+        widget::button::icon(widget::icon::from_name("list-add-symbolic"))
+            .labelled(fl!("increase"))
+            .on_press(increase)
+            .into(),
+    ])
+    .into()
+}
+
+fn build_weekday_buttons<'a>(
+    weekdays: u8,
+    on_toggle: impl Fn(u8) -> Message,
+) -> Element<'a, Message> {
+    let buttons: Vec<_> = schedule::WEEKDAYS
+        .iter()
+        .zip(weekday_names())
+        .map(|(&weekday, name)| {
+            let button = if weekdays & weekday != 0 {
+                widget::button::suggested(name)
+            } else {
+                widget::button::standard(name)
+            };
+            button.on_press(on_toggle(weekday)).into()
+        })
+        .collect();
+    widget::flex_row(buttons).spacing(4).into()
+}
+
+/// Whether an automation rule was triggered between two instants.
+fn automation_due(
+    rule: &AutomationRule,
+    latitude: f64,
+    longitude: f64,
+    since: chrono::DateTime<chrono::Local>,
+    now: chrono::DateTime<chrono::Local>,
+) -> bool {
+    let offset = chrono::Duration::minutes(rule.offset_minutes.into());
+    sun::triggered_between(latitude, longitude, &since, &now, |sun_times| {
+        let event = match rule.event {
+            SunEvent::Sunrise => sun_times.sunrise,
+            SunEvent::Sunset => sun_times.sunset,
+        };
+        event + offset
+    })
+}
+
+/// Describe when an automation rule is triggered.
+fn describe_sun_event(event: SunEvent, offset_minutes: i16) -> String {
+    let minutes = offset_minutes.unsigned_abs();
+    match (event, offset_minutes.signum()) {
+        (SunEvent::Sunrise, 0) => fl!("at-sunrise"),
+        (SunEvent::Sunrise, -1) => fl!("before-sunrise", minutes = minutes),
+        (SunEvent::Sunrise, _) => fl!("after-sunrise", minutes = minutes),
+        (SunEvent::Sunset, 0) => fl!("at-sunset"),
+        (SunEvent::Sunset, -1) => fl!("before-sunset", minutes = minutes),
+        (SunEvent::Sunset, _) => fl!("after-sunset", minutes = minutes),
+    }
+}
+
+/// Brightness change of a step, out of 254.
+fn brightness_step(percent: u8) -> u8 {
+    (u32::from(percent) * 254 / 100).clamp(1, 254) as u8
+}
+
+/// Icon buttons named to the screen readers by the text of their tooltip.
+trait Labelled {
+    fn labelled(self, label: String) -> Self;
+}
+
+impl Labelled for widget::button::Button<'_, Message> {
+    fn labelled(self, label: String) -> Self {
+        self.name(label.clone()).tooltip(label)
+    }
+}
+
+/// Turn a key press in the popup into a message.
+///
+/// Enter is left to the search field, which presses the chosen row on submit.
+fn keyboard_message(event: cosmic::iced::Event, status: cosmic::iced::event::Status) -> Option<Message> {
+    let cosmic::iced::Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }) = event else {
+        return None;
+    };
+    // The other keys typed in a field stay in it, Escape leaves the field first
+    let typing = status == cosmic::iced::event::Status::Captured;
+    match key.as_ref() {
+        Key::Named(Named::Tab) => Some(Message::MoveSelection(if modifiers.shift() { -1 } else { 1 })),
+        Key::Named(Named::ArrowDown) => Some(Message::MoveSelection(1)),
+        Key::Named(Named::ArrowUp) => Some(Message::MoveSelection(-1)),
+        _ if typing => None,
+        Key::Named(Named::Space) => Some(Message::PressSelection),
+        Key::Named(Named::ArrowLeft) => Some(Message::AdjustSelectionBrightness(-1)),
+        Key::Named(Named::ArrowRight) => Some(Message::AdjustSelectionBrightness(1)),
+        Key::Named(Named::Escape) => Some(Message::CloseTopPopup),
+        Key::Character("c") => Some(Message::OpenSelectionColorPicker),
+        Key::Character("/") => Some(Message::FocusSearch),
+        Key::Character(digit) => digit
+            .parse::<usize>()
+            .ok()
+            .filter(|number| (1..=9).contains(number))
+            .map(|number| Message::ActivateSceneNumber(number - 1)),
+        _ => None,
+    }
+}
+
+/// The search field of the popup, focused with `/`.
+fn search_input_id() -> widget::Id {
+    widget::Id::new("search")
+}
+
+/// The scrollable list of the search results, scrolled along with the keyboard selection.
+fn search_results_id() -> widget::Id {
+    widget::Id::new("search-results")
+}
+
+/// The name field of the light being renamed.
+fn light_name_input_id() -> widget::Id {
+    widget::Id::new("light-name")
+}
+
+/// Read the level of a group, from its first light like the groups list does.
+fn panel_group_level(bridge: &huelib::Bridge, group_id: &str) -> Result<PanelLevel, huelib::Error> {
+    let group = bridge.get_group(group_id)?;
+    let brightness = match group.lights.first() {
+        Some(light_id) => bridge.get_light(light_id)?.state.brightness.unwrap_or(0),
+        None => 0,
     };
-    let username = match config.get_username() {
-        Some(username) => username.to_owned(),
-        None => return None,
+    Ok(PanelLevel {
+        group_id: group.id,
+        name: group.name,
+        on: group.state.is_some_and(|state| state.any_on),
+        brightness,
+    })
+}
+
+/// Place of an item in the manual order, the items without one coming last.
+fn order_position(config: &Config, item: &ItemRef) -> usize {
+    config
+        .get_item_order()
+        .iter()
+        .position(|other| other == item)
+        .unwrap_or(usize::MAX)
+}
+
+/// Name of a section of the popup.
+fn section_name(section: Section) -> String {
+    match section {
+        Section::Lights => fl!("lights"),
+        Section::Groups => fl!("groups"),
+        Section::Scenes => fl!("scenes"),
+        Section::Schedules => fl!("schedules"),
+        Section::WakeUps => fl!("wake-ups"),
+        Section::Automations => fl!("automations"),
+        Section::Focus => fl!("focus-timer"),
+    }
+}
+
+/// Name of an urgency level of the notifications.
+fn urgency_name(urgency: Urgency) -> String {
+    match urgency {
+        Urgency::Low => fl!("urgency-low"),
+        Urgency::Normal => fl!("urgency-normal"),
+        Urgency::Critical => fl!("urgency-critical"),
+    }
+}
+
+/// Names of the `FLASH_COLORS`.
+fn flash_color_names() -> [String; 4] {
+    [fl!("red"), fl!("orange"), fl!("green"), fl!("blue")]
+}
+
+/// Short names of the weekdays, from Monday to Sunday.
+fn weekday_names() -> [String; 7] {
+    [
+        fl!("monday-short"),
+        fl!("tuesday-short"),
+        fl!("wednesday-short"),
+        fl!("thursday-short"),
+        fl!("friday-short"),
+        fl!("saturday-short"),
+        fl!("sunday-short"),
+    ]
+}
+
+/// Transition time proposed when enabling it for a scene, in seconds.
+const DEFAULT_SCENE_TRANSITION_SECS: u16 = 30;
+
+/// Brightness proposed when enabling the brightness override of a scene.
+const DEFAULT_SCENE_BRIGHTNESS_PERCENT: u8 = 50;
+
+/// Commands of the command-line tool to bind to keyboard shortcuts.
+const SHORTCUT_COMMANDS: [&str; 6] = [
+    "cosmic-hue toggle",
+    "cosmic-hue brighter",
+    "cosmic-hue dimmer",
+    "cosmic-hue warmer",
+    "cosmic-hue cooler",
+    "cosmic-hue next-scene",
+];
+
+/// Touchpad scroll distance making a brightness step of the default group, in pixels.
+const SCROLL_PIXELS_PER_STEP: f32 = 40.0;
+
+/// Shortest and longest intervals between two refreshes of the lights, in seconds.
+const MIN_REFRESH_INTERVAL_SECS: u16 = 5;
+const MAX_REFRESH_INTERVAL_SECS: u16 = 600;
+
+/// Change of the refresh interval by the step buttons, in seconds.
+const REFRESH_INTERVAL_STEP_SECS: u16 = 5;
+
+/// Longest delay before sending the changes made with the sliders, in milliseconds.
+const MAX_DEBOUNCE_MILLIS: u16 = 2000;
+
+/// Change of the debounce delay by the step buttons, in milliseconds.
+const DEBOUNCE_STEP_MILLIS: u16 = 50;
+
+/// How long the level of the default group stays shown.
+const PANEL_LEVEL_DURATION: Duration = Duration::from_millis(1500);
+
+/// Longest phase of the focus timer, in minutes.
+const MAX_FOCUS_MINUTES: u16 = 120;
+
+/// Hue and saturation of the colors a light can flash with on notifications.
+const FLASH_COLORS: [(u16, u8); 4] = [(0, 254), (5000, 254), (25500, 254), (46920, 254)];
+
+/// Inactivity proposed before dimming the idle group, in minutes.
+const DEFAULT_IDLE_DIM_MINUTES: u16 = 10;
+
+/// Brightness proposed for the dimmed idle group.
+const DEFAULT_IDLE_DIM_BRIGHTNESS_PERCENT: u8 = 20;
+
+/// Duration of the adjustments of the adaptive lighting, in multiples of 100ms.
+const ADAPTIVE_TRANSITION_TIME: u16 = 50;
+
+/// Difference below which a light is considered to follow the adaptive lighting.
+const ADAPTIVE_TOLERANCE: u8 = 5;
+
+/// Largest delay of an automation rule from sunrise or sunset, in minutes.
+const MAX_AUTOMATION_OFFSET_MINUTES: i16 = 180;
+
+/// Delay after which the automation rules missed while asleep are no longer run, in minutes.
+const AUTOMATION_CATCH_UP_MINUTES: i64 = 5;
+
+/// Maximum number of color swatches displayed for a scene.
+const MAX_SCENE_SWATCHES: usize = 6;
+
+/// Extract the distinct colors of the lights which are on in a scene.
+fn scene_palette(scene: &huelib::resource::Scene) -> Vec<(f32, f32, f32)> {
+    let Some(light_states) = scene.light_states.as_ref() else {
+        return Vec::new();
     };
-    Some(huelib::Bridge::new(bridge_ip, username))
+
+    // Sort by light id to keep the swatches in a stable order
+    let mut light_states: Vec<_> = light_states.iter().collect();
+    light_states.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut palette: Vec<(f32, f32, f32)> = Vec::new();
+    for (_, state) in light_states {
+        if state.on == Some(false) {
+            continue;
+        }
+        let color = if let Some((x, y)) = state.color_space_coordinates {
+            xy_to_rgb(x, y, state.brightness)
+        } else if let Some(ct) = state.color_temperature {
+            mired_to_rgb(ct, state.brightness)
+        } else if state.hue.is_some() {
+            hsv_to_rgb(state.hue, state.saturation, state.brightness)
+        } else {
+            // Dimmable lights only have a brightness
+            mired_to_rgb(366, state.brightness)
+        };
+        if !palette.contains(&color) {
+            palette.push(color);
+        }
+    }
+    palette
+}
+
+/// The color of the text of a theme, drawn on the panel.
+fn theme_foreground(theme: &cosmic::cosmic_theme::Theme) -> (f32, f32, f32) {
+    let color = theme.on_bg_color();
+    (color.red, color.green, color.blue)
+}
+
+/// The color of a light at full brightness, in the color mode it is in.
+fn light_tint(state: &huelib::resource::light::State) -> Option<(f32, f32, f32)> {
+    match state.color_mode.as_ref()? {
+        ColorMode::ColorSpaceCoordinates => state
+            .color_space_coordinates
+            .map(|(x, y)| xy_to_rgb(x, y, Some(254))),
+        ColorMode::ColorTemperature => state
+            .color_temperature
+            .map(|mired| mired_to_rgb(mired, Some(254))),
+        ColorMode::HueAndSaturation => state
+            .hue
+            .zip(state.saturation)
+            .map(|(hue, saturation)| hsv_to_rgb(Some(hue), Some(saturation), Some(254))),
+    }
 }
 
 fn hsv_palette_to_hsv_lib(color: palette::Hsv) -> (u16, u8, u8) {
@@ -1399,3 +7155,64 @@ fn hsv_to_rgb(hue: Option<u16>, saturation: Option<u8>, brightness: Option<u8>)
         (0.0, 0.0, 0.0) // fallback color
     }
 }
+
+fn xy_to_rgb(x: f32, y: f32, brightness: Option<u8>) -> (f32, f32, f32) {
+    if y <= 0.0 {
+        return (0.0, 0.0, 0.0);
+    }
+
+    // Convert from CIE xy with full luminance to linear sRGB (Wide RGB D65)
+    let z = 1.0 - x - y;
+    let big_y = 1.0;
+    let big_x = big_y / y * x;
+    let big_z = big_y / y * z;
+
+    let r = big_x * 1.656492 - big_y * 0.354851 - big_z * 0.255038;
+    let g = -big_x * 0.707196 + big_y * 1.655397 + big_z * 0.036152;
+    let b = big_x * 0.051713 - big_y * 0.121364 + big_z * 1.011530;
+
+    // Normalize, apply the gamma correction and the brightness
+    let max = r.max(g).max(b).max(f32::EPSILON);
+    let gamma = |c: f32| {
+        let c = (c / max).max(0.0);
+        if c <= 0.0031308 {
+            12.92 * c
+        } else {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        }
+    };
+    let v = brightness.map(|bri| bri as f32 / 254.0).unwrap_or(1.0);
+
+    (gamma(r) * v, gamma(g) * v, gamma(b) * v)
+}
+
+fn mired_to_rgb(mired: u16, brightness: Option<u8>) -> (f32, f32, f32) {
+    // Approximation of the black body color for a temperature in Kelvin
+    let kelvin = 1_000_000.0 / mired.max(1) as f32;
+    let t = kelvin / 100.0;
+
+    let r = if t <= 66.0 {
+        255.0
+    } else {
+        329.698_73 * (t - 60.0).powf(-0.133_204_76)
+    };
+    let g = if t <= 66.0 {
+        99.470_8 * t.ln() - 161.119_57
+    } else {
+        288.122_16 * (t - 60.0).powf(-0.075_514_846)
+    };
+    let b = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.517_73 * (t - 10.0).ln() - 305.044_8
+    };
+    let v = brightness.map(|bri| bri as f32 / 254.0).unwrap_or(1.0);
+
+    (
+        (r / 255.0).clamp(0.0, 1.0) * v,
+        (g / 255.0).clamp(0.0, 1.0) * v,
+        (b / 255.0).clamp(0.0, 1.0) * v,
+    )
+}
@@ -0,0 +1,33 @@
+// SPDX-License-Identifier: MIT
+
+//! Turning a light red while the user is busy.
+
+use huelib::Bridge;
+use huelib::resource::light;
+use huelib::resource::Adjust;
+
+use crate::snapshot::Snapshot;
+
+/// Hue and saturation of the light while busy.
+const BUSY_HUE: u16 = 0;
+const BUSY_SATURATION: u8 = 254;
+
+/// Turn a light red.
+///
+/// Returns the state of the light before the change, to restore it once available.
+pub fn set_busy(bridge: &Bridge, light_id: &str) -> Result<Snapshot, huelib::Error> {
+    let snapshot = Snapshot::capture(bridge, &[light_id.to_string()])?;
+    let modifier = light::StateModifier::new()
+        .with_on(true)
+        .with_hue(Adjust::Override(BUSY_HUE))
+        .with_saturation(Adjust::Override(BUSY_SATURATION))
+        .with_brightness(Adjust::Override(254));
+    bridge.set_light_state(light_id, &modifier)?;
+    Ok(snapshot)
+}
+
+/// Put the light back as it was before being busy, unless it was turned off since.
+pub fn restore(bridge: &Bridge, snapshot: &Snapshot) -> Result<(), huelib::Error> {
+    snapshot.restore(bridge, true, None)?;
+    Ok(())
+}
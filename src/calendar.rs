@@ -0,0 +1,558 @@
+// SPDX-License-Identifier: MIT
+
+//! Busy periods read from a local iCalendar file, such as a CalDAV export.
+//!
+//! Times with a `TZID` are read in the local time zone, the time zone definitions of the file
+//! being ignored. Recurring events support the common daily, weekly, monthly and yearly rules.
+
+use std::path::Path;
+
+use chrono::{
+    DateTime, Datelike, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, TimeZone, Weekday,
+};
+
+/// An event of the calendar making the user busy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BusyEvent {
+    pub uid: String,
+    pub summary: String,
+    start: NaiveDateTime,
+    /// Whether the times are in UTC, instead of the local time zone.
+    utc: bool,
+    duration: TimeDelta,
+    recurrence: Option<Recurrence>,
+    /// Starts of the cancelled occurrences.
+    exceptions: Vec<NaiveDateTime>,
+}
+
+/// An occurrence of an event in progress.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Occurrence {
+    pub uid: String,
+    pub summary: String,
+    /// The start of the occurrence, in UTC or in the local time zone like its event.
+    pub start: NaiveDateTime,
+}
+
+impl Occurrence {
+    /// Whether two occurrences are the same occurrence of the same event, even renamed.
+    pub fn is_same(&self, other: &Occurrence) -> bool {
+        self.uid == other.uid && self.start == other.start
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Recurrence {
+    frequency: Frequency,
+    interval: u32,
+    count: Option<u32>,
+    until: Option<NaiveDateTime>,
+    /// Days of the week of a weekly event, the day of its start when empty.
+    weekdays: Vec<Weekday>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// An event being read.
+#[derive(Default)]
+struct EventProperties {
+    uid: String,
+    summary: String,
+    start: Option<(NaiveDateTime, bool, bool)>,
+    end: Option<(NaiveDateTime, bool, bool)>,
+    duration: Option<TimeDelta>,
+    recurrence: Option<Recurrence>,
+    exceptions: Vec<NaiveDateTime>,
+    free: bool,
+    cancelled: bool,
+}
+
+/// Read the busy events of an iCalendar file.
+pub fn load(path: &Path) -> std::io::Result<Vec<BusyEvent>> {
+    Ok(parse(&std::fs::read_to_string(path)?))
+}
+
+/// Read the busy events of an iCalendar document, skipping the free and cancelled ones.
+pub fn parse(text: &str) -> Vec<BusyEvent> {
+    let mut events = Vec::new();
+    let mut event: Option<EventProperties> = None;
+    // Components nested in an event, like alarms, have properties of their own
+    let mut nested_depth = 0;
+
+    for line in unfold(text) {
+        let Some((name, params, value)) = split_line(&line) else {
+            continue;
+        };
+        let name = name.to_ascii_uppercase();
+
+        match (name.as_str(), event.as_mut()) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VEVENT") => {
+                event = Some(EventProperties::default());
+            }
+            ("BEGIN", Some(_)) => nested_depth += 1,
+            ("END", Some(_)) if nested_depth > 0 => nested_depth -= 1,
+            ("END", Some(_)) => {
+                if let Some(busy_event) = event.take().and_then(EventProperties::into_busy_event) {
+                    events.push(busy_event);
+                }
+            }
+            (_, Some(_)) if nested_depth > 0 => {}
+            ("UID", Some(event)) => event.uid = value.to_string(),
+            ("SUMMARY", Some(event)) => event.summary = unescape(value),
+            ("DTSTART", Some(event)) => event.start = parse_date_time(value, &params),
+            ("DTEND", Some(event)) => event.end = parse_date_time(value, &params),
+            ("DURATION", Some(event)) => event.duration = parse_duration(value),
+            ("RRULE", Some(event)) => event.recurrence = parse_recurrence(value),
+            ("EXDATE", Some(event)) => event.exceptions.extend(
+                value
+                    .split(',')
+                    .filter_map(|value| parse_date_time(value, &params))
+                    .map(|(date_time, _, _)| date_time),
+            ),
+            ("TRANSP", Some(event)) => event.free = value.eq_ignore_ascii_case("TRANSPARENT"),
+            ("X-MICROSOFT-CDO-BUSYSTATUS", Some(event)) => {
+                event.free = value.eq_ignore_ascii_case("FREE")
+            }
+            ("STATUS", Some(event)) => event.cancelled = value.eq_ignore_ascii_case("CANCELLED"),
+            _ => {}
+        }
+    }
+    events
+}
+
+/// The occurrence of an event in progress, if any.
+///
+/// The times of the events without a time zone are read in the time zone of `now`.
+pub fn current_event<Tz: TimeZone>(events: &[BusyEvent], now: &DateTime<Tz>) -> Option<Occurrence> {
+    events.iter().find_map(|event| {
+        event.occurrence_in_progress(now).map(|start| Occurrence {
+            uid: event.uid.clone(),
+            summary: event.summary.clone(),
+            start,
+        })
+    })
+}
+
+impl EventProperties {
+    fn into_busy_event(self) -> Option<BusyEvent> {
+        if self.free || self.cancelled {
+            return None;
+        }
+        let (start, utc, all_day) = self.start?;
+        let duration = match (self.end, self.duration) {
+            (Some((end, _, _)), _) => end - start,
+            (None, Some(duration)) => duration,
+            // An event without an end lasts the whole day, or is instantaneous
+            (None, None) if all_day => TimeDelta::days(1),
+            (None, None) => TimeDelta::zero(),
+        };
+        Some(BusyEvent {
+            uid: self.uid,
+            summary: self.summary,
+            start,
+            utc,
+            duration,
+            recurrence: self.recurrence,
+            exceptions: self.exceptions,
+        })
+    }
+}
+
+impl BusyEvent {
+    /// The start of the occurrence of the event in progress, if any.
+    pub fn occurrence_in_progress<Tz: TimeZone>(&self, now: &DateTime<Tz>) -> Option<NaiveDateTime> {
+        let now = if self.utc {
+            now.naive_utc()
+        } else {
+            now.naive_local()
+        };
+        self.starts_until(now)
+            .into_iter()
+            .rev()
+            .take_while(|start| *start + self.duration > now)
+            .find(|start| !self.exceptions.contains(start))
+    }
+
+    /// The starts of the occurrences of the event up to a time.
+    fn starts_until(&self, limit: NaiveDateTime) -> Vec<NaiveDateTime> {
+        let Some(recurrence) = &self.recurrence else {
+            return if self.start <= limit {
+                vec![self.start]
+            } else {
+                Vec::new()
+            };
+        };
+
+        let mut starts = Vec::new();
+        for period in 0u32.. {
+            // The dates overflow
+            let Some(period_starts) = recurrence.period_starts(self.start, period) else {
+                break;
+            };
+            for start in period_starts {
+                // The first week of a weekly event can have days before its start
+                if start < self.start {
+                    continue;
+                }
+                if start > limit
+                    || recurrence.until.is_some_and(|until| start > until)
+                    || recurrence.count.is_some_and(|count| starts.len() >= count as usize)
+                {
+                    return starts;
+                }
+                starts.push(start);
+            }
+        }
+        starts
+    }
+}
+
+impl Recurrence {
+    /// The starts of the occurrences in a period of the recurrence, in order.
+    ///
+    /// Returns `None` once the dates overflow.
+    fn period_starts(&self, start: NaiveDateTime, period: u32) -> Option<Vec<NaiveDateTime>> {
+        let periods = period.checked_mul(self.interval)?;
+        match self.frequency {
+            Frequency::Daily => Some(vec![
+                start.checked_add_signed(TimeDelta::days(periods.into()))?,
+            ]),
+            Frequency::Weekly => {
+                let week_start = start.checked_add_signed(TimeDelta::weeks(periods.into()))?;
+                if self.weekdays.is_empty() {
+                    return Some(vec![week_start]);
+                }
+                let monday = week_start.date()
+                    - TimeDelta::days(week_start.weekday().num_days_from_monday().into());
+                let mut starts: Vec<NaiveDateTime> = self
+                    .weekdays
+                    .iter()
+                    .map(|weekday| {
+                        (monday + TimeDelta::days(weekday.num_days_from_monday().into()))
+                            .and_time(start.time())
+                    })
+                    .collect();
+                starts.sort();
+                Some(starts)
+            }
+            Frequency::Monthly => month_start(start, periods),
+            Frequency::Yearly => month_start(start, periods.checked_mul(12)?),
+        }
+    }
+}
+
+/// The start of an event some months later, none in the months without its day like the 31st.
+fn month_start(start: NaiveDateTime, months: u32) -> Option<Vec<NaiveDateTime>> {
+    // The months are added to the first day to avoid moving to the last day of a shorter month
+    let month = start.with_day(1)?.checked_add_months(Months::new(months))?;
+    Some(month.with_day(start.day()).into_iter().collect())
+}
+
+/// Join the lines folded over several lines of the file.
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// The name, the parameters and the value of a property.
+type Property<'a> = (&'a str, Vec<(&'a str, &'a str)>, &'a str);
+
+/// Split a line in the name, the parameters and the value of its property.
+fn split_line(line: &str) -> Option<Property<'_>> {
+    let mut quoted = false;
+    let (colon, _) = line.char_indices().find(|&(_, c)| {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        c == ':' && !quoted
+    })?;
+    let mut parts = line[..colon].split(';');
+    let name = parts.next()?;
+    let params = parts.filter_map(|param| param.split_once('=')).collect();
+    Some((name, params, &line[colon + 1..]))
+}
+
+/// Remove the escaping of a text value.
+fn unescape(value: &str) -> String {
+    value
+        .replace("\\n", " ")
+        .replace("\\N", " ")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
+
+/// Read a date or a date and time.
+///
+/// Returns the time, whether it is in UTC and whether it is a whole day.
+fn parse_date_time(value: &str, params: &[(&str, &str)]) -> Option<(NaiveDateTime, bool, bool)> {
+    let value = value.trim();
+    let is_date = params
+        .iter()
+        .any(|(name, value)| name.eq_ignore_ascii_case("VALUE") && value.eq_ignore_ascii_case("DATE"))
+        || value.len() == 8;
+    if is_date {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return Some((date.and_time(NaiveTime::MIN), false, true));
+    }
+    let (value, utc) = match value.strip_suffix('Z') {
+        Some(value) => (value, true),
+        None => (value, false),
+    };
+    let date_time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    Some((date_time, utc, false))
+}
+
+/// Read a duration like `PT1H30M` or `P1D`.
+fn parse_duration(value: &str) -> Option<TimeDelta> {
+    let value = value.trim();
+    let (value, negative) = match value.strip_prefix('-') {
+        Some(value) => (value, true),
+        None => (value.strip_prefix('+').unwrap_or(value), false),
+    };
+    let mut duration = TimeDelta::zero();
+    let mut number = String::new();
+    let mut in_time = false;
+    for c in value.strip_prefix('P')?.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => in_time = true,
+            _ => {
+                let amount: i64 = number.parse().ok()?;
+                number.clear();
+                duration += match (c, in_time) {
+                    ('W', false) => TimeDelta::weeks(amount),
+                    ('D', false) => TimeDelta::days(amount),
+                    ('H', true) => TimeDelta::hours(amount),
+                    ('M', true) => TimeDelta::minutes(amount),
+                    ('S', true) => TimeDelta::seconds(amount),
+                    _ => return None,
+                };
+            }
+        }
+    }
+    Some(if negative { -duration } else { duration })
+}
+
+/// Read a recurrence rule like `FREQ=WEEKLY;BYDAY=MO,WE;COUNT=10`.
+fn parse_recurrence(value: &str) -> Option<Recurrence> {
+    let mut frequency = None;
+    let mut recurrence = Recurrence {
+        frequency: Frequency::Daily,
+        interval: 1,
+        count: None,
+        until: None,
+        weekdays: Vec::new(),
+    };
+    for (name, value) in value.split(';').filter_map(|part| part.split_once('=')) {
+        match name.to_ascii_uppercase().as_str() {
+            "FREQ" => {
+                frequency = match value.to_ascii_uppercase().as_str() {
+                    "DAILY" => Some(Frequency::Daily),
+                    "WEEKLY" => Some(Frequency::Weekly),
+                    "MONTHLY" => Some(Frequency::Monthly),
+                    "YEARLY" => Some(Frequency::Yearly),
+                    // Events recurring several times a day are not supported
+                    _ => return None,
+                }
+            }
+            "INTERVAL" => recurrence.interval = value.parse().ok().filter(|interval| *interval > 0)?,
+            "COUNT" => recurrence.count = Some(value.parse().ok()?),
+            "UNTIL" => recurrence.until = parse_date_time(value, &[]).map(|(until, _, all_day)| {
+                // The whole last day is included
+                if all_day {
+                    until + TimeDelta::days(1) - TimeDelta::seconds(1)
+                } else {
+                    until
+                }
+            }),
+            "BYDAY" => {
+                recurrence.weekdays = value
+                    .split(',')
+                    // The position of the day in the month is ignored, like in `1MO`
+                    .filter_map(|day| day.get(day.len().saturating_sub(2)..))
+                    .filter_map(|day| match day.to_ascii_uppercase().as_str() {
+                        "MO" => Some(Weekday::Mon),
+                        "TU" => Some(Weekday::Tue),
+                        "WE" => Some(Weekday::Wed),
+                        "TH" => Some(Weekday::Thu),
+                        "FR" => Some(Weekday::Fri),
+                        "SA" => Some(Weekday::Sat),
+                        "SU" => Some(Weekday::Sun),
+                        _ => None,
+                    })
+                    .collect()
+            }
+            _ => {}
+        }
+    }
+    recurrence.frequency = frequency?;
+    Some(recurrence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    /// A time in a time zone one hour ahead of UTC.
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<FixedOffset> {
+        FixedOffset::east_opt(3600)
+            .unwrap()
+            .with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    fn calendar(event: &str) -> Vec<BusyEvent> {
+        parse(&format!(
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:event@example.com\r\n{}\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n",
+            event.replace('\n', "\r\n")
+        ))
+    }
+
+    fn in_progress(events: &[BusyEvent], now: DateTime<FixedOffset>) -> bool {
+        current_event(events, &now).is_some()
+    }
+
+    #[test]
+    fn unfolds_the_long_lines() {
+        let events = calendar(
+            "SUMMARY:Weekly planning\n  with the whole\n\tteam\nDTSTART:20250310T090000",
+        );
+        assert_eq!(events[0].summary, "Weekly planning with the wholeteam");
+        assert_eq!(events[0].uid, "event@example.com");
+    }
+
+    #[test]
+    fn ignores_the_properties_of_the_alarms() {
+        let events = calendar(
+            "SUMMARY:Review\nDTSTART:20250310T090000\nBEGIN:VALARM\nSUMMARY:Reminder\nDURATION:PT15M\nEND:VALARM\nDTEND:20250310T100000",
+        );
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].summary, "Review");
+        assert!(in_progress(&events, at(2025, 3, 10, 9, 30)));
+    }
+
+    #[test]
+    fn reads_the_times_with_a_time_zone_in_the_local_time() {
+        let events = calendar(
+            "DTSTART;TZID=Europe/Paris:20250310T090000\nDTEND;TZID=Europe/Paris:20250310T100000",
+        );
+        assert!(!in_progress(&events, at(2025, 3, 10, 8, 59)));
+        assert!(in_progress(&events, at(2025, 3, 10, 9, 0)));
+        assert!(in_progress(&events, at(2025, 3, 10, 9, 59)));
+        // The end is excluded
+        assert!(!in_progress(&events, at(2025, 3, 10, 10, 0)));
+    }
+
+    #[test]
+    fn reads_the_times_in_utc() {
+        let events = calendar("DTSTART:20250310T090000Z\nDURATION:PT1H30M");
+        assert!(!in_progress(&events, at(2025, 3, 10, 9, 30)));
+        assert!(in_progress(&events, at(2025, 3, 10, 10, 0)));
+        assert!(in_progress(&events, at(2025, 3, 10, 11, 29)));
+        assert!(!in_progress(&events, at(2025, 3, 10, 11, 30)));
+    }
+
+    #[test]
+    fn all_day_events_last_the_whole_day() {
+        let events = calendar("DTSTART;VALUE=DATE:20250310");
+        assert!(!in_progress(&events, at(2025, 3, 9, 23, 59)));
+        assert!(in_progress(&events, at(2025, 3, 10, 0, 0)));
+        assert!(in_progress(&events, at(2025, 3, 10, 23, 59)));
+        assert!(!in_progress(&events, at(2025, 3, 11, 0, 0)));
+
+        let events = calendar("DTSTART;VALUE=DATE:20250310\nDTEND;VALUE=DATE:20250312");
+        assert!(in_progress(&events, at(2025, 3, 11, 12, 0)));
+        assert!(!in_progress(&events, at(2025, 3, 12, 0, 0)));
+    }
+
+    #[test]
+    fn skips_the_free_and_cancelled_events() {
+        assert!(calendar("DTSTART:20250310T090000\nTRANSP:TRANSPARENT").is_empty());
+        assert!(calendar("DTSTART:20250310T090000\nX-MICROSOFT-CDO-BUSYSTATUS:FREE").is_empty());
+        assert!(calendar("DTSTART:20250310T090000\nSTATUS:CANCELLED").is_empty());
+        assert!(calendar("SUMMARY:No start").is_empty());
+    }
+
+    #[test]
+    fn expands_the_weekly_events() {
+        // From Monday the 10th of March 2025, on Mondays and Wednesdays, three times
+        let events = calendar(
+            "DTSTART:20250310T090000\nDURATION:PT1H\nRRULE:FREQ=WEEKLY;BYDAY=MO,WE;COUNT=3",
+        );
+        assert!(in_progress(&events, at(2025, 3, 10, 9, 30)));
+        assert!(!in_progress(&events, at(2025, 3, 11, 9, 30)));
+        assert!(in_progress(&events, at(2025, 3, 12, 9, 30)));
+        assert!(in_progress(&events, at(2025, 3, 17, 9, 30)));
+        assert!(!in_progress(&events, at(2025, 3, 19, 9, 30)));
+    }
+
+    #[test]
+    fn expands_the_daily_events_until_their_end() {
+        let events = calendar(
+            "DTSTART:20250310T090000\nDURATION:PT1H\nRRULE:FREQ=DAILY;INTERVAL=2;UNTIL=20250314",
+        );
+        assert!(in_progress(&events, at(2025, 3, 12, 9, 30)));
+        assert!(!in_progress(&events, at(2025, 3, 13, 9, 30)));
+        // The whole last day is included
+        assert!(in_progress(&events, at(2025, 3, 14, 9, 30)));
+        assert!(!in_progress(&events, at(2025, 3, 16, 9, 30)));
+    }
+
+    #[test]
+    fn skips_the_cancelled_occurrences() {
+        let events = calendar(
+            "DTSTART:20250310T090000\nDURATION:PT1H\nRRULE:FREQ=DAILY\nEXDATE:20250311T090000,20250312T090000",
+        );
+        assert!(in_progress(&events, at(2025, 3, 10, 9, 30)));
+        assert!(!in_progress(&events, at(2025, 3, 11, 9, 30)));
+        assert!(!in_progress(&events, at(2025, 3, 12, 9, 30)));
+        assert!(in_progress(&events, at(2025, 3, 13, 9, 30)));
+    }
+
+    #[test]
+    fn skips_the_months_without_the_day_of_the_event() {
+        let events = calendar(
+            "DTSTART:20250131T090000\nDURATION:PT1H\nRRULE:FREQ=MONTHLY;COUNT=3",
+        );
+        assert!(in_progress(&events, at(2025, 1, 31, 9, 30)));
+        assert!(!in_progress(&events, at(2025, 2, 28, 9, 30)));
+        assert!(in_progress(&events, at(2025, 3, 31, 9, 30)));
+        assert!(!in_progress(&events, at(2025, 4, 30, 9, 30)));
+        // The months skipped don't count
+        assert!(in_progress(&events, at(2025, 5, 31, 9, 30)));
+        assert!(!in_progress(&events, at(2025, 7, 31, 9, 30)));
+
+        let events = calendar("DTSTART;VALUE=DATE:20240229\nRRULE:FREQ=YEARLY");
+        assert!(!in_progress(&events, at(2025, 2, 28, 12, 0)));
+        assert!(!in_progress(&events, at(2025, 3, 1, 12, 0)));
+        assert!(in_progress(&events, at(2028, 2, 29, 12, 0)));
+    }
+
+    #[test]
+    fn tells_the_occurrences_apart() {
+        let events = calendar("DTSTART:20250310T090000\nDURATION:PT1H\nRRULE:FREQ=DAILY");
+        let monday = current_event(&events, &at(2025, 3, 10, 9, 30)).unwrap();
+        let later = current_event(&events, &at(2025, 3, 10, 9, 45)).unwrap();
+        let tuesday = current_event(&events, &at(2025, 3, 11, 9, 30)).unwrap();
+        assert!(monday.is_same(&later));
+        assert!(!monday.is_same(&tuesday));
+        assert_eq!(
+            tuesday.start,
+            NaiveDate::from_ymd_opt(2025, 3, 11).unwrap().and_hms_opt(9, 0, 0).unwrap()
+        );
+    }
+}
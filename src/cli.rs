@@ -0,0 +1,530 @@
+// SPDX-License-Identifier: MIT
+
+//! `cosmic-hue`, a command-line companion to the applet using the bridge it is paired with.
+
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+use cosmic::cosmic_config::{self, CosmicConfigEntry};
+use cosmic_ext_applet_hue::{config, scene};
+use huelib::Bridge;
+use huelib::resource::{Adjust, Group, group, light};
+use palette::{FromColor, Hsv, Srgb};
+use serde_json::{Value, json};
+
+use crate::config::Config;
+
+/// Warmest and coolest color temperatures supported by the lights, in mireds.
+const WARMEST_COLOR_TEMPERATURE: u32 = 500;
+const COOLEST_COLOR_TEMPERATURE: u32 = 153;
+
+/// Control the Philips Hue lights paired with the COSMIC Hue applet.
+#[derive(Parser)]
+#[command(name = "cosmic-hue", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List the lights
+    Lights {
+        #[command(subcommand)]
+        command: ListCommand,
+    },
+    /// Change a light, found by id or by name
+    Light {
+        name: String,
+        #[command(subcommand)]
+        action: StateAction,
+    },
+    /// List the groups
+    Groups {
+        #[command(subcommand)]
+        command: ListCommand,
+    },
+    /// Change a group, found by id or by name
+    Group {
+        name: String,
+        #[command(subcommand)]
+        action: StateAction,
+    },
+    /// List the scenes
+    Scenes {
+        #[command(subcommand)]
+        command: ListCommand,
+    },
+    /// Recall a scene
+    Scene {
+        #[command(subcommand)]
+        command: SceneCommand,
+    },
+    /// Turn a group off if one of its lights is on, on otherwise
+    Toggle {
+        /// The group, the default one of the applet when omitted
+        group: Option<String>,
+    },
+    /// Raise the brightness of a group by a step
+    Brighter { group: Option<String> },
+    /// Lower the brightness of a group by a step
+    Dimmer { group: Option<String> },
+    /// Make the color temperature of a group warmer by a step
+    Warmer { group: Option<String> },
+    /// Make the color temperature of a group cooler by a step
+    Cooler { group: Option<String> },
+    /// Recall the scene of a group following the last one recalled
+    NextScene { group: Option<String> },
+}
+
+#[derive(Subcommand)]
+enum ListCommand {
+    List,
+}
+
+#[derive(Subcommand)]
+enum SceneCommand {
+    /// Recall a scene, found by id or by name, with its options from the applet
+    Activate { name: String },
+}
+
+#[derive(Subcommand)]
+enum StateAction {
+    On,
+    Off,
+    /// Set the brightness, like `50%`
+    Bri {
+        #[arg(value_parser = parse_brightness)]
+        brightness: u8,
+    },
+    /// Set the color, like `#ff8800`
+    Color {
+        #[arg(value_parser = parse_color)]
+        color: (u16, u8),
+    },
+    /// Set the color temperature, like `2700K`
+    Ct {
+        #[arg(value_parser = parse_color_temperature)]
+        color_temperature: u16,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli.command) {
+        Ok(output) => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&output).unwrap_or_default()
+            );
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("{}", json!({ "error": error }));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(command: Command) -> Result<Value, String> {
+    let mut config = config::load();
+    let bridge = config::get_bridge(&config)
+        .ok_or_else(|| "No bridge paired, pair one from the applet first".to_string())?;
+    let shortcuts = config.get_shortcuts();
+    let brightness_step = (u32::from(shortcuts.brightness_step_percent) * 254 / 100).max(1) as u8;
+    let color_temperature_step = shortcuts.color_temperature_step;
+
+    match command {
+        Command::Lights {
+            command: ListCommand::List,
+        } => {
+            let lights = bridge.get_all_lights().map_err(|e| e.to_string())?;
+            Ok(lights
+                .iter()
+                .map(|light| {
+                    json!({
+                        "id": light.id,
+                        "name": light.name,
+                        "on": light.state.on,
+                        "brightness": light.state.brightness,
+                    })
+                })
+                .collect())
+        }
+        Command::Light { name, action } => {
+            let lights = bridge.get_all_lights().map_err(|e| e.to_string())?;
+            let light = find_by_name(&lights, &name, |light| (&light.id, &light.name))?;
+            let modifier = match action {
+                StateAction::On => light::StateModifier::new().with_on(true),
+                StateAction::Off => light::StateModifier::new().with_on(false),
+                StateAction::Bri { brightness } => light::StateModifier::new()
+                    .with_on(true)
+                    .with_brightness(Adjust::Override(brightness)),
+                StateAction::Color {
+                    color: (hue, saturation),
+                } => light::StateModifier::new()
+                    .with_on(true)
+                    .with_hue(Adjust::Override(hue))
+                    .with_saturation(Adjust::Override(saturation)),
+                StateAction::Ct { color_temperature } => light::StateModifier::new()
+                    .with_on(true)
+                    .with_color_temperature(Adjust::Override(color_temperature)),
+            };
+            bridge
+                .set_light_state(&light.id, &modifier)
+                .map_err(|e| e.to_string())?;
+            Ok(json!({ "light": { "id": light.id, "name": light.name } }))
+        }
+        Command::Groups {
+            command: ListCommand::List,
+        } => {
+            let groups = bridge.get_all_groups().map_err(|e| e.to_string())?;
+            Ok(groups
+                .iter()
+                .map(|group| {
+                    json!({
+                        "id": group.id,
+                        "name": group.name,
+                        "any_on": group.state.as_ref().map(|state| state.any_on),
+                        "all_on": group.state.as_ref().map(|state| state.all_on),
+                        "lights": group.lights,
+                    })
+                })
+                .collect())
+        }
+        Command::Group { name, action } => {
+            let groups = bridge.get_all_groups().map_err(|e| e.to_string())?;
+            let group = find_by_name(&groups, &name, |group| (&group.id, &group.name))?;
+            let modifier = match action {
+                StateAction::On => group::StateModifier::new().with_on(true),
+                StateAction::Off => group::StateModifier::new().with_on(false),
+                StateAction::Bri { brightness } => group::StateModifier::new()
+                    .with_on(true)
+                    .with_brightness(Adjust::Override(brightness)),
+                StateAction::Color {
+                    color: (hue, saturation),
+                } => group::StateModifier::new()
+                    .with_on(true)
+                    .with_hue(Adjust::Override(hue))
+                    .with_saturation(Adjust::Override(saturation)),
+                StateAction::Ct { color_temperature } => group::StateModifier::new()
+                    .with_on(true)
+                    .with_color_temperature(Adjust::Override(color_temperature)),
+            };
+            bridge
+                .set_group_state(&group.id, &modifier)
+                .map_err(|e| e.to_string())?;
+            Ok(json!({ "group": { "id": group.id, "name": group.name } }))
+        }
+        Command::Scenes {
+            command: ListCommand::List,
+        } => {
+            let scenes = bridge.get_all_scenes().map_err(|e| e.to_string())?;
+            Ok(scenes
+                .iter()
+                .map(|scene| json!({ "id": scene.id, "name": scene.name, "group": scene.group }))
+                .collect())
+        }
+        Command::Scene {
+            command: SceneCommand::Activate { name },
+        } => {
+            let scenes = bridge.get_all_scenes().map_err(|e| e.to_string())?;
+            let scene = find_by_name(&scenes, &name, |scene| (&scene.id, &scene.name))?;
+            activate_scene(&bridge, &mut config, &scene.id, scene.group.as_deref())?;
+            Ok(json!({ "scene": { "id": scene.id, "name": scene.name } }))
+        }
+        Command::Toggle { group } => {
+            let groups = bridge.get_all_groups().map_err(|e| e.to_string())?;
+            let group = find_group(&groups, &config, group.as_deref())?;
+            let on = !group.state.as_ref().is_some_and(|state| state.any_on);
+            bridge
+                .set_group_state(&group.id, &group::StateModifier::new().with_on(on))
+                .map_err(|e| e.to_string())?;
+            Ok(json!({ "group": { "id": group.id, "name": group.name }, "on": on }))
+        }
+        Command::Brighter { group } => step_group(
+            &bridge,
+            &config,
+            group.as_deref(),
+            // Brightening a group which is off turns it on
+            group::StateModifier::new()
+                .with_on(true)
+                .with_brightness(Adjust::Increment(brightness_step)),
+        ),
+        Command::Dimmer { group } => step_group(
+            &bridge,
+            &config,
+            group.as_deref(),
+            group::StateModifier::new().with_brightness(Adjust::Decrement(brightness_step)),
+        ),
+        Command::Warmer { group } => step_group(
+            &bridge,
+            &config,
+            group.as_deref(),
+            group::StateModifier::new()
+                .with_color_temperature(Adjust::Increment(color_temperature_step)),
+        ),
+        Command::Cooler { group } => step_group(
+            &bridge,
+            &config,
+            group.as_deref(),
+            group::StateModifier::new()
+                .with_color_temperature(Adjust::Decrement(color_temperature_step)),
+        ),
+        Command::NextScene { group } => {
+            let groups = bridge.get_all_groups().map_err(|e| e.to_string())?;
+            let group = find_group(&groups, &config, group.as_deref())?;
+            let mut scenes: Vec<_> = bridge
+                .get_all_scenes()
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .filter(|scene| scene.group.as_ref() == Some(&group.id))
+                .collect();
+            scenes.sort_by_key(|scene| scene.name.to_lowercase());
+
+            // The scene after the last one recalled on the group, the first one otherwise
+            let last = config
+                .get_recent_scenes()
+                .iter()
+                .find_map(|scene_id| scenes.iter().position(|scene| scene.id == *scene_id));
+            let Some(scene) = scenes.get(last.map_or(0, |index| (index + 1) % scenes.len())) else {
+                return Err(format!("No scene in {}", group.name));
+            };
+            activate_scene(&bridge, &mut config, &scene.id, Some(&group.id))?;
+            Ok(json!({
+                "group": { "id": group.id, "name": group.name },
+                "scene": { "id": scene.id, "name": scene.name },
+            }))
+        }
+    }
+}
+
+/// Change a group by a step of its brightness or color temperature.
+fn step_group(
+    bridge: &Bridge,
+    config: &Config,
+    name: Option<&str>,
+    modifier: group::StateModifier,
+) -> Result<Value, String> {
+    let groups = bridge.get_all_groups().map_err(|e| e.to_string())?;
+    let group = find_group(&groups, config, name)?;
+    bridge
+        .set_group_state(&group.id, &modifier)
+        .map_err(|e| e.to_string())?;
+    Ok(json!({ "group": { "id": group.id, "name": group.name } }))
+}
+
+/// Find a group by id or by name, or the default group of the applet.
+fn find_group<'a>(
+    groups: &'a [Group],
+    config: &Config,
+    name: Option<&str>,
+) -> Result<&'a Group, String> {
+    if let Some(name) = name {
+        return find_by_name(groups, name, |group| (&group.id, &group.name));
+    }
+    let group_id = config
+        .get_shortcuts()
+        .default_group
+        .as_ref()
+        .ok_or_else(|| "No default group, choose one in the applet or give a group".to_string())?;
+    groups
+        .iter()
+        .find(|group| group.id == *group_id)
+        .ok_or_else(|| format!("The default group {} no longer exists", group_id))
+}
+
+/// Recall a scene with the options chosen in the applet, and remember it like the applet does.
+fn activate_scene(
+    bridge: &Bridge,
+    config: &mut Config,
+    scene_id: &str,
+    group_id: Option<&str>,
+) -> Result<(), String> {
+    // Light scenes are not attached to a group, recall them on all the lights
+    let group_id = group_id.unwrap_or("0");
+    scene::recall(
+        bridge,
+        scene_id,
+        group_id,
+        config.get_scene_recall_options(scene_id),
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Listed first in the applet, and followed by the next scene
+    let mut recent_scenes = config.get_recent_scenes().to_vec();
+    recent_scenes.retain(|id| id != scene_id);
+    recent_scenes.insert(0, scene_id.to_string());
+    recent_scenes.truncate(config::MAX_RECENT_SCENES);
+    if let Ok(ctx) = cosmic_config::Config::new(config::APP_ID, Config::VERSION) {
+        let _ = config.set_recent_scenes(&ctx, recent_scenes);
+    }
+    Ok(())
+}
+
+/// Find an item by id, or by the name matching best, ignoring the case.
+///
+/// An exact name wins over a name starting with the searched text, which wins over a name
+/// containing it, which wins over a name containing its letters in order.
+fn find_by_name<'a, T>(
+    items: &'a [T],
+    name: &str,
+    id_and_name: impl Fn(&T) -> (&String, &String),
+) -> Result<&'a T, String> {
+    if let Some(item) = items.iter().find(|item| id_and_name(item).0 == name) {
+        return Ok(item);
+    }
+
+    let searched = name.to_lowercase();
+    let score = |item_name: &str| {
+        let item_name = item_name.to_lowercase();
+        if item_name == searched {
+            Some(0)
+        } else if item_name.starts_with(&searched) {
+            Some(1)
+        } else if item_name.contains(&searched) {
+            Some(2)
+        } else {
+            let mut letters = item_name.chars();
+            searched
+                .chars()
+                .all(|letter| letters.any(|item_letter| item_letter == letter))
+                .then_some(3)
+        }
+    };
+
+    let Some(best) = items
+        .iter()
+        .filter_map(|item| score(id_and_name(item).1))
+        .min()
+    else {
+        return Err(format!("Nothing matches \"{}\"", name));
+    };
+    let matches: Vec<&T> = items
+        .iter()
+        .filter(|item| score(id_and_name(item).1) == Some(best))
+        .collect();
+    match matches.as_slice() {
+        [item] => Ok(*item),
+        _ => Err(format!(
+            "\"{}\" matches several names: {}",
+            name,
+            matches
+                .iter()
+                .map(|item| id_and_name(item).1.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// Read a brightness percentage like `50%`, as a brightness of the bridge.
+fn parse_brightness(value: &str) -> Result<u8, String> {
+    let percent: u32 = value
+        .trim_end_matches('%')
+        .parse()
+        .map_err(|_| format!("Invalid brightness: {}", value))?;
+    if percent > 100 {
+        return Err(format!("Brightness above 100%: {}", value));
+    }
+    Ok((percent * 254 / 100).max(1) as u8)
+}
+
+/// Read a color like `#ff8800`, as the hue and saturation of the bridge.
+fn parse_color(value: &str) -> Result<(u16, u8), String> {
+    let color: Srgb<u8> = value
+        .parse()
+        .map_err(|_| format!("Invalid color: {}", value))?;
+    let color = Hsv::from_color(color.into_format::<f32>());
+    Ok((
+        (color.hue.into_positive_degrees() / 360.0 * 65535.0) as u16,
+        (color.saturation * 254.0) as u8,
+    ))
+}
+
+/// Read a color temperature like `2700K`, in mireds within the range of the lights.
+fn parse_color_temperature(value: &str) -> Result<u16, String> {
+    let kelvin: u32 = value
+        .trim_end_matches(['K', 'k'])
+        .parse()
+        .map_err(|_| format!("Invalid color temperature: {}", value))?;
+    Ok(
+        (1_000_000 / kelvin.max(1)).clamp(COOLEST_COLOR_TEMPERATURE, WARMEST_COLOR_TEMPERATURE)
+            as u16,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items() -> Vec<(String, String)> {
+        [
+            ("1", "Desk"),
+            ("2", "Desk lamp"),
+            ("3", "Kitchen"),
+            ("4", "Kitchen island"),
+        ]
+        .into_iter()
+        .map(|(id, name)| (id.to_string(), name.to_string()))
+        .collect()
+    }
+
+    fn find(name: &str) -> Result<String, String> {
+        let items = items();
+        find_by_name(&items, name, |(id, name)| (id, name)).map(|(_, name)| name.clone())
+    }
+
+    #[test]
+    fn finds_by_id_or_by_name() {
+        assert_eq!(find("2").as_deref(), Ok("Desk lamp"));
+        // An exact name wins over the names starting with it
+        assert_eq!(find("desk").as_deref(), Ok("Desk"));
+        assert_eq!(find("Kitchen").as_deref(), Ok("Kitchen"));
+        assert_eq!(find("island").as_deref(), Ok("Kitchen island"));
+        assert_eq!(find("dl").as_deref(), Ok("Desk lamp"));
+    }
+
+    #[test]
+    fn refuses_ambiguous_or_unknown_names() {
+        assert_eq!(
+            find("kit"),
+            Err("\"kit\" matches several names: Kitchen, Kitchen island".to_string())
+        );
+        assert_eq!(
+            find("garage"),
+            Err("Nothing matches \"garage\"".to_string())
+        );
+    }
+
+    #[test]
+    fn parses_the_brightness() {
+        assert_eq!(parse_brightness("50%"), Ok(127));
+        assert_eq!(parse_brightness("100"), Ok(254));
+        // The lowest brightness keeps the light on
+        assert_eq!(parse_brightness("0%"), Ok(1));
+        assert!(parse_brightness("101%").is_err());
+        assert!(parse_brightness("-5%").is_err());
+        assert!(parse_brightness("half").is_err());
+    }
+
+    #[test]
+    fn parses_the_color() {
+        assert_eq!(parse_color("#ff0000"), Ok((0, 254)));
+        assert_eq!(parse_color("#00ff00"), Ok((21845, 254)));
+        assert_eq!(parse_color("0000ff"), Ok((43690, 254)));
+        assert_eq!(parse_color("#ffffff"), Ok((0, 0)));
+        assert!(parse_color("orange").is_err());
+    }
+
+    #[test]
+    fn parses_the_color_temperature() {
+        assert_eq!(parse_color_temperature("2700K"), Ok(370));
+        assert_eq!(parse_color_temperature("4000k"), Ok(250));
+        // Beyond the range of the lights
+        assert_eq!(parse_color_temperature("20000K"), Ok(153));
+        assert_eq!(parse_color_temperature("1000K"), Ok(500));
+        assert_eq!(parse_color_temperature("0"), Ok(500));
+        assert!(parse_color_temperature("warm").is_err());
+    }
+}
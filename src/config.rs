@@ -1,14 +1,294 @@
 // SPDX-License-Identifier: MIT
 
+use std::collections::HashMap;
 use std::net::IpAddr;
+use std::time::Duration;
 
-use cosmic::cosmic_config::{self, cosmic_config_derive::CosmicConfigEntry, CosmicConfigEntry};
+use cosmic::cosmic_config::{
+    self, ConfigGet, ConfigSet, CosmicConfigEntry, cosmic_config_derive::CosmicConfigEntry,
+};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Default, Clone, CosmicConfigEntry, Eq, PartialEq)]
-#[version = 3]
+/// The cosmic-config entry of the applet.
+pub const APP_ID: &str = "com.clockworksfr.cosmichue";
+
+/// Maximum number of recently activated scenes remembered.
+pub const MAX_RECENT_SCENES: usize = 10;
+
+/// Maximum number of pinned lights, groups and scenes.
+pub const MAX_PINS: usize = 6;
+
+/// Key of the version the settings were brought from, saved on the first start of a version.
+const MIGRATED_FROM_KEY: &str = "migrated_from";
+
+#[derive(Debug, Default, Clone, CosmicConfigEntry, PartialEq)]
+#[version = 4]
 pub struct Config {
     bridge_ip: Option<IpAddr>,
     username: Option<String>,
+    /// Id of the paired bridge, to notice another bridge answering at its address.
+    bridge_id: Option<String>,
+    /// Recently activated scene ids, most recent first.
+    recent_scenes: Vec<String>,
+    /// Options applied when recalling a scene, by scene id.
+    scene_recall_options: HashMap<String, SceneRecallOptions>,
+    /// Latitude used to compute the sunrise and sunset, in degrees.
+    latitude: Option<f64>,
+    /// Longitude used to compute the sunrise and sunset, in degrees.
+    longitude: Option<f64>,
+    /// Scenes and group states applied around sunrise or sunset.
+    automation_rules: Vec<AutomationRule>,
+    /// Groups following the adaptive lighting, by group id.
+    adaptive_groups: HashMap<String, AdaptiveOptions>,
+    /// What happens to groups when the session is locked or suspended, by group id.
+    lock_actions: HashMap<String, LockAction>,
+    /// Group dimmed while the user is away.
+    idle_dim: Option<IdleDimOptions>,
+    /// Lights alerted when a desktop notification is shown.
+    notification_rules: Vec<NotificationRule>,
+    /// Light turned red while busy.
+    busy_light: Option<BusyLightOptions>,
+    /// Settings of the focus timer.
+    focus_timer: FocusTimerOptions,
+    /// Number of completed focus sessions, by day (`YYYY-MM-DD`).
+    focus_sessions: HashMap<String, u32>,
+    /// Settings of the keyboard shortcut actions.
+    shortcuts: ShortcutOptions,
+    /// Tint the panel icon with the color of the default group.
+    tint_panel_icon: bool,
+    /// Lights, groups and scenes pinned at the top of the popup, in order.
+    pins: Vec<ItemRef>,
+    /// Also show the pins as buttons in horizontal panels.
+    show_pins_in_panel: bool,
+    /// Refreshes and sections of the popup.
+    general: GeneralOptions,
+    /// Lights, groups and scenes left out of the popup.
+    hidden_items: Vec<ItemRef>,
+    /// Manual order of the lights, groups and scenes, the others coming after them by name.
+    item_order: Vec<ItemRef>,
+    /// Names shown instead of the ones given on the bridge.
+    aliases: HashMap<ItemRef, String>,
+}
+
+/// A light, group or scene of the bridge.
+#[derive(Debug, Clone, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum ItemRef {
+    Light(String),
+    Group(String),
+    Scene(String),
+}
+
+/// Settings of the refreshes and of the popup.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GeneralOptions {
+    /// Interval between two refreshes of the lights, in seconds.
+    pub refresh_interval_secs: u16,
+    /// Delay before sending the changes made with the sliders, in milliseconds.
+    pub debounce_millis: u16,
+    /// Sections left out of the popup.
+    pub hidden_sections: Vec<Section>,
+}
+
+impl Default for GeneralOptions {
+    fn default() -> Self {
+        GeneralOptions {
+            refresh_interval_secs: 30,
+            debounce_millis: 300,
+            hidden_sections: Vec::new(),
+        }
+    }
+}
+
+impl GeneralOptions {
+    pub fn refresh_interval(&self) -> Duration {
+        Duration::from_secs(u64::from(self.refresh_interval_secs.max(1)))
+    }
+
+    pub fn debounce_delay(&self) -> Duration {
+        Duration::from_millis(u64::from(self.debounce_millis))
+    }
+}
+
+/// A section of the popup.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Section {
+    Lights,
+    Groups,
+    Scenes,
+    Schedules,
+    WakeUps,
+    Automations,
+    Focus,
+}
+
+impl Section {
+    pub const ALL: [Section; 7] = [
+        Section::Lights,
+        Section::Groups,
+        Section::Scenes,
+        Section::Schedules,
+        Section::WakeUps,
+        Section::Automations,
+        Section::Focus,
+    ];
+}
+
+/// Settings of the actions run by the keyboard shortcuts.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ShortcutOptions {
+    /// Group changed by the actions when none is given.
+    pub default_group: Option<String>,
+    /// Brightness change of a step, in percent.
+    pub brightness_step_percent: u8,
+    /// Color temperature change of a step, in mireds.
+    pub color_temperature_step: u16,
+}
+
+impl Default for ShortcutOptions {
+    fn default() -> Self {
+        ShortcutOptions {
+            default_group: None,
+            brightness_step_percent: 10,
+            color_temperature_step: 30,
+        }
+    }
+}
+
+/// Settings of the focus timer.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct FocusTimerOptions {
+    /// Light showing the phases of the timer.
+    pub light_id: Option<String>,
+    pub signal: FocusSignal,
+    pub work_minutes: u16,
+    pub break_minutes: u16,
+}
+
+/// How the light shows the phases of the focus timer.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum FocusSignal {
+    /// A color for the work and another one for the breaks.
+    Color,
+    /// The light pulses at each phase change, keeping its color.
+    Pulse,
+}
+
+impl Default for FocusTimerOptions {
+    fn default() -> Self {
+        FocusTimerOptions {
+            light_id: None,
+            signal: FocusSignal::Color,
+            work_minutes: 25,
+            break_minutes: 5,
+        }
+    }
+}
+
+/// A light turned red while the user is busy.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BusyLightOptions {
+    pub light_id: String,
+    /// Path of the iCalendar file telling when the user is busy, busy only by hand without it.
+    pub calendar_path: Option<String>,
+}
+
+/// A light alerted when some desktop notifications are shown.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct NotificationRule {
+    pub id: String,
+    /// Name of the application sending the notifications, any application when empty.
+    pub app_name: String,
+    /// Lowest urgency of the notifications.
+    pub min_urgency: Urgency,
+    pub light_id: String,
+    pub alert: NotificationAlert,
+}
+
+/// Urgency level of a desktop notification.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+pub enum Urgency {
+    Low,
+    Normal,
+    Critical,
+}
+
+/// How a light is alerted.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum NotificationAlert {
+    /// A single breathe cycle of the light.
+    Breathe,
+    /// A few flashes of a color, before going back to the previous state.
+    Flash { hue: u16, saturation: u8 },
+}
+
+impl NotificationRule {
+    /// Whether a notification triggers the rule.
+    pub fn matches(&self, app_name: &str, urgency: Urgency) -> bool {
+        (self.app_name.is_empty() || self.app_name.eq_ignore_ascii_case(app_name))
+            && urgency >= self.min_urgency
+    }
+}
+
+/// Dimming of a group while the user is away.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct IdleDimOptions {
+    pub group_id: String,
+    /// Inactivity before dimming the group, in minutes.
+    pub minutes: u16,
+    /// Brightness of the dimmed lights.
+    pub brightness_percent: u8,
+}
+
+/// What happens to a group when the session is locked or suspended.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum LockAction {
+    TurnOff,
+    /// Recall a scene of the group.
+    Scene(String),
+}
+
+/// Options of the adaptive lighting of a group.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AdaptiveOptions {
+    /// Whether the brightness follows the time of day too.
+    pub adjust_brightness: bool,
+}
+
+/// Options applied when recalling a scene.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SceneRecallOptions {
+    /// Duration of the fade to the scene, in seconds.
+    pub transition_secs: Option<u16>,
+    /// Percentage applied to the brightness stored in the scene.
+    pub brightness_percent: Option<u8>,
+}
+
+/// An action run by the applet around sunrise or sunset.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AutomationRule {
+    pub id: String,
+    pub name: String,
+    pub enabled: bool,
+    pub event: SunEvent,
+    /// Delay from the event, in minutes, negative before it.
+    pub offset_minutes: i16,
+    pub action: AutomationAction,
+}
+
+/// A daily event of the sun.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum SunEvent {
+    Sunrise,
+    Sunset,
+}
+
+/// What an automation rule does when it is triggered.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum AutomationAction {
+    /// Recall a scene in a group.
+    Scene { group_id: String, scene_id: String },
+    /// Turn a group on or off.
+    Group { group_id: String, on: bool },
 }
 
 impl Config {
@@ -19,4 +299,362 @@ impl Config {
     pub fn get_username(&self) -> Option<&str> {
         self.username.as_deref()
     }
+
+    pub fn get_bridge_id(&self) -> Option<&str> {
+        self.bridge_id.as_deref()
+    }
+
+    pub fn get_recent_scenes(&self) -> &[String] {
+        &self.recent_scenes
+    }
+
+    pub fn get_scene_recall_options(&self, scene_id: &str) -> SceneRecallOptions {
+        self.scene_recall_options
+            .get(scene_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn get_all_scene_recall_options(&self) -> &HashMap<String, SceneRecallOptions> {
+        &self.scene_recall_options
+    }
+
+    pub fn get_location(&self) -> Option<(f64, f64)> {
+        self.latitude.zip(self.longitude)
+    }
+
+    pub fn get_automation_rules(&self) -> &[AutomationRule] {
+        &self.automation_rules
+    }
+
+    pub fn get_adaptive_options(&self, group_id: &str) -> Option<AdaptiveOptions> {
+        self.adaptive_groups.get(group_id).copied()
+    }
+
+    pub fn get_all_adaptive_options(&self) -> &HashMap<String, AdaptiveOptions> {
+        &self.adaptive_groups
+    }
+
+    pub fn get_lock_action(&self, group_id: &str) -> Option<&LockAction> {
+        self.lock_actions.get(group_id)
+    }
+
+    pub fn get_all_lock_actions(&self) -> &HashMap<String, LockAction> {
+        &self.lock_actions
+    }
+
+    pub fn get_idle_dim(&self) -> Option<&IdleDimOptions> {
+        self.idle_dim.as_ref()
+    }
+
+    pub fn get_notification_rules(&self) -> &[NotificationRule] {
+        &self.notification_rules
+    }
+
+    pub fn get_busy_light(&self) -> Option<&BusyLightOptions> {
+        self.busy_light.as_ref()
+    }
+
+    pub fn get_focus_timer(&self) -> &FocusTimerOptions {
+        &self.focus_timer
+    }
+
+    pub fn get_focus_sessions(&self) -> &HashMap<String, u32> {
+        &self.focus_sessions
+    }
+
+    pub fn get_shortcuts(&self) -> &ShortcutOptions {
+        &self.shortcuts
+    }
+
+    pub fn get_tint_panel_icon(&self) -> bool {
+        self.tint_panel_icon
+    }
+
+    pub fn get_pins(&self) -> &[ItemRef] {
+        &self.pins
+    }
+
+    pub fn get_show_pins_in_panel(&self) -> bool {
+        self.show_pins_in_panel
+    }
+
+    pub fn get_general(&self) -> &GeneralOptions {
+        &self.general
+    }
+
+    pub fn is_section_visible(&self, section: Section) -> bool {
+        !self.general.hidden_sections.contains(&section)
+    }
+
+    pub fn get_hidden_items(&self) -> &[ItemRef] {
+        &self.hidden_items
+    }
+
+    pub fn is_hidden(&self, item: &ItemRef) -> bool {
+        self.hidden_items.contains(item)
+    }
+
+    pub fn get_item_order(&self) -> &[ItemRef] {
+        &self.item_order
+    }
+
+    pub fn get_aliases(&self) -> &HashMap<ItemRef, String> {
+        &self.aliases
+    }
+
+    pub fn get_alias(&self, item: &ItemRef) -> Option<&str> {
+        self.aliases.get(item).map(String::as_str)
+    }
+}
+
+/// Read the settings, bringing forward those saved by a previous version of the applet.
+pub fn load() -> Config {
+    let Ok(context) = cosmic_config::Config::new(APP_ID, Config::VERSION) else {
+        return Config::default();
+    };
+    load_from(&context, |version| {
+        cosmic_config::Config::new(APP_ID, version)
+    })
+}
+
+/// Read the settings of a context, after bringing forward those of the previous versions on
+/// the first start of this version.
+fn load_from(
+    context: &cosmic_config::Config,
+    previous: impl Fn(u64) -> Result<cosmic_config::Config, cosmic_config::Error>,
+) -> Config {
+    // The settings put back to their defaults afterwards are kept as they are
+    let first_start =
+        context.get::<Option<u64>>(MIGRATED_FROM_KEY).is_err() && !has_settings(context);
+    if first_start {
+        let migrated_from = migrate(context, previous);
+        if let Err(error) = context.set(MIGRATED_FROM_KEY, migrated_from) {
+            println!("Error saving the migration of the settings: {:?}", error);
+        }
+    }
+    match Config::get_entry(context) {
+        Ok(config) => config,
+        Err((_errors, config)) => config,
+    }
+}
+
+/// Whether settings were saved in a context, the bridge being set on the first use of the
+/// applet.
+fn has_settings(context: &cosmic_config::Config) -> bool {
+    context.get::<Option<IpAddr>>("bridge_ip").is_ok()
+}
+
+/// Copy the settings of the most recent previous version into the current one.
+///
+/// Returns the version they were copied from.
+fn migrate(
+    context: &cosmic_config::Config,
+    previous: impl Fn(u64) -> Result<cosmic_config::Config, cosmic_config::Error>,
+) -> Option<u64> {
+    for version in (1..Config::VERSION).rev() {
+        let Ok(previous) = previous(version) else {
+            continue;
+        };
+        if !has_settings(&previous) {
+            continue;
+        }
+        // The missing fields keep their default values
+        let config = match Config::get_entry(&previous) {
+            Ok(config) => config,
+            Err((_errors, config)) => config,
+        };
+        println!("Migrating the settings from version {}", version);
+        if let Err(error) = config.write_entry(context) {
+            println!("Error migrating the settings: {:?}", error);
+        }
+        return Some(version);
+    }
+    None
+}
+
+/// Read the id of the bridge answering at an address, which doesn't need to be paired.
+pub fn read_bridge_id(bridge_ip: IpAddr) -> Result<String, String> {
+    let response = ureq::get(&format!("http://{}/api/config", bridge_ip))
+        .timeout(Duration::from_secs(5))
+        .call()
+        .map_err(|error| error.to_string())?
+        .into_string()
+        .map_err(|error| error.to_string())?;
+    let config: serde_json::Value =
+        serde_json::from_str(&response).map_err(|error| error.to_string())?;
+    config["bridgeid"]
+        .as_str()
+        .map(str::to_lowercase)
+        .ok_or_else(|| "Not a Hue bridge".to_string())
+}
+
+/// Connect to the paired bridge.
+pub fn get_bridge(config: &Config) -> Option<huelib::Bridge> {
+    let bridge_ip: IpAddr = match config.get_bridge_ip() {
+        Some(bridge_ip) => *bridge_ip,
+        None => return None,
+    };
+    let username = match config.get_username() {
+        Some(username) => username.to_owned(),
+        None => return None,
+    };
+    Some(huelib::Bridge::new(bridge_ip, username))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+    use std::path::PathBuf;
+
+    /// Settings saved in a temporary directory, removed at the end of the test.
+    struct TestSettings {
+        path: PathBuf,
+    }
+
+    impl TestSettings {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "cosmic-hue-config-{}-{}",
+                name,
+                std::process::id()
+            ));
+            let _ = std::fs::remove_dir_all(&path);
+            TestSettings { path }
+        }
+
+        fn version(&self, version: u64) -> cosmic_config::Config {
+            cosmic_config::Config::with_custom_path(APP_ID, version, self.path.clone()).unwrap()
+        }
+
+        fn load(&self) -> Config {
+            load_from(&self.version(Config::VERSION), |version| {
+                cosmic_config::Config::with_custom_path(APP_ID, version, self.path.clone())
+            })
+        }
+    }
+
+    impl Drop for TestSettings {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.path);
+        }
+    }
+
+    fn bridge_ip() -> Option<IpAddr> {
+        Some(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 2)))
+    }
+
+    fn pins() -> Vec<ItemRef> {
+        vec![
+            ItemRef::Group("1".to_string()),
+            ItemRef::Scene("abc".to_string()),
+        ]
+    }
+
+    fn shortcuts() -> ShortcutOptions {
+        ShortcutOptions {
+            default_group: Some("1".to_string()),
+            brightness_step_percent: 20,
+            color_temperature_step: 50,
+        }
+    }
+
+    fn automation_rules() -> Vec<AutomationRule> {
+        vec![AutomationRule {
+            id: "rule".to_string(),
+            name: "Evening".to_string(),
+            enabled: true,
+            event: SunEvent::Sunset,
+            offset_minutes: -30,
+            action: AutomationAction::Scene {
+                group_id: "1".to_string(),
+                scene_id: "abc".to_string(),
+            },
+        }]
+    }
+
+    /// Save the settings of the applet at version 3.
+    fn save_version_3(settings: &TestSettings) {
+        let previous = settings.version(3);
+        previous.set("bridge_ip", bridge_ip()).unwrap();
+        previous.set("username", Some("user")).unwrap();
+        previous.set("pins", pins()).unwrap();
+        previous.set("shortcuts", shortcuts()).unwrap();
+        previous
+            .set("automation_rules", automation_rules())
+            .unwrap();
+    }
+
+    #[test]
+    fn migrates_the_settings_of_version_3() {
+        let settings = TestSettings::new("migrate");
+        save_version_3(&settings);
+
+        let config = settings.load();
+        assert_eq!(config.get_bridge_ip(), bridge_ip().as_ref());
+        assert_eq!(config.get_username(), Some("user"));
+        assert_eq!(config.get_pins(), pins());
+        assert_eq!(config.get_shortcuts(), &shortcuts());
+        assert_eq!(config.get_automation_rules(), automation_rules());
+        // The fields added since then keep their defaults
+        assert!(config.get_aliases().is_empty());
+        assert_eq!(
+            settings
+                .version(Config::VERSION)
+                .get::<Option<u64>>(MIGRATED_FROM_KEY)
+                .ok(),
+            Some(Some(3))
+        );
+    }
+
+    #[test]
+    fn keeps_the_settings_put_back_to_their_defaults() {
+        let settings = TestSettings::new("defaults");
+        save_version_3(&settings);
+        settings.load();
+
+        let current = settings.version(Config::VERSION);
+        for key in ["bridge_ip", "username"] {
+            current.set::<Option<String>>(key, None).unwrap();
+        }
+        current.set("pins", Vec::<ItemRef>::new()).unwrap();
+        current
+            .set("shortcuts", ShortcutOptions::default())
+            .unwrap();
+        current
+            .set("automation_rules", Vec::<AutomationRule>::new())
+            .unwrap();
+
+        assert_eq!(settings.load(), Config::default());
+    }
+
+    #[test]
+    fn keeps_the_settings_saved_before_the_marker() {
+        let settings = TestSettings::new("existing");
+        save_version_3(&settings);
+        settings
+            .version(Config::VERSION)
+            .set::<Option<IpAddr>>("bridge_ip", None)
+            .unwrap();
+
+        let config = settings.load();
+        assert_eq!(config.get_bridge_ip(), None);
+        assert!(config.get_pins().is_empty());
+        assert!(config.get_automation_rules().is_empty());
+    }
+
+    #[test]
+    fn starts_from_the_defaults_without_previous_settings() {
+        let settings = TestSettings::new("empty");
+
+        assert_eq!(settings.load(), Config::default());
+        assert_eq!(
+            settings
+                .version(Config::VERSION)
+                .get::<Option<u64>>(MIGRATED_FROM_KEY)
+                .ok(),
+            Some(None)
+        );
+    }
 }
@@ -0,0 +1,85 @@
+// SPDX-License-Identifier: MIT
+
+//! Showing the phases of the focus timer with a light.
+
+use std::time::Duration;
+
+use huelib::Bridge;
+use huelib::resource::light::{self, Alert};
+use huelib::resource::Adjust;
+
+use crate::config::FocusSignal;
+use crate::snapshot::Snapshot;
+
+/// Hue and saturation of the light while working, and during the breaks.
+const WORK_COLOR: (u16, u8) = (5000, 254);
+const BREAK_COLOR: (u16, u8) = (25500, 254);
+
+/// Duration of the color change, in multiples of 100ms.
+const PHASE_TRANSITION_TIME: u16 = 10;
+
+/// Delay between the two pulses announcing a break.
+const PULSE_DELAY: Duration = Duration::from_secs(2);
+
+/// A phase of the focus timer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusPhase {
+    Work,
+    Break,
+}
+
+/// Start showing the phases with a light, showing the current one.
+///
+/// Returns the state of the light before the change when its color follows the phases, to
+/// restore it when the timer stops.
+pub async fn start(
+    bridge: &Bridge,
+    light_id: &str,
+    phase: FocusPhase,
+    signal: FocusSignal,
+) -> Result<Option<Snapshot>, huelib::Error> {
+    let snapshot = match signal {
+        FocusSignal::Color => Some(Snapshot::capture(bridge, &[light_id.to_string()])?),
+        FocusSignal::Pulse => None,
+    };
+    show_phase(bridge, light_id, phase, signal).await?;
+    Ok(snapshot)
+}
+
+/// Show a new phase: a color per phase, or a pulse for the work and two for the breaks.
+pub async fn show_phase(
+    bridge: &Bridge,
+    light_id: &str,
+    phase: FocusPhase,
+    signal: FocusSignal,
+) -> Result<(), huelib::Error> {
+    match signal {
+        FocusSignal::Color => {
+            let (hue, saturation) = match phase {
+                FocusPhase::Work => WORK_COLOR,
+                FocusPhase::Break => BREAK_COLOR,
+            };
+            let modifier = light::StateModifier::new()
+                .with_on(true)
+                .with_hue(Adjust::Override(hue))
+                .with_saturation(Adjust::Override(saturation))
+                .with_transition_time(PHASE_TRANSITION_TIME);
+            bridge.set_light_state(light_id, &modifier)?;
+        }
+        FocusSignal::Pulse => {
+            let modifier = light::StateModifier::new().with_alert(Alert::Select);
+            bridge.set_light_state(light_id, &modifier)?;
+            if phase == FocusPhase::Break {
+                tokio::time::sleep(PULSE_DELAY).await;
+                bridge.set_light_state(light_id, &modifier)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Put the light back as it was before the timer, unless it was turned off since.
+pub fn restore(bridge: &Bridge, snapshot: &Snapshot) -> Result<(), huelib::Error> {
+    snapshot.restore(bridge, true, Some(PHASE_TRANSITION_TIME))?;
+    Ok(())
+}
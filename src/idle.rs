@@ -0,0 +1,39 @@
+// SPDX-License-Identifier: MIT
+
+//! Dimming a group while the user is away from the computer.
+
+use huelib::Bridge;
+use huelib::resource::light;
+use huelib::resource::Adjust;
+
+use crate::snapshot::Snapshot;
+
+/// Duration of the dimming, in multiples of 100ms.
+const DIM_TRANSITION_TIME: u16 = 30;
+
+/// Duration of the return to the previous brightness, in multiples of 100ms.
+const RESTORE_TRANSITION_TIME: u16 = 5;
+
+/// Dim the lights which are on in a group, the dimmer ones are left as they are.
+///
+/// Returns the state of the lights before the change, to restore them on activity.
+pub fn dim(bridge: &Bridge, group_id: &str, brightness_percent: u8) -> Result<Snapshot, huelib::Error> {
+    let snapshot = Snapshot::capture_groups(bridge, &[group_id.to_string()])?;
+    let brightness = (u16::from(brightness_percent.min(100)) * 254 / 100).max(1) as u8;
+
+    let modifier = light::StateModifier::new()
+        .with_brightness(Adjust::Override(brightness))
+        .with_transition_time(DIM_TRANSITION_TIME);
+    for light in &snapshot.lights {
+        if light.on && light.brightness.is_some_and(|current| current > brightness) {
+            bridge.set_light_state(&light.id, &modifier)?;
+        }
+    }
+    Ok(snapshot)
+}
+
+/// Put the lights back as they were before dimming them, leaving the ones turned off since.
+pub fn restore(bridge: &Bridge, snapshot: &Snapshot) -> Result<(), huelib::Error> {
+    snapshot.restore(bridge, true, Some(RESTORE_TRANSITION_TIME))?;
+    Ok(())
+}
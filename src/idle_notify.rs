@@ -0,0 +1,173 @@
+// SPDX-License-Identifier: MIT
+
+//! Idle state of the user from the compositor, with the idle hint of logind as a fallback.
+
+use std::os::fd::{AsFd, AsRawFd};
+use std::time::Duration;
+
+use cosmic::cctk::sctk::reexports::client::backend::WaylandError;
+use cosmic::cctk::sctk::reexports::client::globals::{GlobalListContents, registry_queue_init};
+use cosmic::cctk::sctk::reexports::client::protocol::{wl_registry, wl_seat};
+use cosmic::cctk::sctk::reexports::client::{
+    Connection, Dispatch, DispatchError, EventQueue, QueueHandle,
+};
+use cosmic::cctk::wayland_protocols::ext::idle_notify::v1::client::{
+    ext_idle_notification_v1, ext_idle_notifier_v1,
+};
+use cosmic::iced::futures::channel::mpsc::Sender;
+use futures_util::{SinkExt, Stream};
+use tokio::io::unix::AsyncFd;
+
+use crate::logind;
+
+/// A change of the idle state of the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleEvent {
+    /// Whether the user has been away for longer than the timeout.
+    Idle(bool),
+    /// Neither the compositor nor logind can tell when the user is away.
+    Unavailable,
+}
+
+/// Report when the user has been away for longer than `timeout`, and when they come back.
+///
+/// The compositor is asked through `ext-idle-notify-v1`, as cosmic-idle does. Without it the
+/// idle hint of logind is used, which is only set by some desktops.
+pub fn idle_events(timeout: Duration) -> impl Stream<Item = IdleEvent> {
+    cosmic::iced::stream::channel(1, move |mut output: Sender<IdleEvent>| async move {
+        match Notifications::new(timeout) {
+            Ok(notifications) => {
+                if let Err(error) = notifications.run(&mut output).await {
+                    println!(
+                        "Error reading the idle state from the compositor: {}",
+                        error
+                    );
+                }
+            }
+            Err(error) => {
+                println!(
+                    "The compositor doesn't report the idle state, using logind: {}",
+                    error
+                );
+                if let Err(error) = logind::watch_idle(timeout, &mut output).await {
+                    println!("Error reading the idle state from logind: {}", error);
+                    let _ = output.send(IdleEvent::Unavailable).await;
+                }
+            }
+        }
+        // Keep the subscription alive to avoid restarting it in a loop
+        std::future::pending::<()>().await;
+    })
+}
+
+/// Idle notifications of the compositor.
+struct Notifications {
+    connection: Connection,
+    queue: EventQueue<State>,
+    state: State,
+    // Keep the notification alive, the compositor stops reporting once it is destroyed
+    _notification: ext_idle_notification_v1::ExtIdleNotificationV1,
+}
+
+/// Idle states received since the last dispatch.
+#[derive(Default)]
+struct State {
+    changes: Vec<bool>,
+}
+
+impl Notifications {
+    fn new(timeout: Duration) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let connection = Connection::connect_to_env()?;
+        let (globals, queue) = registry_queue_init::<State>(&connection)?;
+        let handle = queue.handle();
+        let notifier: ext_idle_notifier_v1::ExtIdleNotifierV1 = globals.bind(&handle, 1..=1, ())?;
+        let seat: wl_seat::WlSeat = globals.bind(&handle, 1..=1, ())?;
+        let timeout = u32::try_from(timeout.as_millis()).unwrap_or(u32::MAX);
+        let notification = notifier.get_idle_notification(timeout, &seat, &handle, ());
+        Ok(Self {
+            connection,
+            queue,
+            state: State::default(),
+            _notification: notification,
+        })
+    }
+
+    async fn run(mut self, output: &mut Sender<IdleEvent>) -> Result<(), WaylandError> {
+        let fd = AsyncFd::new(self.connection.as_fd().as_raw_fd())?;
+        loop {
+            self.connection.flush()?;
+            if let Some(guard) = self.queue.prepare_read() {
+                let mut ready = fd.readable().await?;
+                match guard.read() {
+                    Ok(_) => {}
+                    Err(WaylandError::Io(error))
+                        if error.kind() == std::io::ErrorKind::WouldBlock => {}
+                    Err(error) => return Err(error),
+                }
+                ready.clear_ready();
+            }
+            self.queue
+                .dispatch_pending(&mut self.state)
+                .map_err(|error| match error {
+                    DispatchError::Backend(error) => error,
+                    error => WaylandError::Io(std::io::Error::other(error)),
+                })?;
+            for idle in self.state.changes.drain(..) {
+                let _ = output.send(IdleEvent::Idle(idle)).await;
+            }
+        }
+    }
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for State {
+    fn event(
+        _: &mut Self,
+        _: &wl_registry::WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<wl_seat::WlSeat, ()> for State {
+    fn event(
+        _: &mut Self,
+        _: &wl_seat::WlSeat,
+        _: wl_seat::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ext_idle_notifier_v1::ExtIdleNotifierV1, ()> for State {
+    fn event(
+        _: &mut Self,
+        _: &ext_idle_notifier_v1::ExtIdleNotifierV1,
+        _: ext_idle_notifier_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ext_idle_notification_v1::ExtIdleNotificationV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _: &ext_idle_notification_v1::ExtIdleNotificationV1,
+        event: ext_idle_notification_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            ext_idle_notification_v1::Event::Idled => state.changes.push(true),
+            ext_idle_notification_v1::Event::Resumed => state.changes.push(false),
+            _ => {}
+        }
+    }
+}
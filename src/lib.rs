@@ -0,0 +1,6 @@
+// SPDX-License-Identifier: MIT
+
+//! The configuration and the scene recall shared by the applet and the `cosmic-hue` command.
+
+pub mod config;
+pub mod scene;
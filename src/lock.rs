@@ -0,0 +1,55 @@
+// SPDX-License-Identifier: MIT
+
+//! Turning groups off or to a scene while the session is locked or suspended.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use huelib::Bridge;
+use huelib::resource::group;
+
+use crate::config::LockAction;
+use crate::snapshot::Snapshot;
+
+/// Number of attempts to restore the lights, the network can take a while to come back after
+/// a suspend.
+const RESTORE_ATTEMPTS: u32 = 5;
+
+/// Delay between two attempts to restore the lights.
+const RESTORE_RETRY_DELAY: Duration = Duration::from_secs(3);
+
+/// Run the lock action of each group.
+///
+/// Returns the state of the lights before the change, to restore them on unlock.
+pub fn apply(bridge: &Bridge, actions: &HashMap<String, LockAction>) -> Result<Snapshot, huelib::Error> {
+    if actions.is_empty() {
+        return Ok(Snapshot::default());
+    }
+    let group_ids: Vec<String> = actions.keys().cloned().collect();
+    let snapshot = Snapshot::capture_groups(bridge, &group_ids)?;
+
+    for (group_id, action) in actions {
+        let modifier = match action {
+            LockAction::TurnOff => group::StateModifier::new().with_on(false),
+            LockAction::Scene(scene_id) => group::StateModifier::new().with_scene(scene_id.clone()),
+        };
+        bridge.set_group_state(group_id, &modifier)?;
+    }
+    Ok(snapshot)
+}
+
+/// Put the lights back exactly as they were before the lock.
+pub async fn restore(bridge: &Bridge, snapshot: &Snapshot) -> Result<(), huelib::Error> {
+    let mut attempts = 1;
+    loop {
+        match snapshot.restore(bridge, false, None) {
+            Ok(_) => return Ok(()),
+            Err(error) if attempts < RESTORE_ATTEMPTS => {
+                println!("Error restoring the lights after the lock, retrying: {}", error);
+                attempts += 1;
+                tokio::time::sleep(RESTORE_RETRY_DELAY).await;
+            }
+            Err(error) => return Err(error),
+        }
+    }
+}
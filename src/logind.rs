@@ -0,0 +1,548 @@
+// SPDX-License-Identifier: MIT
+
+//! Session lock, suspend and idle events from logind.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use cosmic::iced::futures::channel::mpsc::Sender;
+use futures_util::{SinkExt, Stream, StreamExt};
+use tokio::sync::Notify;
+use zbus::zvariant::{OwnedFd, OwnedObjectPath};
+use zbus::{Connection, proxy};
+
+use crate::idle_notify::IdleEvent;
+
+/// Longest wait for the lights to change before letting the computer suspend, logind doesn't
+/// wait longer by default.
+const SLEEP_DELAY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Interval between two checks of the idle state of the session.
+const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Environment variable selecting the bus of logind, `session` to use a fake logind on the
+/// session bus instead of the system one.
+const LOGIND_BUS_ENV: &str = "COSMIC_HUE_LOGIND_BUS";
+
+#[proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+trait Manager {
+    fn get_session(&self, session_id: &str) -> zbus::Result<OwnedObjectPath>;
+
+    fn inhibit(&self, what: &str, who: &str, why: &str, mode: &str) -> zbus::Result<OwnedFd>;
+
+    #[zbus(signal)]
+    fn prepare_for_sleep(&self, start: bool) -> zbus::Result<()>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.login1.Session",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1/session/auto"
+)]
+trait Session {
+    #[zbus(signal)]
+    fn lock(&self) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn unlock(&self) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn id(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn locked_hint(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn idle_hint(&self) -> zbus::Result<bool>;
+
+    /// When the session became idle, in microseconds since the epoch.
+    #[zbus(property)]
+    fn idle_since_hint(&self) -> zbus::Result<u64>;
+}
+
+/// A change of the session reported by logind.
+#[derive(Debug, Clone)]
+pub enum LogindEvent {
+    /// The session was locked or unlocked.
+    Locked(bool),
+    /// The computer is about to suspend, with the lock delaying it, or resumed.
+    Sleeping(bool, Option<SleepInhibitor>),
+}
+
+/// A delay lock on the suspend, to release once the lights have changed.
+#[derive(Debug, Clone)]
+pub struct SleepInhibitor {
+    fd: Arc<Mutex<Option<OwnedFd>>>,
+    released: Arc<Notify>,
+}
+
+impl SleepInhibitor {
+    fn new(fd: OwnedFd) -> Self {
+        SleepInhibitor {
+            fd: Arc::new(Mutex::new(Some(fd))),
+            released: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Let the computer suspend.
+    pub fn release(&self) {
+        if let Ok(mut fd) = self.fd.lock() {
+            drop(fd.take());
+        }
+        self.released.notify_one();
+    }
+
+    /// Wait for the release, releasing the lock anyway after a while.
+    async fn wait(&self, timeout: Duration) {
+        if tokio::time::timeout(timeout, self.released.notified())
+            .await
+            .is_err()
+        {
+            println!("The lights took too long to change, letting the computer suspend");
+        }
+        self.release();
+    }
+}
+
+/// Listen to the lock and suspend events of the current session.
+pub fn events() -> impl Stream<Item = LogindEvent> {
+    cosmic::iced::stream::channel(10, |mut output: Sender<LogindEvent>| async move {
+        let result = match connect().await {
+            Ok(connection) => listen(&connection, &mut output).await,
+            Err(error) => Err(error),
+        };
+        if let Err(error) = result {
+            println!("Error listening to logind: {}", error);
+        }
+        // Keep the subscription alive to avoid restarting it in a loop
+        std::future::pending::<()>().await;
+    })
+}
+
+/// Connect to the bus of logind.
+async fn connect() -> zbus::Result<Connection> {
+    match std::env::var(LOGIND_BUS_ENV).as_deref() {
+        Ok("session") => Connection::session().await,
+        _ => Connection::system().await,
+    }
+}
+
+/// Find the session of the applet.
+async fn current_session<'a>(
+    connection: &Connection,
+    manager: &ManagerProxy<'a>,
+) -> zbus::Result<SessionProxy<'a>> {
+    // The signals are emitted on the real path of the session, not on `auto`
+    let session_id = SessionProxy::new(connection).await?.id().await?;
+    SessionProxy::builder(connection)
+        .path(manager.get_session(&session_id).await?)?
+        .build()
+        .await
+}
+
+/// Report when the session has been idle for longer than `timeout`, and when it is used again.
+///
+/// The session is only considered idle once the desktop sets the idle hint of logind.
+pub async fn watch_idle(timeout: Duration, output: &mut Sender<IdleEvent>) -> zbus::Result<()> {
+    let connection = connect().await?;
+    let manager = ManagerProxy::new(&connection).await?;
+    let session = current_session(&connection, &manager).await?;
+
+    // The idle time is checked regularly, and right away when the user comes back
+    let mut idle_hint_changes = session.receive_idle_hint_changed().await;
+    let mut interval = tokio::time::interval(IDLE_POLL_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            Some(_) = idle_hint_changes.next() => {}
+        }
+        let idle = if session.idle_hint().await? {
+            let idle_since = UNIX_EPOCH + Duration::from_micros(session.idle_since_hint().await?);
+            SystemTime::now()
+                .duration_since(idle_since)
+                .unwrap_or_default()
+                >= timeout
+        } else {
+            false
+        };
+        let _ = output.send(IdleEvent::Idle(idle)).await;
+    }
+}
+
+async fn listen(connection: &Connection, output: &mut Sender<LogindEvent>) -> zbus::Result<()> {
+    let manager = ManagerProxy::new(connection).await?;
+    let session = current_session(connection, &manager).await?;
+
+    let mut sleep_signals = manager.receive_prepare_for_sleep().await?;
+    let mut lock_signals = session.receive_lock().await?;
+    let mut unlock_signals = session.receive_unlock().await?;
+    let mut locked_hint_changes = session.receive_locked_hint_changed().await;
+
+    // Delay the suspend to turn the lights off first
+    let mut inhibitor = inhibit(&manager).await;
+
+    loop {
+        let event = tokio::select! {
+            Some(signal) = sleep_signals.next() => match signal.args()?.start {
+                true => LogindEvent::Sleeping(true, inhibitor.take().map(SleepInhibitor::new)),
+                false => LogindEvent::Sleeping(false, None),
+            },
+            Some(_) = lock_signals.next() => LogindEvent::Locked(true),
+            Some(_) = unlock_signals.next() => LogindEvent::Locked(false),
+            Some(change) = locked_hint_changes.next() => LogindEvent::Locked(change.get().await?),
+            else => return Ok(()),
+        };
+        let _ = output.send(event.clone()).await;
+
+        match event {
+            // The applet releases the lock once the lights are off
+            LogindEvent::Sleeping(true, Some(sleep_inhibitor)) => {
+                sleep_inhibitor.wait(SLEEP_DELAY_TIMEOUT).await;
+            }
+            LogindEvent::Sleeping(false, _) => {
+                inhibitor = inhibit(&manager).await;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Take a delay inhibitor lock on the suspend, released once the lights are off.
+async fn inhibit(manager: &ManagerProxy<'_>) -> Option<OwnedFd> {
+    manager
+        .inhibit("sleep", "COSMIC Hue", "Turning the lights off", "delay")
+        .await
+        .map_err(|error| println!("Error inhibiting the suspend: {}", error))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, ErrorKind, Read};
+    use std::os::unix::net::UnixStream;
+    use std::process::{Child, Command, Stdio};
+
+    use cosmic::iced::futures::channel::mpsc::{self, Receiver};
+    use zbus::names::BusName;
+
+    const MANAGER_PATH: &str = "/org/freedesktop/login1";
+    const SESSION_PATH: &str = "/org/freedesktop/login1/session/c1";
+
+    /// A private bus, stopped when dropped.
+    struct PrivateBus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl PrivateBus {
+        /// Start a bus, `dbus-daemon` has to be installed.
+        fn start() -> Self {
+            let mut daemon = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .spawn()
+                .expect("dbus-daemon is needed to run the logind tests");
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take().unwrap())
+                .read_line(&mut address)
+                .unwrap();
+            PrivateBus {
+                daemon,
+                address: address.trim().to_string(),
+            }
+        }
+
+        async fn connect(&self) -> Connection {
+            zbus::connection::Builder::address(self.address.as_str())
+                .unwrap()
+                .build()
+                .await
+                .unwrap()
+        }
+    }
+
+    impl Drop for PrivateBus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+
+    /// The ends of the inhibitor locks kept by the fake logind, closed once released.
+    #[derive(Clone, Default)]
+    struct Inhibitors(Arc<Mutex<Vec<UnixStream>>>);
+
+    impl Inhibitors {
+        async fn wait_for_count(&self, count: usize) {
+            for _ in 0..50 {
+                if self.0.lock().unwrap().len() >= count {
+                    return;
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            panic!("no inhibitor lock taken");
+        }
+
+        /// Whether the applet closed its side of a lock.
+        fn is_released(&self, index: usize) -> bool {
+            let inhibitors = self.0.lock().unwrap();
+            let mut stream = &inhibitors[index];
+            stream.set_nonblocking(true).unwrap();
+            match stream.read(&mut [0; 1]) {
+                Ok(0) => true,
+                Err(error) if error.kind() == ErrorKind::WouldBlock => false,
+                result => panic!("unexpected read on the inhibitor lock: {result:?}"),
+            }
+        }
+
+        async fn wait_for_release(&self, index: usize, timeout: Duration) -> bool {
+            let start = tokio::time::Instant::now();
+            while start.elapsed() < timeout {
+                if self.is_released(index) {
+                    return true;
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            false
+        }
+    }
+
+    struct FakeManager {
+        inhibitors: Inhibitors,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.login1.Manager")]
+    impl FakeManager {
+        fn get_session(&self, _session_id: String) -> OwnedObjectPath {
+            OwnedObjectPath::try_from(SESSION_PATH).unwrap()
+        }
+
+        fn inhibit(
+            &self,
+            _what: String,
+            _who: String,
+            _why: String,
+            _mode: String,
+        ) -> zbus::fdo::Result<OwnedFd> {
+            let (kept, given) =
+                UnixStream::pair().map_err(|error| zbus::fdo::Error::Failed(error.to_string()))?;
+            self.inhibitors.0.lock().unwrap().push(kept);
+            Ok(std::os::fd::OwnedFd::from(given).into())
+        }
+    }
+
+    struct FakeSession;
+
+    #[zbus::interface(name = "org.freedesktop.login1.Session")]
+    impl FakeSession {
+        #[zbus(property)]
+        fn id(&self) -> String {
+            "c1".to_string()
+        }
+
+        #[zbus(property)]
+        fn locked_hint(&self) -> bool {
+            false
+        }
+
+        #[zbus(property)]
+        fn idle_hint(&self) -> bool {
+            false
+        }
+
+        #[zbus(property)]
+        fn idle_since_hint(&self) -> u64 {
+            0
+        }
+    }
+
+    /// Serve a fake logind on the bus.
+    async fn start_logind(bus: &PrivateBus) -> (Connection, Inhibitors) {
+        let inhibitors = Inhibitors::default();
+        let manager = FakeManager {
+            inhibitors: inhibitors.clone(),
+        };
+        let connection = zbus::connection::Builder::address(bus.address.as_str())
+            .unwrap()
+            .name("org.freedesktop.login1")
+            .unwrap()
+            .serve_at(MANAGER_PATH, manager)
+            .unwrap()
+            .serve_at("/org/freedesktop/login1/session/auto", FakeSession)
+            .unwrap()
+            .serve_at(SESSION_PATH, FakeSession)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        (connection, inhibitors)
+    }
+
+    fn spawn_listen(connection: Connection) -> Receiver<LogindEvent> {
+        let (mut sender, receiver) = mpsc::channel(10);
+        tokio::spawn(async move {
+            let _ = listen(&connection, &mut sender).await;
+        });
+        receiver
+    }
+
+    /// Skip the events until one matches.
+    async fn wait_for(
+        receiver: &mut Receiver<LogindEvent>,
+        expected: impl Fn(&LogindEvent) -> bool,
+    ) -> LogindEvent {
+        let next = async {
+            loop {
+                let event = receiver.next().await.expect("the listener stopped");
+                if expected(&event) {
+                    return event;
+                }
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(5), next)
+            .await
+            .expect("no matching event")
+    }
+
+    async fn emit(
+        logind: &Connection,
+        path: &str,
+        interface: &str,
+        signal: &str,
+        body: &(impl serde::Serialize + zbus::zvariant::DynamicType),
+    ) {
+        logind
+            .emit_signal(None::<BusName<'_>>, path, interface, signal, body)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn prepare_for_sleep_is_delayed_until_released() {
+        let bus = PrivateBus::start();
+        let (logind, inhibitors) = start_logind(&bus).await;
+        let mut events = spawn_listen(bus.connect().await);
+        inhibitors.wait_for_count(1).await;
+
+        emit(
+            &logind,
+            MANAGER_PATH,
+            "org.freedesktop.login1.Manager",
+            "PrepareForSleep",
+            &(true,),
+        )
+        .await;
+        let event = wait_for(&mut events, |event| {
+            matches!(event, LogindEvent::Sleeping(true, _))
+        })
+        .await;
+        let LogindEvent::Sleeping(true, Some(inhibitor)) = event else {
+            panic!("the suspend is not delayed");
+        };
+
+        // The suspend waits for the lights
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(!inhibitors.is_released(0));
+        inhibitor.release();
+        assert!(inhibitors.wait_for_release(0, Duration::from_secs(2)).await);
+
+        // A new lock is taken for the next suspend
+        emit(
+            &logind,
+            MANAGER_PATH,
+            "org.freedesktop.login1.Manager",
+            "PrepareForSleep",
+            &(false,),
+        )
+        .await;
+        wait_for(&mut events, |event| {
+            matches!(event, LogindEvent::Sleeping(false, None))
+        })
+        .await;
+        inhibitors.wait_for_count(2).await;
+        assert!(!inhibitors.is_released(1));
+    }
+
+    #[tokio::test]
+    async fn prepare_for_sleep_is_released_after_the_timeout() {
+        let bus = PrivateBus::start();
+        let (logind, inhibitors) = start_logind(&bus).await;
+        let mut events = spawn_listen(bus.connect().await);
+        inhibitors.wait_for_count(1).await;
+
+        emit(
+            &logind,
+            MANAGER_PATH,
+            "org.freedesktop.login1.Manager",
+            "PrepareForSleep",
+            &(true,),
+        )
+        .await;
+        // Keep the event, and its lock, without ever releasing it
+        let _event = wait_for(&mut events, |event| {
+            matches!(event, LogindEvent::Sleeping(true, Some(_)))
+        })
+        .await;
+
+        assert!(!inhibitors.is_released(0));
+        assert!(
+            inhibitors
+                .wait_for_release(0, SLEEP_DELAY_TIMEOUT + Duration::from_secs(2))
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn lock_and_unlock() {
+        let bus = PrivateBus::start();
+        let (logind, inhibitors) = start_logind(&bus).await;
+        let mut events = spawn_listen(bus.connect().await);
+        inhibitors.wait_for_count(1).await;
+        // The locked hint is reported first
+        wait_for(&mut events, |event| {
+            matches!(event, LogindEvent::Locked(false))
+        })
+        .await;
+
+        emit(
+            &logind,
+            SESSION_PATH,
+            "org.freedesktop.login1.Session",
+            "Lock",
+            &(),
+        )
+        .await;
+        wait_for(&mut events, |event| {
+            matches!(event, LogindEvent::Locked(true))
+        })
+        .await;
+        emit(
+            &logind,
+            SESSION_PATH,
+            "org.freedesktop.login1.Session",
+            "Unlock",
+            &(),
+        )
+        .await;
+        wait_for(&mut events, |event| {
+            matches!(event, LogindEvent::Locked(false))
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn missing_logind() {
+        let bus = PrivateBus::start();
+        let connection = bus.connect().await;
+        let (mut sender, _receiver) = mpsc::channel(10);
+        let result = tokio::time::timeout(Duration::from_secs(5), listen(&connection, &mut sender))
+            .await
+            .expect("the listener hangs without logind");
+        assert!(result.is_err());
+    }
+}
@@ -1,8 +1,24 @@
 // SPDX-License-Identifier: MIT
 
+mod adaptive;
 mod app;
-mod config;
+mod busy;
+mod calendar;
+mod focus;
 mod i18n;
+mod idle;
+mod idle_notify;
+mod lock;
+mod logind;
+mod notifications;
+mod panel_icon;
+mod schedule;
+mod service;
+mod snapshot;
+mod sun;
+mod wake_up;
+
+use cosmic_ext_applet_hue::{config, scene};
 
 fn main() -> cosmic::iced::Result {
     // Get the system's preferred languages.
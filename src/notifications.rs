@@ -0,0 +1,140 @@
+// SPDX-License-Identifier: MIT
+
+//! Alerting a light when a desktop notification is shown.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use cosmic::iced::futures::channel::mpsc::Sender;
+use futures_util::{SinkExt, Stream, StreamExt};
+use huelib::Bridge;
+use huelib::resource::Adjust;
+use huelib::resource::light::{self, Alert};
+use zbus::zvariant::OwnedValue;
+use zbus::{Connection, MessageStream};
+
+use crate::config::{NotificationAlert, Urgency};
+use crate::snapshot::Snapshot;
+
+/// Match rule of the notifications sent to the notification daemon.
+const NOTIFY_MATCH_RULE: &str =
+    "type='method_call',interface='org.freedesktop.Notifications',member='Notify'";
+
+/// Number of flashes of a light.
+const FLASH_COUNT: u32 = 2;
+
+/// Duration of a flash, and of the pause between two flashes.
+const FLASH_DURATION: Duration = Duration::from_millis(700);
+
+/// A notification shown on the desktop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub app_name: String,
+    pub summary: String,
+    pub urgency: Urgency,
+}
+
+/// The arguments of the `Notify` method of the notification daemon.
+type NotifyArgs = (
+    String,
+    u32,
+    String,
+    String,
+    String,
+    Vec<String>,
+    HashMap<String, OwnedValue>,
+    i32,
+);
+
+/// Listen to the notifications sent on the session bus.
+pub fn notifications() -> impl Stream<Item = Notification> {
+    cosmic::iced::stream::channel(10, |mut output: Sender<Notification>| async move {
+        if let Err(error) = monitor(&mut output).await {
+            println!("Error monitoring the notifications: {}", error);
+        }
+        // Keep the subscription alive to avoid restarting it in a loop
+        std::future::pending::<()>().await;
+    })
+}
+
+async fn monitor(output: &mut Sender<Notification>) -> zbus::Result<()> {
+    let connection = Connection::session().await?;
+    // The connection only receives the monitored messages from now on
+    connection
+        .call_method(
+            Some("org.freedesktop.DBus"),
+            "/org/freedesktop/DBus",
+            Some("org.freedesktop.DBus.Monitoring"),
+            "BecomeMonitor",
+            &(vec![NOTIFY_MATCH_RULE], 0u32),
+        )
+        .await?;
+
+    let mut messages = MessageStream::from(&connection);
+    while let Some(message) = messages.next().await {
+        let message = message?;
+        if !message
+            .header()
+            .member()
+            .is_some_and(|member| member.as_str() == "Notify")
+        {
+            continue;
+        }
+        let Ok((app_name, _, _, summary, _, _, hints, _)) =
+            message.body().deserialize::<NotifyArgs>()
+        else {
+            continue;
+        };
+        let urgency = match hints
+            .get("urgency")
+            .and_then(|urgency| urgency.downcast_ref::<u8>().ok())
+        {
+            Some(0) => Urgency::Low,
+            Some(2) => Urgency::Critical,
+            _ => Urgency::Normal,
+        };
+        let _ = output
+            .send(Notification {
+                app_name,
+                summary,
+                urgency,
+            })
+            .await;
+    }
+    Ok(())
+}
+
+/// Alert a light, putting it back as it was after a flash.
+pub async fn alert(
+    bridge: &Bridge,
+    light_id: &str,
+    alert: NotificationAlert,
+) -> Result<(), huelib::Error> {
+    match alert {
+        NotificationAlert::Breathe => {
+            let modifier = light::StateModifier::new().with_alert(Alert::Select);
+            bridge.set_light_state(light_id, &modifier)?;
+        }
+        NotificationAlert::Flash { hue, saturation } => {
+            let snapshot = Snapshot::capture(bridge, &[light_id.to_string()])?;
+            let flash = light::StateModifier::new()
+                .with_on(true)
+                .with_hue(Adjust::Override(hue))
+                .with_saturation(Adjust::Override(saturation))
+                .with_brightness(Adjust::Override(254))
+                .with_transition_time(0);
+            let off = light::StateModifier::new()
+                .with_on(false)
+                .with_transition_time(0);
+
+            for _ in 0..FLASH_COUNT {
+                bridge.set_light_state(light_id, &flash)?;
+                tokio::time::sleep(FLASH_DURATION).await;
+                bridge.set_light_state(light_id, &off)?;
+                tokio::time::sleep(FLASH_DURATION).await;
+            }
+            snapshot.restore(bridge, false, Some(0))?;
+        }
+    }
+    Ok(())
+}
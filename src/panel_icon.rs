@@ -0,0 +1,82 @@
+// SPDX-License-Identifier: MIT
+
+//! The lightbulb icon of the panel, the one of `resources/icon.svg` drawn from the state of the
+//! lights.
+
+/// Color of the badge shown when the bridge is unreachable.
+const BADGE_COLOR: &str = "#e01b24";
+
+/// What the panel icon shows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PanelIconState {
+    /// At least one light is on.
+    pub on: bool,
+    /// Color of the bulb while a light is on, as RGB from 0 to 1.
+    pub tint: Option<(f32, f32, f32)>,
+    /// The last request to the bridge failed.
+    pub unreachable: bool,
+    /// The icon recalls a scene, instead of showing a light or a group.
+    pub scene: bool,
+    /// Color of the outline, and of the bulb while on without a tint, from the panel theme.
+    pub foreground: (f32, f32, f32),
+}
+
+impl Default for PanelIconState {
+    fn default() -> Self {
+        Self {
+            on: false,
+            tint: None,
+            unreachable: false,
+            scene: false,
+            foreground: (1.0, 1.0, 1.0),
+        }
+    }
+}
+
+impl PanelIconState {
+    /// Draw the icon as an SVG document.
+    pub fn svg(&self) -> Vec<u8> {
+        let stroke = hex(self.foreground);
+        let fill = match (self.on, self.tint) {
+            (false, _) => "none".to_string(),
+            (true, Some(tint)) => hex(tint),
+            (true, None) => stroke.clone(),
+        };
+
+        let mut svg = format!(
+            r##"<svg width="128" height="128" viewBox="0 0 128 128" xmlns="http://www.w3.org/2000/svg">
+  <path d="M 64 20 C 45 20, 32 33, 32 50 C 32 60, 36 68, 42 74 L 42 86 L 86 86 L 86 74 C 92 68, 96 60, 96 50 C 96 33, 83 20, 64 20 Z"
+        fill="{fill}" stroke="{stroke}" stroke-width="6" stroke-linejoin="round" />
+  <line x1="42" y1="92" x2="86" y2="92" stroke="{stroke}" stroke-width="6" stroke-linecap="round" />
+  <line x1="42" y1="100" x2="86" y2="100" stroke="{stroke}" stroke-width="6" stroke-linecap="round" />
+  <path d="M 48 106 L 80 106" fill="none" stroke="{stroke}" stroke-width="6" stroke-linecap="round" />
+"##
+        );
+        if self.scene {
+            svg.push_str(&format!(
+                r##"  <polygon points="105,80 110,95 126,95 114,105 118,120 105,111 92,120 96,105 84,95 100,95" fill="{stroke}" />
+"##
+            ));
+        }
+        if self.unreachable {
+            svg.push_str(&format!(
+                r##"  <circle cx="102" cy="26" r="24" fill="{BADGE_COLOR}" />
+  <line x1="102" y1="14" x2="102" y2="29" stroke="#ffffff" stroke-width="7" stroke-linecap="round" />
+  <circle cx="102" cy="39" r="4" fill="#ffffff" />
+"##
+            ));
+        }
+        svg.push_str("</svg>\n");
+        svg.into_bytes()
+    }
+}
+
+/// Format a color as RGB from 0 to 1 for SVG.
+fn hex((r, g, b): (f32, f32, f32)) -> String {
+    format!("#{:02x}{:02x}{:02x}", channel(r), channel(g), channel(b))
+}
+
+/// Convert a color channel from 0 to 1 into a byte.
+fn channel(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
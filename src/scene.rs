@@ -0,0 +1,59 @@
+// SPDX-License-Identifier: MIT
+
+//! Recalling scenes with their recall options.
+
+use huelib::Bridge;
+use huelib::resource::{Adjust, group, light};
+use huelib::response::{Modified, Response};
+
+use crate::config::SceneRecallOptions;
+
+/// Recall a scene on a group, with its fade duration and brightness.
+pub fn recall(
+    bridge: &Bridge,
+    scene_id: &str,
+    group_id: &str,
+    options: SceneRecallOptions,
+) -> Result<Vec<Response<Modified>>, huelib::Error> {
+    // The transition time is expressed in multiples of 100ms
+    let transition_time = options.transition_secs.map(|secs| secs.saturating_mul(10));
+
+    let mut modifier = group::StateModifier::new().with_scene(scene_id.to_string());
+    if let Some(transition_time) = transition_time {
+        modifier = modifier.with_transition_time(transition_time);
+    }
+    let mut responses = bridge.set_group_state(group_id, &modifier)?;
+    if let Some(brightness_percent) = options.brightness_percent {
+        responses.extend(scale_scene_brightness(
+            bridge,
+            scene_id,
+            brightness_percent,
+            transition_time,
+        )?);
+    }
+    Ok(responses)
+}
+
+/// Scale the brightness stored in a scene for each of its lights.
+fn scale_scene_brightness(
+    bridge: &Bridge,
+    scene_id: &str,
+    brightness_percent: u8,
+    transition_time: Option<u16>,
+) -> Result<Vec<Response<Modified>>, huelib::Error> {
+    let scene = bridge.get_scene(scene_id)?;
+    let mut responses = Vec::new();
+    for (light_id, state) in scene.light_states.unwrap_or_default() {
+        if state.on == Some(false) {
+            continue;
+        }
+        let brightness = state.brightness.unwrap_or(254) as u32 * brightness_percent as u32 / 100;
+        let mut modifier = light::StateModifier::new()
+            .with_brightness(Adjust::Override(brightness.clamp(1, 254) as u8));
+        if let Some(transition_time) = transition_time {
+            modifier = modifier.with_transition_time(transition_time);
+        }
+        responses.extend(bridge.set_light_state(&light_id, &modifier)?);
+    }
+    Ok(responses)
+}
@@ -0,0 +1,220 @@
+// SPDX-License-Identifier: MIT
+
+//! Conversion between the schedules stored on the bridge and their editable form.
+
+use chrono::{NaiveDateTime, NaiveTime};
+use huelib::resource::schedule::Command;
+use huelib::resource::{group, light};
+
+/// Weekday flags of a recurring schedule, from Monday to Sunday, as used by the bridge.
+pub const WEEKDAYS: [u8; 7] = [64, 32, 16, 8, 4, 2, 1];
+
+/// Monday to Friday.
+pub const WORKING_DAYS: u8 = 124;
+
+/// Description of the timers created by the sleep timer of a light or group.
+pub const SLEEP_TIMER_DESCRIPTION: &str = "cosmic-hue sleep timer";
+
+/// When a schedule is triggered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleTime {
+    /// Every selected weekday at a time of day.
+    Recurring { weekdays: u8, time: NaiveTime },
+    /// Once at a date and time.
+    Alarm(NaiveDateTime),
+    /// Once after a duration, in seconds.
+    Timer(u32),
+}
+
+impl ScheduleTime {
+    /// Parse the `localtime` of a schedule, ignoring the randomized part.
+    ///
+    /// Returns `None` for the patterns which can't be edited, like recurring timers.
+    pub fn parse(local_time: &str) -> Option<Self> {
+        let local_time = local_time.split('A').next()?;
+
+        if let Some(recurring) = local_time.strip_prefix('W') {
+            let (weekdays, time) = recurring.split_once("/T")?;
+            return Some(ScheduleTime::Recurring {
+                weekdays: weekdays.parse().ok()?,
+                time: NaiveTime::parse_from_str(time, "%H:%M:%S").ok()?,
+            });
+        }
+
+        if let Some(duration) = local_time.strip_prefix("PT") {
+            return parse_duration(duration).map(ScheduleTime::Timer);
+        }
+
+        NaiveDateTime::parse_from_str(local_time, "%Y-%m-%dT%H:%M:%S")
+            .ok()
+            .map(ScheduleTime::Alarm)
+    }
+
+    /// Format the time as the `localtime` of a schedule.
+    pub fn to_local_time(&self) -> String {
+        match self {
+            ScheduleTime::Recurring { weekdays, time } => {
+                format!("W{}/T{}", weekdays, time.format("%H:%M:%S"))
+            }
+            ScheduleTime::Alarm(date_time) => date_time.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            ScheduleTime::Timer(secs) => format!(
+                "PT{:02}:{:02}:{:02}",
+                secs / 3600,
+                secs / 60 % 60,
+                secs % 60
+            ),
+        }
+    }
+}
+
+/// The randomized part of a `localtime`, like `A00:30:00`.
+pub fn randomization(local_time: &str) -> Option<&str> {
+    local_time.find('A').map(|index| &local_time[index..])
+}
+
+/// Parse a `hh:mm:ss` duration into seconds.
+fn parse_duration(duration: &str) -> Option<u32> {
+    let mut parts = duration.split(':').map(|part| part.parse::<u32>().ok());
+    let (hours, minutes, seconds) = (parts.next()??, parts.next()??, parts.next()??);
+    if parts.next().is_some() || minutes >= 60 || seconds >= 60 {
+        return None;
+    }
+    Some(hours * 3600 + minutes * 60 + seconds)
+}
+
+/// What a schedule does when it is triggered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScheduleTarget {
+    /// Turn a light on or off.
+    Light { id: String, on: bool },
+    /// Turn a group on or off.
+    Group { id: String, on: bool },
+    /// Recall a scene in a group.
+    Scene { group_id: String, scene_id: String },
+}
+
+impl ScheduleTarget {
+    /// Read the target of a schedule command.
+    ///
+    /// Returns `None` for the commands which were not created by this applet.
+    pub fn from_command(command: &Command) -> Option<Self> {
+        // The address looks like `/api/<username>/<resource>/<id>/<state>`
+        let mut parts = command.address.trim_start_matches('/').split('/').skip(2);
+        let (resource, id) = (parts.next()?, parts.next()?.to_string());
+
+        let on = command.body.get("on").and_then(|on| on.as_bool());
+        let scene_id = command.body.get("scene").and_then(|scene| scene.as_str());
+
+        match (resource, on, scene_id) {
+            ("lights", Some(on), None) => Some(ScheduleTarget::Light { id, on }),
+            ("groups", _, Some(scene_id)) => Some(ScheduleTarget::Scene {
+                group_id: id,
+                scene_id: scene_id.to_string(),
+            }),
+            ("groups", Some(on), None) => Some(ScheduleTarget::Group { id, on }),
+            _ => None,
+        }
+    }
+
+    /// Build the command sent by the bridge when the schedule is triggered.
+    pub fn to_command(&self, username: &str) -> Command {
+        match self {
+            ScheduleTarget::Light { id, on } => Command::from_modifier(
+                &light::StateModifier::new().with_on(*on),
+                id.clone(),
+                username,
+            ),
+            ScheduleTarget::Group { id, on } => Command::from_modifier(
+                &group::StateModifier::new().with_on(*on),
+                id.clone(),
+                username,
+            ),
+            ScheduleTarget::Scene { group_id, scene_id } => Command::from_modifier(
+                &group::StateModifier::new().with_scene(scene_id.clone()),
+                group_id.clone(),
+                username,
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_recurring() {
+        assert_eq!(
+            ScheduleTime::parse("W124/T07:30:00"),
+            Some(ScheduleTime::Recurring {
+                weekdays: WORKING_DAYS,
+                time: NaiveTime::from_hms_opt(7, 30, 0).unwrap(),
+            })
+        );
+    }
+
+    #[test]
+    fn parse_alarm() {
+        let date_time = NaiveDateTime::parse_from_str("2024-05-01T22:15:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+        assert_eq!(
+            ScheduleTime::parse("2024-05-01T22:15:00"),
+            Some(ScheduleTime::Alarm(date_time))
+        );
+    }
+
+    #[test]
+    fn parse_timer() {
+        assert_eq!(ScheduleTime::parse("PT01:30:15"), Some(ScheduleTime::Timer(5415)));
+        assert_eq!(ScheduleTime::parse("PT00:60:00"), None);
+        assert_eq!(ScheduleTime::parse("PT00:10"), None);
+    }
+
+    #[test]
+    fn parse_ignores_the_randomized_part() {
+        assert_eq!(
+            ScheduleTime::parse("W127/T06:00:00A00:30:00"),
+            Some(ScheduleTime::Recurring {
+                weekdays: 127,
+                time: NaiveTime::from_hms_opt(6, 0, 0).unwrap(),
+            })
+        );
+        assert_eq!(ScheduleTime::parse("PT00:05:00A00:01:00"), Some(ScheduleTime::Timer(300)));
+    }
+
+    #[test]
+    fn parse_unsupported_patterns() {
+        // Recurring timers and malformed times can't be edited
+        assert_eq!(ScheduleTime::parse("R/PT00:10:00"), None);
+        assert_eq!(ScheduleTime::parse("R05/PT00:10:00"), None);
+        assert_eq!(ScheduleTime::parse("W124/T25:00:00"), None);
+        assert_eq!(ScheduleTime::parse(""), None);
+    }
+
+    #[test]
+    fn format_round_trip() {
+        for local_time in ["W124/T07:30:00", "W1/T23:59:59", "2024-05-01T22:15:00", "PT00:05:00", "PT12:00:00"] {
+            assert_eq!(ScheduleTime::parse(local_time).unwrap().to_local_time(), local_time);
+        }
+    }
+
+    #[test]
+    fn randomized_part() {
+        assert_eq!(randomization("W127/T06:00:00A00:30:00"), Some("A00:30:00"));
+        assert_eq!(randomization("PT00:05:00A00:01:00"), Some("A00:01:00"));
+        assert_eq!(randomization("W127/T06:00:00"), None);
+        assert_eq!(randomization("2024-05-01T22:15:00"), None);
+    }
+
+    #[test]
+    fn target_round_trip() {
+        let targets = [
+            ScheduleTarget::Light { id: "3".to_string(), on: true },
+            ScheduleTarget::Group { id: "1".to_string(), on: false },
+            ScheduleTarget::Scene { group_id: "2".to_string(), scene_id: "AbC123".to_string() },
+        ];
+        for target in targets {
+            let command = target.to_command("username");
+            assert_eq!(ScheduleTarget::from_command(&command), Some(target));
+        }
+    }
+}
@@ -0,0 +1,374 @@
+// SPDX-License-Identifier: MIT
+
+//! D-Bus service to drive the lights from scripts, launchers and keyboard shortcuts, with the
+//! bridge paired by the applet.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use cosmic::iced::futures::channel::mpsc::Sender;
+use futures_util::{SinkExt, Stream};
+use huelib::Bridge;
+use huelib::resource::{Adjust, Group, Light, group, light};
+use tokio::sync::Notify;
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::{OwnedValue, Value};
+use zbus::{connection, fdo, interface};
+
+use crate::config;
+use crate::scene;
+
+/// The well-known name of the service on the session bus.
+pub const BUS_NAME: &str = "fr.clockworks.CosmicHue1";
+
+/// The path of the object implementing the service.
+pub const OBJECT_PATH: &str = "/fr/clockworks/CosmicHue1";
+
+/// A change of the service for the applet.
+#[derive(Debug, Clone)]
+pub enum ServiceChange {
+    /// The service is served, and signals the changes made elsewhere when asked.
+    Started(ServiceHandle),
+    /// Lights changed through the service, for the applet to refresh its state.
+    LightsChanged(Vec<String>),
+}
+
+/// Handle to the running service.
+#[derive(Debug, Clone)]
+pub struct ServiceHandle(Arc<Notify>);
+
+impl ServiceHandle {
+    /// Check the lights and signal their changes made elsewhere, on the refresh of the applet
+    /// instead of polling the bridge a second time.
+    pub fn signal_changes(&self) {
+        self.0.notify_one();
+    }
+}
+
+struct Service {
+    output: Sender<ServiceChange>,
+    known_states: Mutex<KnownStates>,
+}
+
+/// Last known state of the lights and groups, to signal their changes.
+#[derive(Default)]
+struct KnownStates {
+    /// State of the lights (on, brightness) by id.
+    lights: HashMap<String, (bool, u8)>,
+    /// State of the groups (any light on) by id.
+    groups: HashMap<String, bool>,
+}
+
+/// Serve the D-Bus interface of the applet on the session bus.
+pub fn serve() -> impl Stream<Item = ServiceChange> {
+    cosmic::iced::stream::channel(10, |output: Sender<ServiceChange>| async move {
+        if let Err(error) = run(output).await {
+            println!("Error serving {} on the session bus: {}", BUS_NAME, error);
+        }
+        // Keep the subscription alive to avoid restarting it in a loop
+        std::future::pending::<()>().await;
+    })
+}
+
+async fn run(mut output: Sender<ServiceChange>) -> zbus::Result<()> {
+    let service = Service {
+        output: output.clone(),
+        known_states: Mutex::default(),
+    };
+    let connection = connection::Builder::session()?
+        .name(BUS_NAME)?
+        .serve_at(OBJECT_PATH, service)?
+        .build()
+        .await?;
+    let service = connection
+        .object_server()
+        .interface::<_, Service>(OBJECT_PATH)
+        .await?;
+
+    // Signal the changes made by the applet or by the other clients of the bridge, when the
+    // applet refreshes its own state
+    let refresh = Arc::new(Notify::new());
+    let _ = output
+        .send(ServiceChange::Started(ServiceHandle(refresh.clone())))
+        .await;
+    loop {
+        service
+            .get()
+            .await
+            .signal_changes(service.signal_emitter())
+            .await;
+        refresh.notified().await;
+    }
+}
+
+#[interface(name = "fr.clockworks.CosmicHue1")]
+impl Service {
+    /// List the lights as (id, name, on, brightness).
+    async fn list_lights(&self) -> fdo::Result<Vec<(String, String, bool, u8)>> {
+        let lights = blocking(|| bridge()?.get_all_lights().map_err(failed)).await?;
+        Ok(lights
+            .into_iter()
+            .map(|light| {
+                (
+                    light.id,
+                    light.name,
+                    light.state.on.unwrap_or(false),
+                    light.state.brightness.unwrap_or(0),
+                )
+            })
+            .collect())
+    }
+
+    /// List the groups as (id, name, any light on).
+    async fn list_groups(&self) -> fdo::Result<Vec<(String, String, bool)>> {
+        let groups = blocking(|| bridge()?.get_all_groups().map_err(failed)).await?;
+        Ok(groups
+            .into_iter()
+            .map(|group| {
+                let on = group.state.is_some_and(|state| state.any_on);
+                (group.id, group.name, on)
+            })
+            .collect())
+    }
+
+    /// List the scenes as (id, name, group id), the group id being empty for light scenes.
+    async fn list_scenes(&self) -> fdo::Result<Vec<(String, String, String)>> {
+        let scenes = blocking(|| bridge()?.get_all_scenes().map_err(failed)).await?;
+        Ok(scenes
+            .into_iter()
+            .map(|scene| (scene.id, scene.name, scene.group.unwrap_or_default()))
+            .collect())
+    }
+
+    /// Change the state of a light.
+    ///
+    /// The supported properties are `on`, `brightness` (0 to 254), `color_temperature` (in
+    /// mireds), `hue` (0 to 65535), `saturation` (0 to 254) and `transition_time` (in multiples
+    /// of 100ms).
+    async fn set_light_state(
+        &self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+        light_id: &str,
+        state: HashMap<String, OwnedValue>,
+    ) -> fdo::Result<()> {
+        let mut modifier = light::StateModifier::new();
+        for (name, value) in &state {
+            modifier = match name.as_str() {
+                "on" => modifier.with_on(bool::try_from(&**value).map_err(|_| invalid(name))?),
+                "brightness" => {
+                    modifier.with_brightness(Adjust::Override(integer(value, name, 254)? as u8))
+                }
+                "color_temperature" => modifier.with_color_temperature(Adjust::Override(integer(
+                    value,
+                    name,
+                    u16::MAX.into(),
+                )?
+                    as u16)),
+                "hue" => modifier.with_hue(Adjust::Override(
+                    integer(value, name, u16::MAX.into())? as u16,
+                )),
+                "saturation" => {
+                    modifier.with_saturation(Adjust::Override(integer(value, name, 254)? as u8))
+                }
+                "transition_time" => {
+                    modifier.with_transition_time(integer(value, name, u16::MAX.into())? as u16)
+                }
+                _ => {
+                    return Err(fdo::Error::InvalidArgs(format!(
+                        "Unknown property: {}",
+                        name
+                    )));
+                }
+            };
+        }
+        let id = light_id.to_string();
+        blocking(move || bridge()?.set_light_state(&id, &modifier).map_err(failed)).await?;
+        self.changed(&emitter, vec![light_id.to_string()]).await;
+        Ok(())
+    }
+
+    /// Turn a group off if one of its lights is on, on otherwise.
+    ///
+    /// Returns whether the group is now on.
+    async fn toggle_group(
+        &self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+        group_id: &str,
+    ) -> fdo::Result<bool> {
+        let group_id = group_id.to_string();
+        let (light_ids, on) = blocking(move || {
+            let bridge = bridge()?;
+            let group = bridge.get_group(&group_id).map_err(failed)?;
+            let on = !group.state.is_some_and(|state| state.any_on);
+            bridge
+                .set_group_state(&group_id, &group::StateModifier::new().with_on(on))
+                .map_err(failed)?;
+            Ok((group.lights, on))
+        })
+        .await?;
+        self.changed(&emitter, light_ids).await;
+        Ok(on)
+    }
+
+    /// Recall a scene, with the fade duration and brightness chosen in the applet.
+    async fn activate_scene(
+        &self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+        scene_id: &str,
+    ) -> fdo::Result<()> {
+        let id = scene_id.to_string();
+        let light_ids = blocking(move || {
+            // The settings may have changed since the service started
+            let config = config::load();
+            let bridge = config::get_bridge(&config).ok_or_else(not_paired)?;
+            let scene = bridge.get_scene(&id).map_err(failed)?;
+            // Light scenes are not attached to a group, recall them on all the lights
+            let group_id = scene.group.clone().unwrap_or_else(|| "0".to_string());
+            scene::recall(
+                &bridge,
+                &id,
+                &group_id,
+                config.get_scene_recall_options(&id),
+            )
+            .map_err(failed)?;
+            Ok(scene
+                .light_states
+                .map(|states| states.into_keys().collect())
+                .unwrap_or_default())
+        })
+        .await?;
+        let _ = Self::scene_activated(&emitter, scene_id).await;
+        self.changed(&emitter, light_ids).await;
+        Ok(())
+    }
+
+    /// A light was turned on or off, or its brightness changed.
+    #[zbus(signal)]
+    async fn light_changed(
+        emitter: &SignalEmitter<'_>,
+        light_id: &str,
+        on: bool,
+        brightness: u8,
+    ) -> zbus::Result<()>;
+
+    /// A group was turned on or off.
+    #[zbus(signal)]
+    async fn group_changed(
+        emitter: &SignalEmitter<'_>,
+        group_id: &str,
+        on: bool,
+    ) -> zbus::Result<()>;
+
+    /// A scene was recalled through the service.
+    #[zbus(signal)]
+    async fn scene_activated(emitter: &SignalEmitter<'_>, scene_id: &str) -> zbus::Result<()>;
+}
+
+impl Service {
+    /// Tell the applet about lights changed through the service, and signal their new state.
+    async fn changed(&self, emitter: &SignalEmitter<'_>, light_ids: Vec<String>) {
+        let _ = self
+            .output
+            .clone()
+            .send(ServiceChange::LightsChanged(light_ids))
+            .await;
+        self.signal_changes(emitter).await;
+    }
+
+    /// Signal the lights and groups whose state changed since the last check.
+    async fn signal_changes(&self, emitter: &SignalEmitter<'_>) {
+        let Ok((lights, groups)) = blocking(|| {
+            let bridge = bridge()?;
+            Ok((bridge.get_all_lights().ok(), bridge.get_all_groups().ok()))
+        })
+        .await
+        else {
+            return;
+        };
+        // The lock is only taken to swap in the new states
+        let (changed_lights, changed_groups) = self
+            .known_states
+            .lock()
+            .unwrap()
+            .update(lights.unwrap_or_default(), groups.unwrap_or_default());
+
+        for (light_id, (on, brightness)) in changed_lights {
+            let _ = Self::light_changed(emitter, &light_id, on, brightness).await;
+        }
+        for (group_id, on) in changed_groups {
+            let _ = Self::group_changed(emitter, &group_id, on).await;
+        }
+    }
+}
+
+impl KnownStates {
+    /// Record the new states, and return those which changed.
+    fn update(
+        &mut self,
+        lights: Vec<Light>,
+        groups: Vec<Group>,
+    ) -> (Vec<(String, (bool, u8))>, Vec<(String, bool)>) {
+        // The first check only records the states
+        let first_check = self.lights.is_empty();
+
+        let mut changed_lights = Vec::new();
+        for light in lights {
+            let state = (
+                light.state.on.unwrap_or(false),
+                light.state.brightness.unwrap_or(0),
+            );
+            if self.lights.insert(light.id.clone(), state) != Some(state) && !first_check {
+                changed_lights.push((light.id, state));
+            }
+        }
+        let mut changed_groups = Vec::new();
+        for group in groups {
+            let on = group.state.is_some_and(|state| state.any_on);
+            if self.groups.insert(group.id.clone(), on) != Some(on) && !first_check {
+                changed_groups.push((group.id, on));
+            }
+        }
+        (changed_lights, changed_groups)
+    }
+}
+
+/// Run blocking calls to the bridge away from the tasks of the bus.
+async fn blocking<T: Send + 'static>(
+    call: impl FnOnce() -> fdo::Result<T> + Send + 'static,
+) -> fdo::Result<T> {
+    tokio::task::spawn_blocking(call)
+        .await
+        .map_err(|error| fdo::Error::Failed(error.to_string()))?
+}
+
+/// Connect to the bridge paired by the applet.
+fn bridge() -> fdo::Result<Bridge> {
+    config::get_bridge(&config::load()).ok_or_else(not_paired)
+}
+
+/// Read an integer property, whatever its D-Bus type, within `0..=max`.
+fn integer(value: &OwnedValue, name: &str, max: i64) -> fdo::Result<i64> {
+    let integer = match &**value {
+        Value::U8(value) => i64::from(*value),
+        Value::I16(value) => i64::from(*value),
+        Value::U16(value) => i64::from(*value),
+        Value::I32(value) => i64::from(*value),
+        Value::U32(value) => i64::from(*value),
+        Value::I64(value) => *value,
+        Value::U64(value) => i64::try_from(*value).unwrap_or(i64::MAX),
+        _ => return Err(invalid(name)),
+    };
+    Ok(integer.clamp(0, max))
+}
+
+fn invalid(name: &str) -> fdo::Error {
+    fdo::Error::InvalidArgs(format!("Invalid value for {}", name))
+}
+
+fn not_paired() -> fdo::Error {
+    fdo::Error::Failed("No bridge paired with the applet".to_string())
+}
+
+fn failed(error: huelib::Error) -> fdo::Error {
+    fdo::Error::Failed(error.to_string())
+}
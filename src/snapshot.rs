@@ -0,0 +1,143 @@
+// SPDX-License-Identifier: MIT
+
+//! Snapshots of the state of lights, to put them back as they were after changing them.
+
+use huelib::Bridge;
+use huelib::resource::light::{self, ColorMode};
+use huelib::resource::{Adjust, Light};
+use huelib::response::{Modified, Response};
+
+/// The color of a light, in the color mode it was set with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SnapshotColor {
+    /// Color temperature, in mireds.
+    ColorTemperature(u16),
+    /// Coordinates in the CIE color space.
+    ColorSpaceCoordinates(f32, f32),
+    HueAndSaturation(u16, u8),
+}
+
+/// The state of a light.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LightSnapshot {
+    pub id: String,
+    pub on: bool,
+    pub brightness: Option<u8>,
+    pub color: Option<SnapshotColor>,
+}
+
+/// The state of a set of lights.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Snapshot {
+    pub lights: Vec<LightSnapshot>,
+}
+
+impl LightSnapshot {
+    /// Read the state of a light.
+    pub fn from_light(light: &Light) -> Self {
+        let state = &light.state;
+        let color = match state.color_mode {
+            Some(ColorMode::ColorTemperature) => {
+                state.color_temperature.map(SnapshotColor::ColorTemperature)
+            }
+            Some(ColorMode::ColorSpaceCoordinates) => state
+                .color_space_coordinates
+                .map(|(x, y)| SnapshotColor::ColorSpaceCoordinates(x, y)),
+            Some(ColorMode::HueAndSaturation) => state
+                .hue
+                .zip(state.saturation)
+                .map(|(hue, saturation)| SnapshotColor::HueAndSaturation(hue, saturation)),
+            None => None,
+        };
+        LightSnapshot {
+            id: light.id.clone(),
+            on: state.on.unwrap_or(false),
+            brightness: state.brightness,
+            color,
+        }
+    }
+
+    /// Build the modifier putting the light back in this state.
+    pub fn modifier(&self, transition_time: Option<u16>) -> light::StateModifier {
+        let mut modifier = light::StateModifier::new().with_on(self.on);
+        // The bridge refuses to change the color of a light which is off
+        if !self.on {
+            return modifier;
+        }
+        if let Some(brightness) = self.brightness {
+            modifier = modifier.with_brightness(Adjust::Override(brightness));
+        }
+        modifier = match self.color {
+            Some(SnapshotColor::ColorTemperature(color_temperature)) => {
+                modifier.with_color_temperature(Adjust::Override(color_temperature))
+            }
+            Some(SnapshotColor::ColorSpaceCoordinates(x, y)) => {
+                modifier.with_color_space_coordinates(Adjust::Override((x, y)))
+            }
+            Some(SnapshotColor::HueAndSaturation(hue, saturation)) => modifier
+                .with_hue(Adjust::Override(hue))
+                .with_saturation(Adjust::Override(saturation)),
+            None => modifier,
+        };
+        if let Some(transition_time) = transition_time {
+            modifier = modifier.with_transition_time(transition_time);
+        }
+        modifier
+    }
+}
+
+impl Snapshot {
+    /// Capture the state of some lights of the bridge.
+    pub fn capture(bridge: &Bridge, light_ids: &[String]) -> Result<Self, huelib::Error> {
+        let lights = bridge
+            .get_all_lights()?
+            .iter()
+            .filter(|light| light_ids.contains(&light.id))
+            .map(LightSnapshot::from_light)
+            .collect();
+        Ok(Snapshot { lights })
+    }
+
+    /// Capture the state of the lights of some groups of the bridge.
+    pub fn capture_groups(bridge: &Bridge, group_ids: &[String]) -> Result<Self, huelib::Error> {
+        let mut light_ids: Vec<String> = bridge
+            .get_all_groups()?
+            .into_iter()
+            .filter(|group| group_ids.contains(&group.id))
+            .flat_map(|group| group.lights)
+            .collect();
+        light_ids.sort();
+        light_ids.dedup();
+        Self::capture(bridge, &light_ids)
+    }
+
+    /// Put the lights back in the captured state.
+    ///
+    /// With `skip_turned_off`, the lights turned off since the capture are left off.
+    pub fn restore(
+        &self,
+        bridge: &Bridge,
+        skip_turned_off: bool,
+        transition_time: Option<u16>,
+    ) -> Result<Vec<Response<Modified>>, huelib::Error> {
+        let turned_off: Vec<String> = if skip_turned_off {
+            bridge
+                .get_all_lights()?
+                .into_iter()
+                .filter(|light| light.state.on == Some(false))
+                .map(|light| light.id)
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let mut responses = Vec::new();
+        for light in &self.lights {
+            if turned_off.contains(&light.id) {
+                continue;
+            }
+            responses.extend(bridge.set_light_state(&light.id, &light.modifier(transition_time))?);
+        }
+        Ok(responses)
+    }
+}
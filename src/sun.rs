@@ -0,0 +1,172 @@
+// SPDX-License-Identifier: MIT
+
+//! Sunrise and sunset times computed locally from a position, with the sunrise equation.
+//!
+//! The results are accurate to about a minute, which is enough to trigger automations.
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+
+/// Julian day of 2000-01-01 at noon.
+const J2000: f64 = 2451545.0;
+
+/// Julian day of the Unix epoch.
+const UNIX_EPOCH_JULIAN_DAY: f64 = 2440587.5;
+
+/// Elevation of the center of the sun at sunrise and sunset, accounting for the refraction
+/// and the apparent radius of the sun, in degrees.
+const SUNRISE_ELEVATION: f64 = -0.833;
+
+/// Obliquity of the ecliptic, in degrees.
+const EARTH_OBLIQUITY: f64 = 23.4397;
+
+/// Sunrise and sunset of a day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SunTimes {
+    pub sunrise: DateTime<Utc>,
+    pub sunset: DateTime<Utc>,
+}
+
+/// Compute the sunrise and sunset of a day at a position, in degrees with the east and the
+/// north being positive.
+///
+/// Returns `None` during the polar day and the polar night.
+pub fn sun_times(date: NaiveDate, latitude: f64, longitude: f64) -> Option<SunTimes> {
+    let epoch = NaiveDate::from_ymd_opt(2000, 1, 1)?;
+    let days = date.signed_duration_since(epoch).num_days() as f64;
+
+    // Mean solar time at the longitude
+    let mean_solar_time = days + 0.0008 - longitude / 360.0;
+    let mean_anomaly = (357.5291 + 0.98560028 * mean_solar_time).rem_euclid(360.0);
+    let mean_anomaly_rad = mean_anomaly.to_radians();
+    let center = 1.9148 * mean_anomaly_rad.sin()
+        + 0.0200 * (2.0 * mean_anomaly_rad).sin()
+        + 0.0003 * (3.0 * mean_anomaly_rad).sin();
+    let ecliptic_longitude = (mean_anomaly + center + 180.0 + 102.9372).rem_euclid(360.0);
+    let ecliptic_longitude_rad = ecliptic_longitude.to_radians();
+
+    let solar_transit = J2000 + mean_solar_time + 0.0053 * mean_anomaly_rad.sin()
+        - 0.0069 * (2.0 * ecliptic_longitude_rad).sin();
+
+    let declination_sin = ecliptic_longitude_rad.sin() * EARTH_OBLIQUITY.to_radians().sin();
+    let declination_cos = declination_sin.asin().cos();
+    let latitude_rad = latitude.to_radians();
+    let hour_angle_cos = (SUNRISE_ELEVATION.to_radians().sin() - latitude_rad.sin() * declination_sin)
+        / (latitude_rad.cos() * declination_cos);
+    if !(-1.0..=1.0).contains(&hour_angle_cos) {
+        return None;
+    }
+    let hour_angle = hour_angle_cos.acos().to_degrees();
+
+    Some(SunTimes {
+        sunrise: julian_day_to_utc(solar_transit - hour_angle / 360.0)?,
+        sunset: julian_day_to_utc(solar_transit + hour_angle / 360.0)?,
+    })
+}
+
+/// Whether a time picked from the sunrise and sunset of each day fell in `since..=now`.
+///
+/// The days on both sides of the interval are checked too, as an offset can move the time
+/// picked for a day before or after its midnight.
+pub fn triggered_between<Tz: TimeZone>(
+    latitude: f64,
+    longitude: f64,
+    since: &DateTime<Tz>,
+    now: &DateTime<Tz>,
+    trigger: impl Fn(SunTimes) -> DateTime<Utc>,
+) -> bool {
+    let first = since.date_naive().pred_opt();
+    let last = now.date_naive().succ_opt();
+    let (Some(first), Some(last)) = (first, last) else {
+        return false;
+    };
+    first
+        .iter_days()
+        .take_while(|date| *date <= last)
+        .filter_map(|date| sun_times(date, latitude, longitude))
+        .map(trigger)
+        .any(|time| *since < time && time <= *now)
+}
+
+/// Convert a Julian day to a date and time.
+fn julian_day_to_utc(julian_day: f64) -> Option<DateTime<Utc>> {
+    let secs = ((julian_day - UNIX_EPOCH_JULIAN_DAY) * 86400.0).round() as i64;
+    DateTime::from_timestamp(secs, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check a computed time against a published one, within a few minutes.
+    fn assert_near(time: DateTime<Utc>, expected: &str) {
+        let expected = DateTime::parse_from_rfc3339(expected).unwrap().with_timezone(&Utc);
+        let difference = (time - expected).num_seconds().abs();
+        assert!(difference <= 180, "{time} is {difference}s away from {expected}");
+    }
+
+    #[test]
+    fn paris_summer_solstice() {
+        let date = NaiveDate::from_ymd_opt(2024, 6, 21).unwrap();
+        let times = sun_times(date, 48.8566, 2.3522).unwrap();
+        // 05:47 and 21:58 in Paris summer time
+        assert_near(times.sunrise, "2024-06-21T03:47:00Z");
+        assert_near(times.sunset, "2024-06-21T19:58:00Z");
+    }
+
+    #[test]
+    fn new_york_winter_solstice() {
+        let date = NaiveDate::from_ymd_opt(2024, 12, 21).unwrap();
+        let times = sun_times(date, 40.7128, -74.006).unwrap();
+        // 07:16 and 16:32 in New York standard time
+        assert_near(times.sunrise, "2024-12-21T12:16:00Z");
+        assert_near(times.sunset, "2024-12-21T21:32:00Z");
+    }
+
+    #[test]
+    fn equinox_at_the_equator() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 20).unwrap();
+        let times = sun_times(date, 0.0, 0.0).unwrap();
+        assert_near(times.sunrise, "2024-03-20T06:05:00Z");
+        assert_near(times.sunset, "2024-03-20T18:12:00Z");
+    }
+
+    /// The sunrise of a day moved by an offset, in minutes.
+    fn sunrise_with_offset(minutes: i64) -> impl Fn(SunTimes) -> DateTime<Utc> {
+        move |times| times.sunrise + chrono::Duration::minutes(minutes)
+    }
+
+    fn utc(time: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn triggered_between_two_checks() {
+        let since = utc("2024-06-21T03:35:00Z");
+        let now = utc("2024-06-21T04:00:00Z");
+        assert!(triggered_between(48.8566, 2.3522, &since, &now, sunrise_with_offset(0)));
+        assert!(!triggered_between(48.8566, 2.3522, &since, &now, sunrise_with_offset(30)));
+    }
+
+    #[test]
+    fn offsets_moving_the_trigger_past_midnight() {
+        // The sunrise of June 21 in Paris at 03:47, four hours earlier falls on June 20
+        let since = utc("2024-06-20T23:35:00Z");
+        let now = utc("2024-06-21T00:00:00Z");
+        assert!(triggered_between(48.8566, 2.3522, &since, &now, sunrise_with_offset(-240)));
+
+        // The sunset of June 21 at 19:58, five hours later falls on June 22
+        let since = utc("2024-06-22T00:45:00Z");
+        let now = utc("2024-06-22T01:10:00Z");
+        let sunset_later = |times: SunTimes| times.sunset + chrono::Duration::minutes(300);
+        assert!(triggered_between(48.8566, 2.3522, &since, &now, sunset_later));
+    }
+
+    #[test]
+    fn polar_day_and_night() {
+        // Tromsø has the midnight sun in June and the polar night in December
+        let summer = NaiveDate::from_ymd_opt(2024, 6, 21).unwrap();
+        let winter = NaiveDate::from_ymd_opt(2024, 12, 21).unwrap();
+        assert_eq!(sun_times(summer, 69.6496, 18.956), None);
+        assert_eq!(sun_times(winter, 69.6496, 18.956), None);
+    }
+}
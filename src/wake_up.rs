@@ -0,0 +1,180 @@
+// SPDX-License-Identifier: MIT
+
+//! Wake-up alarms, stored on the bridge as a pair of schedules: the first one turns the
+//! group on with a dim and warm light, the second one fades it to a bright daylight.
+
+use chrono::NaiveTime;
+use huelib::resource::schedule::{Command, Creator, Schedule, Status};
+use huelib::resource::{Adjust, group};
+
+use crate::schedule::ScheduleTime;
+
+/// Prefix of the description of the schedules of a wake-up alarm.
+const WAKE_UP_DESCRIPTION: &str = "cosmic-hue wake-up";
+
+/// Color temperature of the light when the alarm starts, in mireds (about 2200K).
+const START_COLOR_TEMPERATURE: u16 = 454;
+
+/// Color temperature of the light at the end of the fade, in mireds (about 5500K).
+const DAYLIGHT_COLOR_TEMPERATURE: u16 = 182;
+
+/// Delay between turning the group on and starting the fade.
+const FADE_DELAY_SECS: i64 = 10;
+
+/// Longest fade supported by the bridge, in minutes.
+pub const MAX_DURATION_MINUTES: u16 = 100;
+
+/// A wake-up alarm of a group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WakeUp {
+    /// Identifier shared by the schedules of the alarm.
+    pub id: String,
+    pub name: String,
+    pub group_id: String,
+    pub weekdays: u8,
+    pub time: NaiveTime,
+    /// Duration of the fade to daylight.
+    pub duration_minutes: u16,
+    pub enabled: bool,
+    /// The schedules of the bridge making the alarm.
+    pub schedule_ids: Vec<String>,
+}
+
+impl WakeUp {
+    /// Whether a schedule belongs to a wake-up alarm.
+    pub fn is_wake_up_schedule(schedule: &Schedule) -> bool {
+        schedule.description.starts_with(WAKE_UP_DESCRIPTION)
+    }
+
+    /// Gather the wake-up alarms from the schedules of the bridge.
+    pub fn from_schedules(schedules: &[Schedule]) -> Vec<WakeUp> {
+        let mut wake_ups: Vec<WakeUp> = Vec::new();
+
+        for schedule in schedules {
+            // The description looks like `<prefix> <id> start|fade`
+            let Some(description) = schedule.description.strip_prefix(WAKE_UP_DESCRIPTION) else {
+                continue;
+            };
+            let Some((id, role)) = description.trim().split_once(' ') else {
+                continue;
+            };
+            let Some(ScheduleTime::Recurring { weekdays, time }) =
+                ScheduleTime::parse(&schedule.local_time)
+            else {
+                continue;
+            };
+
+            let index = match wake_ups.iter().position(|wake_up| wake_up.id == id) {
+                Some(index) => index,
+                None => {
+                    wake_ups.push(WakeUp {
+                        id: id.to_string(),
+                        name: schedule.name.clone(),
+                        group_id: group_id(&schedule.command).unwrap_or_default(),
+                        weekdays,
+                        time,
+                        duration_minutes: 30,
+                        enabled: true,
+                        schedule_ids: Vec::new(),
+                    });
+                    wake_ups.len() - 1
+                }
+            };
+            let wake_up = &mut wake_ups[index];
+
+            wake_up.schedule_ids.push(schedule.id.clone());
+            wake_up.enabled &= schedule.status == Status::Enabled;
+            match role {
+                "start" => {
+                    wake_up.weekdays = weekdays;
+                    wake_up.time = time;
+                }
+                "fade" => {
+                    // The transition time is expressed in multiples of 100ms
+                    if let Some(transition_time) = schedule
+                        .command
+                        .body
+                        .get("transitiontime")
+                        .and_then(|transition_time| transition_time.as_u64())
+                    {
+                        wake_up.duration_minutes = (transition_time / 600) as u16;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        wake_ups.sort_by(|a, b| a.time.cmp(&b.time));
+        wake_ups
+    }
+
+    /// Build the schedules making the alarm.
+    pub fn creators(&self, username: &str) -> [Creator; 2] {
+        let start = group::StateModifier::new()
+            .with_on(true)
+            .with_brightness(Adjust::Override(1))
+            .with_color_temperature(Adjust::Override(START_COLOR_TEMPERATURE))
+            .with_transition_time(0);
+        let fade = group::StateModifier::new()
+            .with_brightness(Adjust::Override(254))
+            .with_color_temperature(Adjust::Override(DAYLIGHT_COLOR_TEMPERATURE))
+            .with_transition_time(self.duration_minutes.min(MAX_DURATION_MINUTES) * 600);
+
+        // Starting the fade can move the alarm to the next day
+        let fade_time = self.time + chrono::Duration::seconds(FADE_DELAY_SECS);
+        let fade_weekdays = if fade_time < self.time {
+            next_weekdays(self.weekdays)
+        } else {
+            self.weekdays
+        };
+
+        let creator = |modifier: &group::StateModifier, time: ScheduleTime, role: &str| {
+            Creator::new(
+                Command::from_modifier(modifier, self.group_id.clone(), username),
+                time.to_local_time(),
+            )
+            .with_name(self.name.clone())
+            .with_description(format!("{} {} {}", WAKE_UP_DESCRIPTION, self.id, role))
+            .with_status(if self.enabled {
+                Status::Enabled
+            } else {
+                Status::Disabled
+            })
+        };
+
+        [
+            creator(
+                &start,
+                ScheduleTime::Recurring {
+                    weekdays: self.weekdays,
+                    time: self.time,
+                },
+                "start",
+            ),
+            creator(
+                &fade,
+                ScheduleTime::Recurring {
+                    weekdays: fade_weekdays,
+                    time: fade_time,
+                },
+                "fade",
+            ),
+        ]
+    }
+}
+
+/// Read the group targeted by a schedule command.
+fn group_id(command: &Command) -> Option<String> {
+    // The address looks like `/api/<username>/groups/<id>/action`
+    let mut parts = command.address.trim_start_matches('/').split('/').skip(2);
+    match (parts.next(), parts.next()) {
+        (Some("groups"), Some(id)) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+/// Shift the weekday flags of a recurring schedule by one day.
+fn next_weekdays(weekdays: u8) -> u8 {
+    // Monday is the highest bit and Sunday the lowest one
+    (weekdays >> 1) | ((weekdays & 1) << 6)
+}